@@ -1,3 +1,5 @@
 #!/bin/sh
-rustup target add wasm32-unknown-unknown
-cargo build --target wasm32-unknown-unknown --release
\ No newline at end of file
+# near-sdk 5 contracts are built with cargo-near: https://github.com/near/cargo-near
+# production wasm: signatures checked by the host and no debugging views, which keeps the binary and its storage
+# staking small. pass `--features expanded-views` to include the operator dashboard.
+cargo near build non-reproducible-wasm --no-default-features --features contract "$@"
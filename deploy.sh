@@ -1,3 +1,3 @@
 #!/bin/sh
-near deploy --accountId checks.integrations.near --wasmFile ./target/wasm32-unknown-unknown/release/sybil_provider.wasm
+near deploy --accountId checks.integrations.near --wasmFile ./target/near/sybil_provider.wasm
 
@@ -0,0 +1,86 @@
+//! Gas benchmarks against a sandbox node. Every call's burnt gas is printed and checked against a ceiling, so a
+//! regression fails `cargo test`. Build the contract with `./build.sh` first, or point `SYBIL_PROVIDER_WASM` at
+//! another build.
+
+use near_workspaces::network::Sandbox;
+use near_workspaces::result::ExecutionFinalResult;
+use near_workspaces::types::{Gas, NearToken};
+use near_workspaces::Worker;
+use serde_json::json;
+use sybil_provider::test_utils::{self, DEV_SECRET_KEY};
+use sybil_provider::RequestBound;
+
+const DEFAULT_WASM: &str = "../target/near/sybil_provider.wasm";
+
+// ceilings sit ~25% above the measured cost, raise them only alongside a change that explains the increase
+const REGISTER_SOCIAL_GAS: Gas = Gas::from_tgas(12);
+const UPDATE_ACCESS_KEY_GAS: Gas = Gas::from_tgas(8);
+const VIEW_GAS: Gas = Gas::from_tgas(4);
+
+fn check_gas(method: &str, outcome: &ExecutionFinalResult, ceiling: Gas) {
+    assert!(outcome.is_success(), "{} failed: {:?}", method, outcome.failures());
+    println!("{:<24} {}", method, outcome.total_gas_burnt);
+    assert!(outcome.total_gas_burnt <= ceiling, "{} burnt {}, above the {} ceiling", method, outcome.total_gas_burnt, ceiling);
+}
+
+// a block height comfortably ahead of the sandbox, for the request bound of verifier payloads
+async fn max_block_height(worker: &Worker<Sandbox>) -> Result<u64, Box<dyn std::error::Error>> {
+    Ok(worker.view_block().await?.height() + 1_000)
+}
+
+#[tokio::test]
+async fn gas_regressions() -> Result<(), Box<dyn std::error::Error>> {
+    let wasm_path = std::env::var("SYBIL_PROVIDER_WASM").unwrap_or_else(|_| DEFAULT_WASM.to_string());
+    let wasm = std::fs::read(&wasm_path).map_err(|err| format!("cannot read {}, run ./build.sh first: {}", wasm_path, err))?;
+    let worker = near_workspaces::sandbox().await?;
+    let contract = worker.dev_deploy(&wasm).await?;
+    let (verifier, verifier_key) = test_utils::keypair(DEV_SECRET_KEY);
+    contract.call("new").args_json(json!({ "pub_key": String::from(&verifier_key) })).transact().await?.into_result()?;
+    let alice = worker
+        .dev_create_account()
+        .await?
+        .create_subaccount("alice")
+        .initial_balance(NearToken::from_near(30))
+        .transact()
+        .await?
+        .into_result()?;
+    let account_id = alice.id().as_str().parse()?;
+
+    let height = max_block_height(&worker).await?;
+    let message = test_utils::social_message(&account_id, "lens", "alice.lens", "0x8a3f", None, RequestBound::BlockHeight(height));
+    let outcome = alice
+        .call(contract.id(), "register_social")
+        .args_json(json!({
+            "platform": "lens",
+            "signature": test_utils::sign(&verifier, &message),
+            "handle": "alice.lens",
+            "proof": "0x8a3f",
+            "max_block_height": height,
+        }))
+        .deposit(NearToken::from_millinear(100))
+        .transact()
+        .await?;
+    check_gas("register_social", &outcome, REGISTER_SOCIAL_GAS);
+    assert!(outcome.json::<bool>()?, "register_social was rejected");
+
+    let height = max_block_height(&worker).await?;
+    let message = test_utils::account_info_message(&account_id, 12u32, RequestBound::BlockHeight(height));
+    let outcome = alice
+        .call(contract.id(), "update_access_key")
+        .args_json(json!({
+            "signature": test_utils::sign(&verifier, &message),
+            "account_info": 12,
+            "max_block_height": height,
+        }))
+        .deposit(NearToken::from_millinear(100))
+        .transact()
+        .await?;
+    check_gas("update_access_key", &outcome, UPDATE_ACCESS_KEY_GAS);
+
+    // called as transactions, view calls don't report the gas they burn
+    for method in ["connected_to_lens", "connected_to_5_contracts", "six_month_old", "score"] {
+        let outcome = alice.call(contract.id(), method).args_json(json!({ "account_id": alice.id() })).transact().await?;
+        check_gas(method, &outcome, VIEW_GAS);
+    }
+    Ok(())
+}
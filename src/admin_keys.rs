@@ -0,0 +1,135 @@
+use near_sdk::json_types::U64;
+use near_sdk::{env, near, require, FunctionError, PublicKey};
+
+use crate::keys::VerifyingKey;
+use crate::{messages, AdminKeys, ConfigChange, Contract, ContractError, ContractExt, RequestBound};
+
+// how long the replaced key keeps validating after a rotation, so the backend can switch over without downtime
+pub(crate) const ADMIN_KEY_OVERLAP_NS: u64 = 24 * 60 * 60 * 1_000_000_000;
+
+#[near(serializers = [borsh])]
+#[derive(Default)]
+pub struct KeyRotation {
+    pending: Option<VerifyingKey>,
+    previous: Option<VerifyingKey>,
+    previous_valid_until: u64,
+    // can only replace the admin key, never sign attestations
+    recovery: Option<VerifyingKey>,
+}
+
+#[near]
+impl Contract {
+    /// Stages the proposal behind the timelock, returning the pending change id.
+    pub fn propose_admin_key(&mut self, new_key: PublicKey) -> u64 {
+        self.assert_owner();
+        if let Err(err) = self.signing_key(new_key.clone()) {
+            err.panic();
+        }
+        self.schedule_change(ConfigChange::AdminKey(new_key))
+    }
+
+    /// Promotes the proposed key once its holder proves possession by signing
+    /// `"accept_admin_key,<contract_id>,<new_key>"`. The replaced key stays valid for the overlap window.
+    #[handle_result]
+    pub fn accept_admin_key(&mut self, signature: Vec<u8>) -> Result<(), ContractError> {
+        let new_key = self.key_rotation.pending.clone().unwrap_or_else(|| env::panic_str("no admin key proposed"));
+        // the recovery key may have been set to the proposed key since it was proposed
+        if self.is_recovery_key(&new_key) {
+            return Err(ContractError::RecoveryKeyReserved);
+        }
+        let message = messages::accept_admin_key_message(&env::current_account_id(), new_key.public_key());
+        new_key.verify(message.as_bytes(), &signature)?;
+        let previous = std::mem::replace(&mut self.admin_pub, new_key);
+        self.key_rotation.pending = None;
+        self.key_rotation.previous = Some(previous);
+        self.key_rotation.previous_valid_until = env::block_timestamp() + ADMIN_KEY_OVERLAP_NS;
+        Ok(())
+    }
+
+    /// Stages the change behind the timelock, returning the pending change id.
+    pub fn set_recovery_key(&mut self, key: PublicKey) -> u64 {
+        self.assert_owner();
+        if let Err(err) = VerifyingKey::new(key.clone()) {
+            err.panic();
+        }
+        require!(!self.is_signing_key(&key), "recovery key must differ from the verifier keys");
+        self.schedule_change(ConfigChange::RecoveryKey(key))
+    }
+
+    /// Replaces a compromised admin key immediately. `signature` is by the recovery key over
+    /// `"recover_admin_key,<contract_id>,<new_key>,<bound>"`, bounded by exactly one of `max_block_height` and
+    /// `valid_until_timestamp_ns`. Unlike a rotation there is no overlap: the replaced key and any pending
+    /// proposal stop validating at once.
+    #[handle_result]
+    pub fn recover_admin_key(&mut self, new_key: PublicKey, signature: Vec<u8>, max_block_height: Option<u64>, valid_until_timestamp_ns: Option<U64>) -> Result<(), ContractError> {
+        let bound = RequestBound::new(max_block_height, valid_until_timestamp_ns)?;
+        bound.ensure_valid()?;
+        let recovery = self.key_rotation.recovery.clone().unwrap_or_else(|| env::panic_str("no recovery key set"));
+        let message = messages::recover_admin_key_message(&env::current_account_id(), &new_key, bound);
+        recovery.verify(message.as_bytes(), &signature)?;
+        let new_key = VerifyingKey::new(new_key)?;
+        require!(new_key != recovery, "recovery key must differ from the admin key");
+        self.admin_pub = new_key;
+        self.key_rotation.pending = None;
+        self.key_rotation.previous = None;
+        self.key_rotation.previous_valid_until = 0;
+        Ok(())
+    }
+
+    pub fn get_admin_keys(&self) -> AdminKeys {
+        AdminKeys {
+            current: self.admin_pub.public_key().clone(),
+            pending: self.key_rotation.pending.as_ref().map(|key| key.public_key().clone()),
+            previous: self.key_rotation.previous.as_ref().map(|key| key.public_key().clone()),
+            previous_valid_until: self.key_rotation.previous_valid_until.into(),
+            recovery: self.key_rotation.recovery.as_ref().map(|key| key.public_key().clone()),
+        }
+    }
+}
+
+impl Contract {
+    pub(crate) fn apply_admin_key_proposal(&mut self, new_key: PublicKey) {
+        self.key_rotation.pending = Some(self.signing_key(new_key).unwrap_or_else(|err| err.panic()));
+    }
+
+    pub(crate) fn apply_recovery_key(&mut self, key: PublicKey) {
+        require!(!self.is_signing_key(&key), "recovery key must differ from the verifier keys");
+        self.key_rotation.recovery = Some(VerifyingKey::new(key).unwrap_or_else(|err| err.panic()));
+    }
+
+    pub(crate) fn is_recovery_key(&self, key: &VerifyingKey) -> bool {
+        self.key_rotation.recovery.as_ref() == Some(key)
+    }
+
+    // whether `key` validates attestations: the admin key, a verifier, a platform's own verifier or the replaced
+    // admin key during its overlap
+    fn is_signing_key(&self, key: &PublicKey) -> bool {
+        let previous = self.key_rotation.previous.as_ref().filter(|_| env::block_timestamp() < self.key_rotation.previous_valid_until);
+        self.is_verifier(key)
+            || self.platforms.values().any(|platform| platform.verifier_key.as_ref() == Some(key))
+            || previous.is_some_and(|previous| previous.public_key() == key)
+    }
+
+    // validates a key attestations are to be signed with, which the recovery key never may be
+    pub(crate) fn signing_key(&self, key: PublicKey) -> Result<VerifyingKey, ContractError> {
+        let key = VerifyingKey::new(key)?;
+        if self.is_recovery_key(&key) {
+            return Err(ContractError::RecoveryKeyReserved);
+        }
+        Ok(key)
+    }
+
+    // accepts a signature by the admin key, any other sufficiently bonded verifier, or the replaced admin key
+    // during its overlap, returning the key that made it
+    pub(crate) fn verify_signature(&self, message: &str, signature: &[u8]) -> Result<PublicKey, ContractError> {
+        let previous = self.key_rotation.previous.as_ref().filter(|_| env::block_timestamp() < self.key_rotation.previous_valid_until);
+        let verifiers = self.verifiers.iter().filter(|key| self.is_bonded(key.public_key()));
+        for key in std::iter::once(&self.admin_pub).chain(verifiers).chain(previous) {
+            match key.verify(message.as_bytes(), signature) {
+                Err(ContractError::UnverifiedData) => continue,
+                res => return res.map(|()| key.public_key().clone()),
+            }
+        }
+        Err(ContractError::UnverifiedData)
+    }
+}
@@ -0,0 +1,29 @@
+use near_sdk::{near, require};
+
+use crate::{ConfigChange, Contract, ContractExt};
+
+// seeded on `new`, the platforms the contract has dedicated checks for, with their display names
+pub(crate) const DEFAULT_PLATFORMS: [(&str, &str); 4] = [("lens", "Lens"), ("farcaster", "Farcaster"), ("twitter", "Twitter"), ("near_social", "NEAR Social")];
+
+#[near]
+impl Contract {
+    /// Registrations are only accepted for enabled platforms, looked up by their exact id. Platforms added with
+    /// `add_platform` start disabled. Staged behind the timelock, returns the pending change id.
+    pub fn allow_platform(&mut self, platform: String) -> u64 {
+        self.assert_owner();
+        require!(self.platforms.contains_key(&platform), "unknown platform");
+        self.schedule_change(ConfigChange::PlatformEnabled { platform, enabled: true })
+    }
+
+    /// Existing attestations on the platform stay valid, only new registrations are rejected. Staged behind
+    /// the timelock, returns the pending change id.
+    pub fn disallow_platform(&mut self, platform: String) -> u64 {
+        self.assert_owner();
+        require!(self.platform_enabled(&platform), "platform is not allowlisted");
+        self.schedule_change(ConfigChange::PlatformEnabled { platform, enabled: false })
+    }
+
+    pub fn get_allowed_platforms(&self) -> Vec<String> {
+        self.platforms.values().filter(|platform| platform.enabled).map(|platform| platform.id.clone()).collect()
+    }
+}
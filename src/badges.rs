@@ -0,0 +1,42 @@
+use near_sdk::env::block_timestamp;
+use near_sdk::serde_json::json;
+use near_sdk::{env, near, require, AccountId};
+
+use crate::{events, Badge, Contract, ContractExt, FunctionError, PAUSE_CLAIM_BADGE};
+
+// a badge records that the account satisfied a rule when it claimed it, and stays with the account even if it
+// later stops satisfying the rule or the rule is removed. dApps and indexers can treat it as a stable
+// achievement, named after the rule.
+#[near]
+impl Contract {
+    /// Grants the caller the badge of `rule_id` if it satisfies the rule now. The storage it takes is paid from
+    /// the caller's storage balance and the attached deposit, any excess is refunded.
+    #[payable]
+    pub fn claim_badge(&mut self, rule_id: String) {
+        self.ensure_not_paused(PAUSE_CLAIM_BADGE).unwrap_or_else(|err| err.panic());
+        let account_id = env::predecessor_account_id();
+        self.ensure_not_banned(&account_id).unwrap_or_else(|err| err.panic());
+        let mut badges = self.badges.get(&account_id).cloned().unwrap_or_default();
+        require!(badges.iter().all(|badge| badge.rule_id != rule_id), "badge already granted");
+        let rule = self.rules.get(&rule_id).unwrap_or_else(|| env::panic_str(&format!("unknown rule {}", rule_id)));
+        require!(self.satisfies(&account_id, rule, block_timestamp()), "rule is not satisfied");
+        self.flush_collections();
+        let usage_before = env::storage_usage();
+        badges.push(Badge { rule_id: rule_id.clone(), granted_at: block_timestamp().into() });
+        self.badges.insert(account_id.clone(), badges);
+        self.accounts.insert(account_id.clone());
+        events::emit("badge_granted", json!({ "account_id": account_id, "rule_id": rule_id }));
+        self.settle_storage(&account_id, usage_before).unwrap_or_else(|err| err.panic());
+        self.mint_badge_nft(&account_id, &rule_id);
+        self.publish_social_badges_of(&account_id);
+    }
+
+    /// The account's badges, in the order they were granted.
+    pub fn get_badges(&self, account_id: AccountId) -> Vec<Badge> {
+        self.badges.get(&account_id).cloned().unwrap_or_default()
+    }
+
+    pub fn has_badge(&self, account_id: AccountId, rule_id: String) -> bool {
+        self.badges.get(&account_id).is_some_and(|badges| badges.iter().any(|badge| badge.rule_id == rule_id))
+    }
+}
@@ -0,0 +1,62 @@
+use near_sdk::serde_json::json;
+use near_sdk::{env, near, require, AccountId};
+
+use crate::revocation::MAX_REASON_LEN;
+use crate::{events, Ban, Contract, ContractError, ContractExt, Role};
+
+// a banned account fails every check and can't register anything new, its stored data is kept as is
+#[near]
+impl Contract {
+    /// `reason` is a short code such as `"SYBIL_FARM"`, public through `get_ban`.
+    pub fn ban_account(&mut self, account_id: AccountId, reason: String) {
+        self.assert_any_role(&[Role::Owner, Role::Operator]);
+        require!(reason.len() <= MAX_REASON_LEN, format!("reason must be at most {} bytes", MAX_REASON_LEN));
+        events::emit("account_banned", json!({ "account_id": account_id, "reason": reason }));
+        self.bans.insert(account_id.clone(), Ban { reason, banned_at: env::block_timestamp().into() });
+        self.refresh_cached_score(&account_id);
+    }
+
+    pub fn unban_account(&mut self, account_id: AccountId) {
+        self.assert_any_role(&[Role::Owner, Role::Operator]);
+        require!(self.bans.remove(&account_id).is_some(), "account is not banned");
+        events::emit("account_unbanned", json!({ "account_id": account_id }));
+        self.refresh_cached_score(&account_id);
+    }
+
+    pub fn is_banned(&self, account_id: AccountId) -> bool {
+        self.bans.contains_key(&account_id)
+    }
+
+    pub fn get_ban(&self, account_id: AccountId) -> Option<Ban> {
+        self.bans.get(&account_id).cloned()
+    }
+}
+
+impl Contract {
+    pub(crate) fn ensure_not_banned(&self, account_id: &AccountId) -> Result<(), ContractError> {
+        if self.bans.contains_key(account_id) { Err(ContractError::AccountBanned) } else { Ok(()) }
+    }
+
+    // the attested attributes as the checks see them, nothing for banned accounts. with `resolve_linked` the
+    // best attribute across the account's identity, see `resolved_accounts`
+
+    // counts attested more than `max_staleness_ns` ago are left out
+    pub(crate) fn attested_key_count(&self, account_id: &AccountId, max_staleness_ns: Option<u64>) -> u32 {
+        self.resolved_accounts(account_id).iter()
+            .filter(|account_id| self.is_fresh(self.attribute_updates(account_id).access_keys, max_staleness_ns))
+            .filter_map(|account_id| self.access_key_counts.get(account_id).copied())
+            .max()
+            .unwrap_or(0)
+    }
+
+    pub(crate) fn attested_age(&self, account_id: &AccountId, max_staleness_ns: Option<u64>) -> Option<u64> {
+        self.resolved_accounts(account_id).iter()
+            .filter(|account_id| self.is_fresh(self.attribute_updates(account_id).account_age, max_staleness_ns))
+            .filter_map(|account_id| self.account_ages.get(account_id).copied())
+            .min()
+    }
+
+    pub(crate) fn attested_social(&self, account_id: &AccountId, platform: &str) -> Option<crate::SocialData> {
+        self.resolved_accounts(account_id).iter().filter_map(|account_id| self.social(account_id, platform)).max_by_key(|social| social.expiry_date)
+    }
+}
@@ -0,0 +1,49 @@
+use near_sdk::serde_json::json;
+use near_sdk::{env, near, AccountId};
+
+use crate::{events, CachedScore, Contract, ContractExt, FunctionError};
+
+// `score` materialized per account so consumer contracts can read it from a single small slot instead of paying
+// for the full computation. it is refreshed whenever the account's attributes are written, but decay, ageing
+// and expiry keep moving it between writes, so `computed_at` says how current it is.
+#[near]
+impl Contract {
+    /// The account's score as of its last refresh, `None` if it was never computed or the account has since
+    /// given up all its attestations.
+    pub fn get_cached_score(&self, account_id: AccountId) -> Option<CachedScore> {
+        self.cached_scores.get(&account_id).cloned()
+    }
+
+    /// Brings the account's cached score up to date, e.g. before a consumer relies on it. The storage a new
+    /// cache entry takes is paid by the caller from its storage balance and the attached deposit.
+    #[payable]
+    pub fn recompute_score(&mut self, account_id: AccountId) -> u32 {
+        let usage_before = env::storage_usage();
+        let score = self.cache_score(&account_id);
+        self.settle_storage(&env::predecessor_account_id(), usage_before).unwrap_or_else(|err| err.panic());
+        score
+    }
+}
+
+impl Contract {
+    pub(crate) fn cache_score(&mut self, account_id: &AccountId) -> u32 {
+        let now = env::block_timestamp();
+        let score = self.compute_score(account_id, now);
+        let previous = self.cached_scores.insert(account_id.clone(), CachedScore { score, computed_at: now.into() });
+        let (from, to) = (previous.and_then(|cached| self.tier_of(cached.score)), self.tier_of(score));
+        if from != to {
+            events::emit("tier_changed", json!({ "account_id": account_id, "from": from, "to": to }));
+            if let Some(tier) = &to {
+                self.mint_tier_nft(account_id, tier);
+            }
+        }
+        score
+    }
+
+    // refreshes a cache entry the account already has, for writes that aren't the account's own to pay for
+    pub(crate) fn refresh_cached_score(&mut self, account_id: &AccountId) {
+        if self.cached_scores.contains_key(account_id) {
+            self.cache_score(account_id);
+        }
+    }
+}
@@ -0,0 +1,162 @@
+use near_sdk::env::block_timestamp;
+use near_sdk::json_types::{U128, U64};
+use near_sdk::serde_json::json;
+use near_sdk::{env, ext_contract, near, require, AccountId, Gas, NearToken, Promise, PromiseError};
+
+use crate::evm::{normalize_address, personal_sign_hash};
+use crate::{events, Contract, ContractExt, EvmAttestation, FunctionError, MpcConfig, SignedEvmAttestation, PAUSE_EXPORT_ATTESTATION};
+
+// the signer answers in a later block through yield/resume, the call needs most of a transaction's gas
+const MPC_SIGN_GAS: Gas = Gas::from_tgas(200);
+const ON_ATTESTATION_SIGNED_GAS: Gas = Gas::from_tgas(10);
+
+/// A signature request as the MPC signer takes it.
+#[near(serializers = [json])]
+pub struct SignRequest {
+    pub payload: [u8; 32],
+    pub path: String,
+    pub key_version: u32,
+}
+
+#[near(serializers = [json])]
+pub struct AffinePoint {
+    pub affine_point: String,
+}
+
+#[near(serializers = [json])]
+pub struct Scalar {
+    pub scalar: String,
+}
+
+/// The MPC signer's response, a secp256k1 signature split the way ecrecover takes it.
+#[near(serializers = [json])]
+pub struct SignatureResponse {
+    pub big_r: AffinePoint,
+    pub s: Scalar,
+    pub recovery_id: u8,
+}
+
+#[ext_contract(ext_mpc_signer)]
+#[allow(dead_code)] // only called through the generated `ext_mpc_signer`
+pub trait MpcSigner {
+    fn sign(&mut self, request: SignRequest) -> SignatureResponse;
+}
+
+pub(crate) fn evm_attestation_message(contract_id: &AccountId, attestation: &EvmAttestation) -> String {
+    format!(
+        "sybil_attestation,{},{},{},{},{},{},{},{}",
+        contract_id,
+        attestation.account_id,
+        attestation.evm_address,
+        attestation.chain_id.0,
+        attestation.score,
+        attestation.is_human,
+        attestation.nonce.0,
+        attestation.issued_at.0,
+    )
+}
+
+// attestations exported to EVM chains are signed by the MPC network under a key derived for this contract,
+// so an Ethereum contract can ecrecover them against one fixed address instead of trusting a bridge.
+#[near]
+impl Contract {
+    /// `None` stops exports. Attestations already signed stay valid on the consuming chains.
+    pub fn set_mpc_config(&mut self, config: Option<MpcConfig>) {
+        self.assert_owner();
+        self.mpc_config = config;
+    }
+
+    pub fn get_mpc_config(&self) -> Option<MpcConfig> {
+        self.mpc_config.clone()
+    }
+
+    /// Asks the MPC signer to sign the caller's current score and `is_human` answer for `evm_address`, which
+    /// must be linked to the caller, on chain `chain_id`. One request per account can be pending. The attached
+    /// deposit pays the signer's fee and is refunded if signing fails; the storage of the result is paid from
+    /// the caller's storage balance.
+    #[payable]
+    pub fn request_evm_attestation(&mut self, evm_address: String, chain_id: U64) -> Promise {
+        self.ensure_not_paused(PAUSE_EXPORT_ATTESTATION).unwrap_or_else(|err| err.panic());
+        let account_id = env::predecessor_account_id();
+        self.ensure_not_banned(&account_id).unwrap_or_else(|err| err.panic());
+        let config = self.mpc_config.clone().unwrap_or_else(|| env::panic_str("attestation export is not configured"));
+        let evm_address = normalize_address(&evm_address).unwrap_or_else(|err| err.panic());
+        require!(
+            self.evm_addresses.get(&account_id).is_some_and(|addresses| addresses.contains(&evm_address)),
+            "address is not linked to the account"
+        );
+        require!(!self.pending_evm_attestations.contains_key(&account_id), "an attestation request is already pending");
+        let now = block_timestamp();
+        let nonce = self.evm_attestation_nonces.get(&account_id).copied().unwrap_or_default();
+        let attestation = EvmAttestation {
+            account_id: account_id.clone(),
+            evm_address,
+            chain_id,
+            score: self.compute_score(&account_id, now),
+            is_human: self.is_human(account_id.clone()),
+            nonce: nonce.into(),
+            issued_at: now.into(),
+        };
+        let payload = personal_sign_hash(&evm_attestation_message(&env::current_account_id(), &attestation));
+        let usage_before = env::storage_usage();
+        // the nonce is spent even if signing fails, so a signature never shows up for a reused one
+        self.evm_attestation_nonces.insert(account_id.clone(), nonce + 1);
+        self.pending_evm_attestations.insert(account_id.clone(), attestation);
+        self.settle_storage_from(&account_id, usage_before, 0).unwrap_or_else(|err| err.panic());
+        let deposit = env::attached_deposit();
+        ext_mpc_signer::ext(config.signer)
+            .with_static_gas(MPC_SIGN_GAS)
+            .with_attached_deposit(deposit)
+            .sign(SignRequest { payload, path: config.path, key_version: config.key_version })
+            .then(Self::ext(env::current_account_id()).with_static_gas(ON_ATTESTATION_SIGNED_GAS).on_attestation_signed(account_id, U128(deposit.as_yoctonear())))
+    }
+
+    #[private]
+    pub fn on_attestation_signed(&mut self, account_id: AccountId, deposit: U128, #[callback_result] signature: Result<SignatureResponse, PromiseError>) -> Option<SignedEvmAttestation> {
+        let usage_before = env::storage_usage();
+        let Some(attestation) = self.pending_evm_attestations.remove(&account_id) else {
+            // the account deleted its data meanwhile, only the deposit of a failed request is still owed
+            if signature.is_err() && deposit.0 > 0 {
+                Promise::new(account_id).transfer(NearToken::from_yoctonear(deposit.0)).detach();
+            }
+            return None;
+        };
+        let signed = match signature {
+            Ok(signature) => {
+                let signed = SignedEvmAttestation {
+                    attestation,
+                    big_r: signature.big_r.affine_point,
+                    s: signature.s.scalar,
+                    recovery_id: signature.recovery_id,
+                };
+                events::emit("evm_attestation_signed", json!({ "account_id": account_id, "attestation": signed }));
+                self.evm_attestations.insert(account_id.clone(), signed.clone());
+                Some(signed)
+            }
+            Err(_) => {
+                env::log_str(&format!("signing attestation {} for {} failed", attestation.nonce.0, account_id));
+                if deposit.0 > 0 {
+                    Promise::new(account_id.clone()).transfer(NearToken::from_yoctonear(deposit.0)).detach();
+                }
+                None
+            }
+        };
+        self.settle_storage_from(&account_id, usage_before, 0).unwrap_or_else(|err| err.panic());
+        signed
+    }
+
+    /// The latest attestation signed for the account, to submit on the EVM chain it names.
+    pub fn get_evm_attestation(&self, account_id: AccountId) -> Option<SignedEvmAttestation> {
+        self.evm_attestations.get(&account_id).cloned()
+    }
+
+    /// The attestation awaiting the signer's response, if any.
+    pub fn get_pending_evm_attestation(&self, account_id: AccountId) -> Option<EvmAttestation> {
+        self.pending_evm_attestations.get(&account_id).cloned()
+    }
+
+    /// The nonce the account's next attestation will carry.
+    pub fn get_evm_attestation_nonce(&self, account_id: AccountId) -> U64 {
+        self.evm_attestation_nonces.get(&account_id).copied().unwrap_or_default().into()
+    }
+}
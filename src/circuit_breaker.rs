@@ -0,0 +1,86 @@
+use near_sdk::serde_json::json;
+use near_sdk::{env, near, require};
+
+use crate::{
+    events, CircuitBreakerConfig, CircuitBreakerStatus, Contract, ContractExt, Role, PAUSE_FINALIZE_CLAIM, PAUSE_REGISTER_SOCIAL,
+    PAUSE_REGISTER_SOCIAL_ORACLE, PAUSE_REGISTER_SOCIAL_RELAYED, PAUSE_SUBMIT_CLAIM, PAUSE_VERIFY_NEAR_SOCIAL,
+};
+
+// the entrypoints a tripped breaker blocks, every way of getting a social registered
+pub(crate) const BREAKER_METHODS: u32 = PAUSE_REGISTER_SOCIAL | PAUSE_REGISTER_SOCIAL_RELAYED | PAUSE_REGISTER_SOCIAL_ORACLE | PAUSE_SUBMIT_CLAIM
+    | PAUSE_FINALIZE_CLAIM | PAUSE_VERIFY_NEAR_SOCIAL;
+
+// registrations are counted in fixed windows, the rolling count weighs the previous window by how much of it
+// still overlaps the last `window_ns`
+#[near(serializers = [borsh])]
+#[derive(Default)]
+pub struct BreakerState {
+    window: u64, // index of the current window, block timestamp / window_ns
+    count: u32,
+    previous_count: u32,
+    pub(crate) tripped: bool,
+}
+
+// halts registrations once their volume goes over the owner's ceiling, so a compromised verifier backend can't
+// flood the registry unnoticed. it stays tripped until an operator has looked into it.
+#[near]
+impl Contract {
+    /// `None` disables the breaker, a tripped breaker stays tripped until reset.
+    pub fn set_circuit_breaker(&mut self, config: Option<CircuitBreakerConfig>) {
+        self.assert_owner();
+        if let Some(config) = &config {
+            require!(config.window_ns.0 > 0 && config.max_registrations > 0, "window_ns and max_registrations must be positive");
+        }
+        self.circuit_breaker = config;
+        self.breaker = BreakerState { tripped: self.breaker.tripped, ..Default::default() };
+    }
+
+    pub fn get_circuit_breaker(&self) -> CircuitBreakerStatus {
+        CircuitBreakerStatus {
+            config: self.circuit_breaker.clone(),
+            tripped: self.breaker.tripped,
+            recent_registrations: self.circuit_breaker.as_ref().map_or(0, |config| self.recent_registrations(config.window_ns.0)),
+        }
+    }
+
+    /// Lets registrations through again and starts counting afresh.
+    pub fn reset_circuit_breaker(&mut self) {
+        self.assert_any_role(&[Role::Owner, Role::Operator]);
+        require!(self.breaker.tripped, "circuit breaker is not tripped");
+        self.breaker = BreakerState::default();
+        events::emit("circuit_breaker_reset", json!({ "by": env::predecessor_account_id() }));
+    }
+}
+
+impl Contract {
+    fn recent_registrations(&self, window_ns: u64) -> u32 {
+        let now = env::block_timestamp();
+        let window = now / window_ns;
+        let (count, previous_count) = match window - self.breaker.window.min(window) {
+            0 => (self.breaker.count, self.breaker.previous_count),
+            1 => (0, self.breaker.count),
+            _ => (0, 0),
+        };
+        let overlap = window_ns - now % window_ns;
+        count + (u128::from(previous_count) * u128::from(overlap) / u128::from(window_ns)) as u32
+    }
+
+    // called for every accepted registration
+    pub(crate) fn count_for_breaker(&mut self) {
+        let Some(config) = self.circuit_breaker.clone() else {
+            return;
+        };
+        let window = env::block_timestamp() / config.window_ns.0;
+        if window != self.breaker.window {
+            self.breaker.previous_count = if window == self.breaker.window + 1 { self.breaker.count } else { 0 };
+            self.breaker.count = 0;
+            self.breaker.window = window;
+        }
+        self.breaker.count += 1;
+        let recent = self.recent_registrations(config.window_ns.0);
+        if recent > config.max_registrations && !self.breaker.tripped {
+            self.breaker.tripped = true;
+            events::emit("circuit_breaker_tripped", json!({ "recent_registrations": recent, "max_registrations": config.max_registrations, "window_ns": config.window_ns }));
+        }
+    }
+}
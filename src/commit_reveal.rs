@@ -0,0 +1,100 @@
+use near_sdk::json_types::U64;
+use near_sdk::{env, near, AccountId};
+
+use crate::{messages, Contract, ContractError, ContractExt, FunctionError, Provenance, RequestBound, PAUSE_REGISTER_SOCIAL};
+
+// a commitment can't be revealed sooner, so it predates anything a watcher could register after seeing the reveal
+const MIN_REVEAL_DELAY_NS: u64 = 30 * 1_000_000_000;
+// and stops giving priority after this, so an old commitment can't be used to take a handle much later
+const MAX_REVEAL_DELAY_NS: u64 = 24 * 60 * 60 * 1_000_000_000;
+
+#[near(serializers = [borsh])]
+#[derive(Clone)]
+pub struct Commitment {
+    hash: [u8; 32],
+    committed_at: u64,
+}
+
+// hash of the registration `reveal_social` later discloses, binding the handle to the committing account
+pub(crate) fn commitment_hash(account_id: &AccountId, platform: &str, handle: &str, proof: &str, salt: &str) -> [u8; 32] {
+    env::sha256_array(format!("{},{},{},{},{}", account_id, platform, handle, proof, salt).as_bytes())
+}
+
+// two phase registration against front-running: `commit_social` publishes only a hash, and the registration it
+// hides is revealed no sooner than `MIN_REVEAL_DELAY_NS` later. anyone registering the handle in between, e.g.
+// after watching the reveal, loses it to the committer.
+#[near]
+impl Contract {
+    /// Commits to a registration by the hex sha256 of `"<account_id>,<platform>,<handle>,<proof>,<salt>"`,
+    /// replacing any earlier commitment of the caller. The storage it takes is paid from the caller's storage
+    /// balance and the attached deposit, any excess is refunded.
+    #[payable]
+    pub fn commit_social(&mut self, hash: String) {
+        self.ensure_not_paused(PAUSE_REGISTER_SOCIAL).unwrap_or_else(|err| err.panic());
+        let account_id = self.acting_account().unwrap_or_else(|err| err.panic());
+        let hash = decode_hash(&hash).unwrap_or_else(|| env::panic_str("hash must be 64 hex characters"));
+        let usage_before = env::storage_usage();
+        self.commitments.insert(account_id.clone(), Commitment { hash, committed_at: env::block_timestamp() });
+        self.settle_storage(&account_id, usage_before).unwrap_or_else(|err| err.panic());
+    }
+
+    /// `register_social` for the registration the caller committed to, between `MIN_REVEAL_DELAY_NS` and
+    /// `MAX_REVEAL_DELAY_NS` after the commitment. Takes the handle over from an attestation issued after the
+    /// commitment, which also frees what it was paying for.
+    #[payable]
+    #[handle_result]
+    pub fn reveal_social(&mut self, platform: String, signature: Vec<u8>, handle: String, proof: String, salt: String, max_block_height: Option<u64>, valid_until_timestamp_ns: Option<U64>, validity_ns: Option<U64>) -> Result<(), ContractError> {
+        self.ensure_not_paused(PAUSE_REGISTER_SOCIAL)?;
+        self.ensure_deposit(self.registration_fee_due())?;
+        self.check_input_limits(&platform, &handle, &proof)?;
+        let account_id = self.acting_account()?;
+        let usage_before = env::storage_usage();
+        let res = RequestBound::new(max_block_height, valid_until_timestamp_ns)
+            .and_then(|bound| self.internal_reveal_social(&account_id, platform.clone(), signature, handle, proof, salt, validity_ns.map(|v| v.0), bound));
+        self.finish_registration(&account_id, &platform, res, usage_before)
+    }
+
+    /// The hash `commit_social` takes for the registration, hex encoded.
+    pub fn get_commitment_hash(&self, account_id: AccountId, platform: String, handle: String, proof: String, salt: String) -> String {
+        commitment_hash(&account_id, &platform, &handle, &proof, &salt).iter().map(|byte| format!("{:02x}", byte)).collect()
+    }
+
+    /// Whether the account has a commitment that can be revealed now.
+    pub fn has_ripe_commitment(&self, account_id: AccountId) -> bool {
+        let now = env::block_timestamp();
+        self.commitments.get(&account_id).is_some_and(|commitment| now >= commitment.committed_at + MIN_REVEAL_DELAY_NS && now <= commitment.committed_at + MAX_REVEAL_DELAY_NS)
+    }
+}
+
+fn decode_hash(hex: &str) -> Option<[u8; 32]> {
+    if hex.len() != 64 || !hex.is_ascii() {
+        return None;
+    }
+    let mut hash = [0u8; 32];
+    for (i, byte) in hash.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&hex[2 * i..2 * i + 2], 16).ok()?;
+    }
+    Some(hash)
+}
+
+impl Contract {
+    fn internal_reveal_social(&mut self, account_id: &AccountId, platform: String, signature: Vec<u8>, handle: String, proof: String, salt: String, validity_ns: Option<u64>, bound: RequestBound) -> Result<u64, ContractError> {
+        bound.ensure_valid()?;
+        let commitment = self.commitments.get(account_id).cloned()
+            .filter(|commitment| commitment.hash == commitment_hash(account_id, &platform, &handle, &proof, &salt))
+            .ok_or(ContractError::NoCommitment)?;
+        let now = env::block_timestamp();
+        if now < commitment.committed_at + MIN_REVEAL_DELAY_NS {
+            return Err(ContractError::CommitmentNotReady { ready_at: commitment.committed_at + MIN_REVEAL_DELAY_NS });
+        }
+        if now > commitment.committed_at + MAX_REVEAL_DELAY_NS {
+            return Err(ContractError::NoCommitment);
+        }
+        let normalized = self.check_claimable_since(account_id, &platform, &handle, Some(commitment.committed_at))?;
+        let message = messages::social_message(account_id, &platform, &handle, &proof, validity_ns, bound);
+        let signer = self.verify_platform_signature(&platform, &message, &signature)?;
+        let validity_ns = self.attestation_validity(&platform, validity_ns)?;
+        self.commitments.remove(account_id);
+        self.admit_social(account_id, &platform, normalized, &proof, validity_ns, Provenance::Verifier(Some(signer)))
+    }
+}
@@ -0,0 +1,68 @@
+use near_sdk::json_types::U128;
+use near_sdk::{env, near, require, AccountId};
+
+use crate::{proofs, AccountDetails, Contract, ContractExt, SocialDetails};
+
+// the public views answer yes/no questions and compare hashes. the full record, linked addresses and proof
+// digests included, goes only to consumer contracts the owner approved, through a paid call.
+#[near]
+impl Contract {
+    pub fn add_consumer(&mut self, account_id: AccountId) {
+        self.assert_owner();
+        env::log_str(&format!("authorized consumer {}", account_id));
+        self.consumers.insert(account_id);
+    }
+
+    pub fn remove_consumer(&mut self, account_id: AccountId) {
+        self.assert_owner();
+        require!(self.consumers.remove(&account_id), "account is not an authorized consumer");
+        env::log_str(&format!("removed consumer {}", account_id));
+    }
+
+    pub fn get_consumers(&self) -> Vec<AccountId> {
+        self.consumers.iter().cloned().collect()
+    }
+
+    /// In yoctoNEAR, charged per `get_account_details` call and added to the treasury. 0 makes it free.
+    pub fn set_consumer_fee(&mut self, fee: U128) {
+        self.assert_owner();
+        env::log_str(&format!("consumer fee set to {}", fee.0));
+        self.consumer_fee = fee.0;
+    }
+
+    pub fn get_consumer_fee(&self) -> U128 {
+        self.consumer_fee.into()
+    }
+
+    /// The account's full record, for authorized consumer contracts calling with at least `get_consumer_fee`
+    /// attached or held in their `register_consumer` balance. Any excess deposit is refunded. Attributes are
+    /// returned as stored, also for banned accounts.
+    #[payable]
+    pub fn get_account_details(&mut self, account_id: AccountId) -> AccountDetails {
+        let consumer = env::predecessor_account_id();
+        require!(self.consumers.contains(&consumer), "only authorized consumers can call this method");
+        self.charge_query(self.consumer_fee);
+        let socials = self.social_platforms.get(&account_id).into_iter().flatten()
+            .flat_map(|platform| self.socials_on(&account_id, platform).into_iter().map(move |social| (platform, social)))
+            .map(|(platform, social)| SocialDetails {
+                platform: platform.clone(),
+                handle: social.handle,
+                proof_digest: proofs::digest_hex(&social.proof_digest),
+                proof_type: social.proof_type,
+                issued_at: social.issued_date.into(),
+                expires_at: social.expiry_date.into(),
+                provenance: social.provenance,
+            })
+            .collect();
+        AccountDetails {
+            access_keys: self.access_key_counts.get(&account_id).copied(),
+            created_at: self.account_ages.get(&account_id).map(|&created| created.into()),
+            socials,
+            evm_addresses: self.evm_addresses.get(&account_id).cloned().unwrap_or_default(),
+            ed25519_addresses: self.ed25519_addresses.get(&account_id).cloned().unwrap_or_default(),
+            passport_score: self.passport_scores.get(&account_id).cloned(),
+            world_id: self.world_ids.get(&account_id).cloned(),
+            metadata: self.get_profile_metadata(account_id),
+        }
+    }
+}
@@ -0,0 +1,53 @@
+use near_sdk::{env, near, AccountId};
+
+use crate::Contract;
+#[cfg(feature = "expanded-views")]
+use crate::{ContractExt, OperatorDashboard};
+
+pub(crate) const DAY_NS: u64 = 24 * 60 * 60 * 1_000_000_000;
+
+#[near(serializers = [borsh])]
+#[derive(Default)]
+pub struct IssuanceStats {
+    last_issued_at: u64,
+    day: u64, // days since epoch the counter below belongs to
+    issued_today: u32,
+}
+
+impl Contract {
+    // called after every verifier signed write is accepted, before the account settles its storage
+    pub(crate) fn record_issuance(&mut self, account_id: &AccountId) {
+        self.record_write(account_id);
+        self.warn_expiring(account_id);
+        self.cache_score(account_id);
+        let now = env::block_timestamp();
+        if self.issuance.day != now / DAY_NS {
+            self.issuance.day = now / DAY_NS;
+            self.issuance.issued_today = 0;
+        }
+        self.issuance.issued_today += 1;
+        self.issuance.last_issued_at = now;
+    }
+}
+
+#[cfg(feature = "expanded-views")]
+#[near]
+impl Contract {
+    pub fn get_operator_dashboard(&self) -> OperatorDashboard {
+        let now = env::block_timestamp();
+        let issued_today = if self.issuance.day == now / DAY_NS { self.issuance.issued_today } else { 0 };
+        let storage_usage = env::storage_usage();
+        let storage_capacity = (env::account_balance().as_yoctonear() / env::storage_byte_cost().as_yoctonear()) as u64;
+        OperatorDashboard {
+            last_issued_at: self.issuance.last_issued_at.into(),
+            issued_today,
+            account_balance: env::account_balance().as_yoctonear().into(),
+            storage_usage: storage_usage.into(),
+            storage_headroom: storage_capacity.saturating_sub(storage_usage).into(),
+            treasury_balance: self.treasury.into(),
+            circuit_breaker_tripped: self.breaker.tripped,
+            open_disputes: self.disputes.len(),
+            pending_changes: self.pending_changes.len(),
+        }
+    }
+}
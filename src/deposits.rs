@@ -0,0 +1,31 @@
+use near_sdk::json_types::U128;
+use near_sdk::{env, near};
+
+use crate::{Contract, ContractError, ContractExt};
+
+// checked before anything else in the payable attestation methods, so an underfunded call reverts with the
+// exact amount to attach rather than failing later on storage. the NEP-145 methods keep their own rules.
+#[near]
+impl Contract {
+    /// In yoctoNEAR, 0 disables the check.
+    pub fn set_min_deposit(&mut self, amount: U128) {
+        self.assert_owner();
+        self.min_deposit = amount.0;
+    }
+
+    pub fn get_min_deposit(&self) -> U128 {
+        self.min_deposit.into()
+    }
+}
+
+impl Contract {
+    // a registration needs its fee on top of the minimum deposit, both taken from the same attached deposit
+    pub(crate) fn ensure_deposit(&self, fee: u128) -> Result<(), ContractError> {
+        let got = env::attached_deposit().as_yoctonear();
+        let need = self.min_deposit.checked_add(fee).ok_or(ContractError::InsufficientDeposit { need: u128::MAX, got })?;
+        if got < need {
+            return Err(ContractError::InsufficientDeposit { need, got });
+        }
+        Ok(())
+    }
+}
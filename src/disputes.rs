@@ -0,0 +1,87 @@
+use near_sdk::json_types::U128;
+use near_sdk::serde_json::json;
+use near_sdk::{env, near, require, AccountId, NearToken, Promise};
+
+use crate::{events, Contract, ContractError, ContractExt, Dispute, DisputeOutcome, FunctionError, RevocationCode, Role, PAUSE_FILE_DISPUTE};
+
+// short enough to fit the revocation reason it ends up in
+const MAX_EVIDENCE_URI_LEN: usize = 200;
+const DEFAULT_PAGE_SIZE: u32 = 50;
+
+// anyone can contest an attestation by putting up the dispute bond. an operator weighs the evidence: a revoked
+// attestation returns the bond to the disputer along with a slash of the signing verifier's bond, a dismissed
+// dispute forfeits it to the treasury.
+#[near]
+impl Contract {
+    /// Contests the account's attestation on `platform` and returns the dispute's id. `get_dispute_bond` of the
+    /// attached deposit is held until the dispute is resolved, the rest pays for storing it and any excess is
+    /// refunded.
+    #[payable]
+    pub fn file_dispute(&mut self, account_id: AccountId, platform: String, evidence_uri: String) -> u64 {
+        self.ensure_not_paused(PAUSE_FILE_DISPUTE).unwrap_or_else(|err| err.panic());
+        let disputer = env::predecessor_account_id();
+        require!(disputer != account_id, "cannot dispute your own attestation");
+        require!(evidence_uri.len() <= MAX_EVIDENCE_URI_LEN, format!("evidence_uri must be at most {} bytes", MAX_EVIDENCE_URI_LEN));
+        if self.handles_on(&account_id, &platform).is_empty() {
+            ContractError::NotRegistered.panic();
+        }
+        let bond = self.dispute_bond;
+        let got = env::attached_deposit().as_yoctonear();
+        if got < bond {
+            ContractError::InsufficientDeposit { need: bond, got }.panic();
+        }
+        let id = self.next_dispute_id;
+        self.next_dispute_id += 1;
+        let usage_before = env::storage_usage();
+        events::emit("dispute_filed", json!({ "id": id, "account_id": account_id, "platform": platform, "disputer": disputer, "evidence_uri": evidence_uri }));
+        let dispute = Dispute { id, account_id, platform, evidence_uri, disputer: disputer.clone(), bond: bond.into(), filed_at: env::block_timestamp().into() };
+        self.disputes.insert(id, dispute);
+        self.settle_storage_from(&disputer, usage_before, got - bond).unwrap_or_else(|err| err.panic());
+        id
+    }
+
+    /// Open disputes, oldest first.
+    pub fn get_disputes(&self, from_index: Option<u32>, limit: Option<u32>) -> Vec<Dispute> {
+        let from = from_index.unwrap_or(0) as usize;
+        let limit = limit.unwrap_or(DEFAULT_PAGE_SIZE) as usize;
+        self.disputes.values().skip(from).take(limit).cloned().collect()
+    }
+
+    pub fn get_dispute(&self, id: u64) -> Option<Dispute> {
+        self.disputes.get(&id).cloned()
+    }
+
+    /// Revokes the disputed attestation, if the account still holds one, or dismisses the dispute.
+    pub fn resolve_dispute(&mut self, id: u64, outcome: DisputeOutcome) {
+        self.assert_any_role(&[Role::Owner, Role::Operator]);
+        self.flush_collections();
+        let usage_before = env::storage_usage();
+        let dispute = self.disputes.remove(&id).unwrap_or_else(|| env::panic_str("no open dispute with this id"));
+        self.flush_collections();
+        self.release_storage(&dispute.disputer, usage_before.saturating_sub(env::storage_usage()));
+        events::emit("dispute_resolved", json!({ "id": id, "outcome": outcome }));
+        match outcome {
+            DisputeOutcome::Revoke => {
+                self.slash_signers(&dispute.account_id, &dispute.platform, &dispute.disputer);
+                if !self.handles_on(&dispute.account_id, &dispute.platform).is_empty() {
+                    self.revoke(dispute.account_id, dispute.platform, RevocationCode::Disputed, format!("dispute {}: {}", id, dispute.evidence_uri));
+                }
+                if dispute.bond.0 > 0 {
+                    Promise::new(dispute.disputer).transfer(NearToken::from_yoctonear(dispute.bond.0)).detach();
+                }
+            }
+            DisputeOutcome::Dismiss => self.treasury += dispute.bond.0,
+        }
+    }
+
+    /// In yoctoNEAR, applies to disputes filed from now on.
+    pub fn set_dispute_bond(&mut self, bond: U128) {
+        self.assert_owner();
+        env::log_str(&format!("dispute bond set to {}", bond.0));
+        self.dispute_bond = bond.0;
+    }
+
+    pub fn get_dispute_bond(&self) -> U128 {
+        self.dispute_bond.into()
+    }
+}
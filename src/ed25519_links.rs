@@ -0,0 +1,78 @@
+use near_sdk::{bs58, env, near, AccountId, PublicKey};
+
+use crate::evm::MAX_LINKED_ADDRESSES;
+use crate::keys::verify_ed25519;
+use crate::{Contract, ContractError, ContractExt, LinkedAddress, PAUSE_LINK_ED25519_ADDRESS, RequestBound};
+
+const MAX_CHAIN_LEN: usize = 32;
+
+// its own method tag, so neither an EVM link nor a link under another chain label can be replayed as this one
+pub(crate) fn link_message(contract_id: &AccountId, account_id: &AccountId, chain: &str, address: &str, max_block_height: u64) -> String {
+    format!("link_ed25519_address,{},{},{},{},{}", contract_id, account_id, chain, address, max_block_height)
+}
+
+fn check_chain(chain: &str) -> Result<(), ContractError> {
+    if chain.is_empty() || chain.len() > MAX_CHAIN_LEN || chain.contains(',') {
+        return Err(ContractError::InvalidChain);
+    }
+    Ok(())
+}
+
+fn decode_address(address: &str) -> Result<PublicKey, ContractError> {
+    let bytes = bs58::decode(address).into_vec().map_err(|_| ContractError::InvalidEd25519Address)?;
+    if bytes.len() != 32 {
+        return Err(ContractError::InvalidEd25519Address);
+    }
+    let mut key = vec![0u8];
+    key.extend(bytes);
+    PublicKey::try_from(key).map_err(|_| ContractError::InvalidEd25519Address)
+}
+
+#[near]
+impl Contract {
+    /// Links an external ed25519 keypair (e.g. a solana address) to the caller, at most
+    /// `MAX_LINKED_ADDRESSES` per account. `signature` is the raw ed25519 signature by `address` over
+    /// `"link_ed25519_address,<contract_id>,<account_id>,<chain>,<address>,<max_block_height>"`. The storage it
+    /// takes is paid from the caller's storage balance and the attached deposit, any excess is refunded.
+    #[payable]
+    #[handle_result]
+    pub fn link_ed25519_address(&mut self, chain: String, address: String, signature: Vec<u8>, max_block_height: u64) -> Result<(), ContractError> {
+        self.ensure_not_paused(PAUSE_LINK_ED25519_ADDRESS)?;
+        self.ensure_deposit(0)?;
+        check_chain(&chain)?;
+        RequestBound::BlockHeight(max_block_height).ensure_valid()?;
+        let account_id = self.acting_account()?;
+        self.ensure_not_banned(&account_id)?;
+        let key = decode_address(&address)?;
+        let message = link_message(&env::current_account_id(), &account_id, &chain, &address, max_block_height);
+        verify_ed25519(&key, message.as_bytes(), &signature)?;
+        let index_key = (chain.clone(), address.clone());
+        if self.ed25519_owners.get(&index_key).is_some_and(|owner| *owner != account_id) {
+            return Err(ContractError::AddressAlreadyLinked);
+        }
+        let mut addresses = self.ed25519_addresses.get(&account_id).cloned().unwrap_or_default();
+        self.flush_collections();
+        let usage_before = env::storage_usage();
+        if !self.ed25519_owners.contains_key(&index_key) {
+            if addresses.len() >= MAX_LINKED_ADDRESSES {
+                return Err(ContractError::TooManyLinkedAddresses { max: MAX_LINKED_ADDRESSES as u32 });
+            }
+            addresses.push(LinkedAddress { chain, address, linked_at: env::block_timestamp().into() });
+            self.ed25519_addresses.insert(account_id.clone(), addresses);
+            self.accounts.insert(account_id.clone());
+            self.ed25519_owners.insert(index_key, account_id.clone());
+        }
+        self.settle_storage(&account_id, usage_before)?;
+        Ok(())
+    }
+
+    /// Whether `address` on `chain` is linked to the account. The full list is only available to authorized
+    /// consumers, see `get_account_details`.
+    pub fn is_ed25519_address_linked(&self, account_id: AccountId, chain: String, address: String) -> bool {
+        self.ed25519_owners.get(&(chain, address)) == Some(&account_id)
+    }
+
+    pub fn ed25519_address_owner(&self, chain: String, address: String) -> Option<AccountId> {
+        self.ed25519_owners.get(&(chain, address)).cloned()
+    }
+}
@@ -0,0 +1,181 @@
+use std::fmt;
+
+use near_sdk::{env, FunctionError};
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum ContractError {
+    RequestExpired,
+    InvalidRequestBound,
+    HandleAlreadyRegistered,
+    IncorrectProof,
+    PlatformAtCapacity,
+    InvalidSignatureLength { expected: usize, got: usize },
+    MalformedSignature,
+    UnsupportedKeyType,
+    MalformedPublicKey,
+    UnverifiedData,
+    InvalidUserSignature,
+    InvalidNonce,
+    InvalidEvmAddress,
+    InvalidEvmSignature,
+    InvalidEd25519Address,
+    AddressAlreadyLinked,
+    InvalidAccountAge,
+    InputTooLong { field: &'static str, max: u32 },
+    TooManyPlatforms { max: u32 },
+    Paused,
+    MethodPaused,
+    UnknownPlatform,
+    AccountBanned,
+    InsufficientDeposit { need: u128, got: u128 },
+    NotRegistered,
+    TooManyHandles { max: u32 },
+    HandleCoolingDown { until: u64 },
+    MalformedHandle,
+    AddressNotLinked,
+    OracleRejected,
+    ClaimPending,
+    ProfileClaimMissing,
+    TooManyMetadataEntries { max: u32 },
+    IndirectCall,
+    RateLimited { max: u32 },
+    CircuitBreakerTripped,
+    NoCommitment,
+    CommitmentNotReady { ready_at: u64 },
+    InvalidValidity { max: u64 },
+    InvalidStamp,
+    TooManyStamps { max: u32 },
+    UnknownStampType,
+    StampIssuerNotAllowed,
+    InvalidWorldIdProof,
+    WorldIdAlreadyUsed,
+    TooManyLinkedAddresses { max: u32 },
+    InvalidChain,
+    RecoveryKeyReserved,
+    TransferToSelf,
+}
+
+impl fmt::Display for ContractError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ContractError::RequestExpired => write!(f, "expired request"),
+            ContractError::InvalidRequestBound => {
+                write!(f, "exactly one of max_block_height or valid_until_timestamp_ns must be set")
+            }
+            ContractError::HandleAlreadyRegistered => write!(f, "handle already registered"),
+            ContractError::IncorrectProof => write!(f, "incorrect proof"),
+            ContractError::PlatformAtCapacity => write!(f, "all slots for this platform are currently held"),
+            ContractError::InvalidSignatureLength { expected, got } => {
+                write!(f, "invalid signature length: expected {} bytes, got {}", expected, got)
+            }
+            ContractError::MalformedSignature => write!(f, "malformed ed25519 signature"),
+            ContractError::UnsupportedKeyType => write!(f, "public key must be an ed25519 key"),
+            ContractError::MalformedPublicKey => write!(f, "public key is not a valid ed25519 point"),
+            ContractError::UnverifiedData => write!(f, "unverified data"),
+            ContractError::InvalidUserSignature => write!(f, "invalid NEP-413 signature from account key"),
+            ContractError::InvalidNonce => write!(f, "nonce must be 32 unused bytes"),
+            ContractError::InvalidEvmAddress => write!(f, "evm address must be 0x followed by 40 hex characters"),
+            ContractError::InvalidEvmSignature => write!(f, "signature was not produced by the claimed evm address"),
+            ContractError::InvalidEd25519Address => write!(f, "address must be a base58 encoded 32 byte ed25519 key"),
+            ContractError::AddressAlreadyLinked => write!(f, "address already linked to another account"),
+            ContractError::InvalidAccountAge => write!(f, "account age must fit in a u64 timestamp"),
+            ContractError::InputTooLong { field, max } => write!(f, "{} must be at most {} bytes", field, max),
+            ContractError::TooManyPlatforms { max } => write!(f, "account already holds attestations for {} platforms", max),
+            ContractError::Paused => write!(f, "contract is paused"),
+            ContractError::MethodPaused => write!(f, "this method is paused"),
+            ContractError::UnknownPlatform => write!(f, "platform is not on the allowlist"),
+            ContractError::AccountBanned => write!(f, "account is banned"),
+            ContractError::InsufficientDeposit { need, got } => {
+                write!(f, "insufficient deposit: need {} yoctoNEAR, got {}", need, got)
+            }
+            ContractError::NotRegistered => write!(f, "no attestation for this platform"),
+            ContractError::TooManyHandles { max } => write!(f, "account already holds {} handles on this platform", max),
+            ContractError::HandleCoolingDown { until } => write!(f, "handle was recently released and can be claimed from {}", until),
+            ContractError::MalformedHandle => write!(f, "handle does not match the platform's handle format"),
+            ContractError::AddressNotLinked => write!(f, "address is not linked to the account"),
+            ContractError::OracleRejected => write!(f, "oracle did not confirm the handle belongs to the address"),
+            ContractError::ClaimPending => write!(f, "another account has a pending claim on this handle"),
+            ContractError::ProfileClaimMissing => write!(f, "profile does not hold the expected claim"),
+            ContractError::TooManyMetadataEntries { max } => write!(f, "profile metadata holds at most {} entries", max),
+            ContractError::IndirectCall => write!(f, "call must be made by the signer or an intermediary it allowed"),
+            ContractError::RateLimited { max } => write!(f, "account already had {} attestations accepted this epoch", max),
+            ContractError::CircuitBreakerTripped => write!(f, "registrations are halted after abnormal volume"),
+            ContractError::NoCommitment => write!(f, "no unexpired commitment matches the revealed registration"),
+            ContractError::CommitmentNotReady { ready_at } => write!(f, "commitment can be revealed from {}", ready_at),
+            ContractError::InvalidValidity { max } => write!(f, "validity must be positive and at most {} ns", max),
+            ContractError::InvalidStamp => write!(f, "stamp value or expiry doesn't match the stamp type's schema"),
+            ContractError::TooManyStamps { max } => write!(f, "account already holds {} stamps", max),
+            ContractError::UnknownStampType => write!(f, "stamp type has no schema"),
+            ContractError::StampIssuerNotAllowed => write!(f, "the stamp type's schema doesn't allow this issuer"),
+            ContractError::InvalidWorldIdProof => write!(f, "world id hashes must be 0x followed by 64 hex characters and the level orb or device"),
+            ContractError::WorldIdAlreadyUsed => write!(f, "world id already backs another account"),
+            ContractError::TooManyLinkedAddresses { max } => write!(f, "account already has {} linked addresses", max),
+            ContractError::InvalidChain => write!(f, "chain must be 1 to 32 bytes without commas"),
+            ContractError::RecoveryKeyReserved => write!(f, "the recovery key can only replace the admin key, not sign attestations"),
+            ContractError::TransferToSelf => write!(f, "cannot transfer a handle to its owner"),
+        }
+    }
+}
+
+impl ContractError {
+    /// Stable identifier recorded with failed attempts, independent of the message wording.
+    pub fn code(&self) -> &'static str {
+        match self {
+            ContractError::RequestExpired => "REQUEST_EXPIRED",
+            ContractError::InvalidRequestBound => "INVALID_REQUEST_BOUND",
+            ContractError::HandleAlreadyRegistered => "HANDLE_ALREADY_REGISTERED",
+            ContractError::IncorrectProof => "INCORRECT_PROOF",
+            ContractError::PlatformAtCapacity => "PLATFORM_AT_CAPACITY",
+            ContractError::InvalidSignatureLength { .. } => "INVALID_SIGNATURE_LENGTH",
+            ContractError::MalformedSignature => "MALFORMED_SIGNATURE",
+            ContractError::UnsupportedKeyType => "UNSUPPORTED_KEY_TYPE",
+            ContractError::MalformedPublicKey => "MALFORMED_PUBLIC_KEY",
+            ContractError::UnverifiedData => "UNVERIFIED_DATA",
+            ContractError::InvalidUserSignature => "INVALID_USER_SIGNATURE",
+            ContractError::InvalidNonce => "INVALID_NONCE",
+            ContractError::InvalidEvmAddress => "INVALID_EVM_ADDRESS",
+            ContractError::InvalidEvmSignature => "INVALID_EVM_SIGNATURE",
+            ContractError::InvalidEd25519Address => "INVALID_ED25519_ADDRESS",
+            ContractError::AddressAlreadyLinked => "ADDRESS_ALREADY_LINKED",
+            ContractError::InvalidAccountAge => "INVALID_ACCOUNT_AGE",
+            ContractError::InputTooLong { .. } => "INPUT_TOO_LONG",
+            ContractError::TooManyPlatforms { .. } => "TOO_MANY_PLATFORMS",
+            ContractError::Paused => "PAUSED",
+            ContractError::MethodPaused => "METHOD_PAUSED",
+            ContractError::UnknownPlatform => "UNKNOWN_PLATFORM",
+            ContractError::AccountBanned => "ACCOUNT_BANNED",
+            ContractError::InsufficientDeposit { .. } => "INSUFFICIENT_DEPOSIT",
+            ContractError::NotRegistered => "NOT_REGISTERED",
+            ContractError::TooManyHandles { .. } => "TOO_MANY_HANDLES",
+            ContractError::HandleCoolingDown { .. } => "HANDLE_COOLING_DOWN",
+            ContractError::MalformedHandle => "MALFORMED_HANDLE",
+            ContractError::AddressNotLinked => "ADDRESS_NOT_LINKED",
+            ContractError::OracleRejected => "ORACLE_REJECTED",
+            ContractError::ClaimPending => "CLAIM_PENDING",
+            ContractError::ProfileClaimMissing => "PROFILE_CLAIM_MISSING",
+            ContractError::TooManyMetadataEntries { .. } => "TOO_MANY_METADATA_ENTRIES",
+            ContractError::IndirectCall => "INDIRECT_CALL",
+            ContractError::RateLimited { .. } => "RATE_LIMITED",
+            ContractError::CircuitBreakerTripped => "CIRCUIT_BREAKER_TRIPPED",
+            ContractError::NoCommitment => "NO_COMMITMENT",
+            ContractError::CommitmentNotReady { .. } => "COMMITMENT_NOT_READY",
+            ContractError::InvalidValidity { .. } => "INVALID_VALIDITY",
+            ContractError::InvalidStamp => "INVALID_STAMP",
+            ContractError::TooManyStamps { .. } => "TOO_MANY_STAMPS",
+            ContractError::UnknownStampType => "UNKNOWN_STAMP_TYPE",
+            ContractError::StampIssuerNotAllowed => "STAMP_ISSUER_NOT_ALLOWED",
+            ContractError::InvalidWorldIdProof => "INVALID_WORLD_ID_PROOF",
+            ContractError::WorldIdAlreadyUsed => "WORLD_ID_ALREADY_USED",
+            ContractError::TooManyLinkedAddresses { .. } => "TOO_MANY_LINKED_ADDRESSES",
+            ContractError::InvalidChain => "INVALID_CHAIN",
+            ContractError::RecoveryKeyReserved => "RECOVERY_KEY_RESERVED",
+            ContractError::TransferToSelf => "TRANSFER_TO_SELF",
+        }
+    }
+}
+
+impl FunctionError for ContractError {
+    fn panic(&self) -> ! {
+        env::panic_str(&self.to_string())
+    }
+}
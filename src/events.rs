@@ -0,0 +1,10 @@
+use near_sdk::env;
+use near_sdk::serde_json::{json, Value};
+
+// NEP-297 events, logged as `EVENT_JSON:{"standard": .., "version": .., "event": .., "data": [..]}`
+const STANDARD: &str = "sybil-provider";
+const VERSION: &str = "1.0.0";
+
+pub(crate) fn emit(event: &str, data: Value) {
+    env::log_str(&format!("EVENT_JSON:{}", json!({ "standard": STANDARD, "version": VERSION, "event": event, "data": [data] })));
+}
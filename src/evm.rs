@@ -0,0 +1,89 @@
+use near_sdk::{env, near, AccountId};
+
+use crate::{Contract, ContractError, ContractExt, PAUSE_LINK_EVM_ADDRESS, RequestBound};
+
+// per account and per kind, so an account's linked addresses always stay cheap to load
+pub(crate) const MAX_LINKED_ADDRESSES: usize = 8;
+
+// EIP-191 `personal_sign` digest of `message`
+pub(crate) fn personal_sign_hash(message: &str) -> [u8; 32] {
+    let prefixed = format!("\x19Ethereum Signed Message:\n{}{}", message.len(), message);
+    env::keccak256_array(prefixed.as_bytes())
+}
+
+// normalizes `0x`-prefixed hex addresses to lowercase, rejecting anything that isn't 20 bytes
+pub(crate) fn normalize_address(address: &str) -> Result<String, ContractError> {
+    let hex = address.strip_prefix("0x").ok_or(ContractError::InvalidEvmAddress)?;
+    if hex.len() != 40 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(ContractError::InvalidEvmAddress);
+    }
+    Ok(format!("0x{}", hex.to_ascii_lowercase()))
+}
+
+fn recover_address(hash: &[u8; 32], signature: &[u8]) -> Option<String> {
+    if signature.len() != 65 {
+        return None;
+    }
+    // wallets return v as 27/28, ecrecover expects the raw recovery id
+    let v = match signature[64] {
+        27 | 28 => signature[64] - 27,
+        v => v,
+    };
+    let public_key = env::ecrecover(hash, &signature[..64], v, true)?;
+    let address = &env::keccak256_array(public_key)[12..];
+    Some(address.iter().fold(String::from("0x"), |acc, byte| acc + &format!("{:02x}", byte)))
+}
+
+// names the method and this contract so the signature can't be replayed elsewhere
+pub(crate) fn link_message(contract_id: &AccountId, account_id: &AccountId, max_block_height: u64) -> String {
+    format!("link_evm_address,{},{},{}", contract_id, account_id, max_block_height)
+}
+
+#[near]
+impl Contract {
+    /// Links an Ethereum address to the caller, at most `MAX_LINKED_ADDRESSES` per account. `signature` is the
+    /// 65 byte `personal_sign` signature by `address` over
+    /// `"link_evm_address,<contract_id>,<account_id>,<max_block_height>"`. The storage it takes is paid from the
+    /// caller's storage balance and the attached deposit, any excess is refunded.
+    #[payable]
+    #[handle_result]
+    pub fn link_evm_address(&mut self, address: String, signature: Vec<u8>, max_block_height: u64) -> Result<(), ContractError> {
+        self.ensure_not_paused(PAUSE_LINK_EVM_ADDRESS)?;
+        self.ensure_deposit(0)?;
+        RequestBound::BlockHeight(max_block_height).ensure_valid()?;
+        let account_id = self.acting_account()?;
+        self.ensure_not_banned(&account_id)?;
+        let address = normalize_address(&address)?;
+        let hash = personal_sign_hash(&link_message(&env::current_account_id(), &account_id, max_block_height));
+        if recover_address(&hash, &signature).as_ref() != Some(&address) {
+            return Err(ContractError::InvalidEvmSignature);
+        }
+        if self.evm_owners.get(&address).is_some_and(|owner| *owner != account_id) {
+            return Err(ContractError::AddressAlreadyLinked);
+        }
+        let mut addresses = self.evm_addresses.get(&account_id).cloned().unwrap_or_default();
+        self.flush_collections();
+        let usage_before = env::storage_usage();
+        if !addresses.contains(&address) {
+            if addresses.len() >= MAX_LINKED_ADDRESSES {
+                return Err(ContractError::TooManyLinkedAddresses { max: MAX_LINKED_ADDRESSES as u32 });
+            }
+            addresses.push(address.clone());
+            self.evm_addresses.insert(account_id.clone(), addresses);
+            self.accounts.insert(account_id.clone());
+            self.evm_owners.insert(address, account_id.clone());
+        }
+        self.settle_storage(&account_id, usage_before)?;
+        Ok(())
+    }
+
+    /// Whether `address` is linked to the account. The full list is only available to authorized consumers,
+    /// see `get_account_details`.
+    pub fn is_evm_address_linked(&self, account_id: AccountId, address: String) -> bool {
+        normalize_address(&address).is_ok_and(|address| self.evm_owners.get(&address) == Some(&account_id))
+    }
+
+    pub fn evm_address_owner(&self, address: String) -> Option<AccountId> {
+        normalize_address(&address).ok().and_then(|address| self.evm_owners.get(&address).cloned())
+    }
+}
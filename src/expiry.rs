@@ -0,0 +1,57 @@
+use near_sdk::json_types::U64;
+use near_sdk::serde_json::json;
+use near_sdk::{env, near, AccountId};
+
+use crate::dashboard::DAY_NS;
+use crate::{events, Contract, ContractExt, ExpiringSocial};
+
+pub(crate) const DEFAULT_EXPIRY_WARNING_NS: u64 = 14 * DAY_NS;
+
+// notification bots watch for `expiring_soon` events, or poll `get_expiring`, to remind users to renew. views
+// can't emit events, so the check runs on the account's own accepted writes, once per attestation and expiry.
+#[near]
+impl Contract {
+    /// How long before its expiry an attestation is warned about, 0 turns the warnings off.
+    pub fn set_expiry_warning(&mut self, warning_ns: U64) {
+        self.assert_owner();
+        self.expiry_warning_ns = warning_ns.0;
+    }
+
+    pub fn get_expiry_warning(&self) -> U64 {
+        self.expiry_warning_ns.into()
+    }
+
+    /// The account's attestations that are still valid but expire within `within_ns`, soonest first.
+    pub fn get_expiring(&self, account_id: AccountId, within_ns: U64) -> Vec<ExpiringSocial> {
+        let mut expiring = self.expiring_within(&account_id, within_ns.0);
+        expiring.sort_by_key(|social| social.expires_at.0);
+        expiring
+    }
+}
+
+impl Contract {
+    fn expiring_within(&self, account_id: &AccountId, within_ns: u64) -> Vec<ExpiringSocial> {
+        let now = env::block_timestamp();
+        let platforms = self.social_platforms.get(account_id).cloned().unwrap_or_default();
+        platforms.into_iter()
+            .flat_map(|platform| self.socials_on(account_id, &platform).into_iter().map(move |social| (platform.clone(), social)))
+            .filter(|(_, social)| social.expiry_date > now && social.expiry_date <= now.saturating_add(within_ns))
+            .map(|(platform, social)| ExpiringSocial { platform, handle: social.handle, expires_at: social.expiry_date.into() })
+            .collect()
+    }
+
+    // emits `expiring_soon` for attestations of the account that entered the warning window since last checked
+    pub(crate) fn warn_expiring(&mut self, account_id: &AccountId) {
+        if self.expiry_warning_ns == 0 {
+            return;
+        }
+        for social in self.expiring_within(account_id, self.expiry_warning_ns) {
+            let key = (account_id.clone(), social.platform.clone(), social.handle.clone());
+            if self.expiry_warnings.get(&key) == Some(&social.expires_at.0) {
+                continue;
+            }
+            self.expiry_warnings.insert(key, social.expires_at.0);
+            events::emit("expiring_soon", json!({ "account_id": account_id, "platform": social.platform, "handle": social.handle, "expires_at": social.expires_at }));
+        }
+    }
+}
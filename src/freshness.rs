@@ -0,0 +1,27 @@
+use near_sdk::json_types::U64;
+use near_sdk::{env, near, AccountId};
+
+use crate::{AttributeUpdates, Contract, ContractExt};
+
+// the threshold views take an optional `max_staleness_ns`, so consumers can refuse to rely on a count or age
+// attested long ago. attributes without a recorded update are never fresh.
+#[near]
+impl Contract {
+    pub fn get_attribute_updates(&self, account_id: AccountId) -> AttributeUpdates {
+        self.attribute_updates(&account_id)
+    }
+}
+
+impl Contract {
+    pub(crate) fn attribute_updates(&self, account_id: &AccountId) -> AttributeUpdates {
+        self.attribute_updates.get(account_id).cloned().unwrap_or_default()
+    }
+
+    pub(crate) fn attribute_updates_mut(&mut self, account_id: &AccountId) -> &mut AttributeUpdates {
+        self.attribute_updates.entry(account_id.clone()).or_default()
+    }
+
+    pub(crate) fn is_fresh(&self, updated_at: Option<U64>, max_staleness_ns: Option<u64>) -> bool {
+        max_staleness_ns.is_none_or(|max| updated_at.is_some_and(|updated_at| env::block_timestamp().saturating_sub(updated_at.0) <= max))
+    }
+}
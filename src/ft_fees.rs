@@ -0,0 +1,138 @@
+use near_sdk::json_types::U128;
+use near_sdk::serde_json::json;
+use near_sdk::{env, ext_contract, near, require, AccountId, Gas, NearToken, Promise, PromiseError, PromiseOrValue};
+
+use crate::{events, Contract, ContractExt, FunctionError};
+
+const MAX_FEE_TOKENS: u32 = 8;
+const FT_TRANSFER_GAS: Gas = Gas::from_tgas(10);
+const ON_FT_WITHDRAWN_GAS: Gas = Gas::from_tgas(10);
+const YOCTO_PER_NEAR: u128 = 1_000_000_000_000_000_000_000_000;
+
+/// The NEP-141 method used to pay fee tokens out.
+#[ext_contract(ext_ft)]
+#[allow(dead_code)] // only called through the generated `ext_ft`
+pub trait FungibleToken {
+    fn ft_transfer(&mut self, receiver_id: AccountId, amount: U128, memo: Option<String>);
+}
+
+// fees are priced in NEAR. accounts can instead prepay them in an allowlisted token through `ft_transfer_call`,
+// each fee then taking its NEAR price converted at the owner's rate from that credit. collected token fees are
+// kept per token, apart from the NEAR treasury.
+#[near]
+impl Contract {
+    /// Accepts `token_id` for fees at `units_per_near` of its smallest unit per NEAR, `None` stops accepting
+    /// it. Credit already held stays withdrawable.
+    pub fn set_fee_token(&mut self, token_id: AccountId, units_per_near: Option<U128>) {
+        self.assert_owner();
+        match units_per_near {
+            Some(rate) => {
+                require!(rate.0 > 0, "rate must be positive");
+                require!(self.fee_tokens.contains_key(&token_id) || self.fee_tokens.len() < MAX_FEE_TOKENS, format!("at most {} fee tokens", MAX_FEE_TOKENS));
+                env::log_str(&format!("fee token {} at {} per NEAR", token_id, rate.0));
+                self.fee_tokens.insert(token_id, rate.0);
+            }
+            None => require!(self.fee_tokens.remove(&token_id).is_some(), "not a fee token"),
+        }
+    }
+
+    pub fn get_fee_tokens(&self) -> Vec<(AccountId, U128)> {
+        self.fee_tokens.iter().map(|(token_id, rate)| (token_id.clone(), U128(*rate))).collect()
+    }
+
+    /// NEP-141 receiver. Credits the transfer to the sender's fee balance in the token, `msg` must be empty.
+    /// Transfers of tokens that aren't accepted, or with another `msg`, are returned in full.
+    pub fn ft_on_transfer(&mut self, sender_id: AccountId, amount: U128, msg: String) -> PromiseOrValue<U128> {
+        let token_id = env::predecessor_account_id();
+        if !self.fee_tokens.contains_key(&token_id) || !msg.is_empty() {
+            env::log_str(&format!("returned {} of {}", amount.0, token_id));
+            return PromiseOrValue::Value(amount);
+        }
+        let usage_before = env::storage_usage();
+        *self.ft_fee_balances.entry((sender_id.clone(), token_id.clone())).or_insert(0) += amount.0;
+        events::emit("fee_balance_credited", json!({ "account_id": sender_id, "token_id": token_id, "amount": amount }));
+        self.settle_storage_from(&sender_id, usage_before, 0).unwrap_or_else(|err| err.panic());
+        PromiseOrValue::Value(U128(0))
+    }
+
+    pub fn get_fee_balance(&self, account_id: AccountId, token_id: AccountId) -> U128 {
+        U128(self.ft_fee_balances.get(&(account_id, token_id)).copied().unwrap_or(0))
+    }
+
+    /// Withdraws `amount` of the caller's unspent credit in `token_id`, all of it if `None`. Requires 1
+    /// yoctoNEAR. The credit is restored if the transfer fails.
+    #[payable]
+    pub fn withdraw_fee_balance(&mut self, token_id: AccountId, amount: Option<U128>) -> Promise {
+        near_sdk::assert_one_yocto();
+        let account_id = env::predecessor_account_id();
+        let key = (account_id.clone(), token_id.clone());
+        let balance = self.ft_fee_balances.get(&key).copied().unwrap_or(0);
+        let amount = amount.map_or(balance, |amount| amount.0);
+        require!(amount > 0 && amount <= balance, "amount exceeds the fee balance");
+        self.ft_fee_balances.insert(key, balance - amount);
+        self.transfer_ft(token_id, account_id.clone(), amount, Some(account_id))
+    }
+
+    pub fn get_ft_treasury_balance(&self, token_id: AccountId) -> U128 {
+        U128(self.ft_treasury.get(&token_id).copied().unwrap_or(0))
+    }
+
+    pub fn withdraw_ft_treasury(&mut self, token_id: AccountId, amount: U128, to: AccountId) -> Promise {
+        self.assert_owner();
+        let balance = self.ft_treasury.get(&token_id).copied().unwrap_or(0);
+        require!(amount.0 <= balance, "amount exceeds the treasury balance");
+        self.ft_treasury.insert(token_id.clone(), balance - amount.0);
+        env::log_str(&format!("withdrew {} of {} from the treasury to {}", amount.0, token_id, to));
+        self.transfer_ft(token_id, to, amount.0, None)
+    }
+
+    // puts a failed transfer back where it came from, the account's fee balance or else the treasury
+    #[private]
+    pub fn on_ft_withdrawn(&mut self, token_id: AccountId, amount: U128, account_id: Option<AccountId>, #[callback_result] res: Result<(), PromiseError>) -> bool {
+        if res.is_ok() {
+            return true;
+        }
+        match account_id {
+            Some(account_id) => *self.ft_fee_balances.entry((account_id, token_id)).or_insert(0) += amount.0,
+            None => *self.ft_treasury.entry(token_id).or_insert(0) += amount.0,
+        }
+        false
+    }
+}
+
+impl Contract {
+    // what of the registration fee the caller still has to attach in NEAR, nothing if its token credit covers it
+    pub(crate) fn registration_fee_due(&self) -> u128 {
+        let payer = env::predecessor_account_id();
+        if self.fee_token_covering(&payer, self.registration_fee).is_some() { 0 } else { self.registration_fee }
+    }
+
+    // charges `fee` to the payer's token credit if it covers it, returning the part still owed in NEAR
+    pub(crate) fn take_fee(&mut self, payer: &AccountId, fee: u128) -> u128 {
+        let Some((token_id, units)) = self.fee_token_covering(payer, fee) else { return fee };
+        *self.ft_fee_balances.get_mut(&(payer.clone(), token_id.clone())).unwrap() -= units;
+        *self.ft_treasury.entry(token_id.clone()).or_insert(0) += units;
+        events::emit("fee_paid_in_token", json!({ "account_id": payer, "token_id": token_id, "amount": U128(units) }));
+        0
+    }
+
+    // the first accepted token the payer holds enough credit in for `fee`, with the amount it costs
+    fn fee_token_covering(&self, payer: &AccountId, fee: u128) -> Option<(AccountId, u128)> {
+        if fee == 0 {
+            return None;
+        }
+        self.fee_tokens.iter().find_map(|(token_id, rate)| {
+            let units = fee.checked_mul(*rate)?.div_ceil(YOCTO_PER_NEAR);
+            let balance = self.ft_fee_balances.get(&(payer.clone(), token_id.clone())).copied().unwrap_or(0);
+            (balance >= units).then(|| (token_id.clone(), units))
+        })
+    }
+
+    fn transfer_ft(&self, token_id: AccountId, receiver_id: AccountId, amount: u128, account_id: Option<AccountId>) -> Promise {
+        ext_ft::ext(token_id.clone())
+            .with_static_gas(FT_TRANSFER_GAS)
+            .with_attached_deposit(NearToken::from_yoctonear(1))
+            .ft_transfer(receiver_id, U128(amount), None)
+            .then(Self::ext(env::current_account_id()).with_static_gas(ON_FT_WITHDRAWN_GAS).on_ft_withdrawn(token_id, U128(amount), account_id))
+    }
+}
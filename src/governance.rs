@@ -0,0 +1,39 @@
+use near_sdk::{env, near};
+
+use crate::{ConfigChange, Contract, ContractExt};
+
+// the owner can be a Sputnik/AstroDAO contract, whose `FunctionCall` proposals carry one method name and a
+// json args blob. this takes any timelocked change as a single `{"change": ...}` argument, so one proposal
+// template covers key rotation, platform config and verifier additions alike.
+#[near]
+impl Contract {
+    /// Validates and stages `change` exactly like its dedicated method. Returns the pending change id, or
+    /// `None` when it applied immediately (raising the timelock delay).
+    pub fn propose_config_change(&mut self, change: ConfigChange) -> Option<u64> {
+        match change {
+            ConfigChange::AdminKey(key) => Some(self.propose_admin_key(key)),
+            ConfigChange::RecoveryKey(key) => Some(self.set_recovery_key(key)),
+            ConfigChange::PlatformConfig { platform, config } => Some(self.set_platform_config(platform, config)),
+            ConfigChange::TimelockDelay(delay_ns) => self.set_timelock_delay(delay_ns),
+            ConfigChange::AddVerifier(key) => Some(self.add_verifier(key)),
+            ConfigChange::PlatformVerifier { platform, key } => Some(self.set_platform_verifier(platform, key)),
+            ConfigChange::PlatformOracle { platform, oracle_id } => Some(self.set_platform_oracle(platform, oracle_id)),
+            ConfigChange::NearSocialContract(account_id) => Some(self.set_near_social_contract(account_id)),
+            ConfigChange::PlatformEnabled { platform, enabled: true } => Some(self.allow_platform(platform)),
+            ConfigChange::PlatformEnabled { platform, enabled: false } => Some(self.disallow_platform(platform)),
+        }
+    }
+
+    /// Applies every pending change whose timelock has passed, in proposal order, so the follow-up vote
+    /// doesn't need the ids. Returns the ids applied.
+    pub fn execute_ready_changes(&mut self) -> Vec<u64> {
+        self.assert_owner();
+        let now = env::block_timestamp();
+        let mut ready: Vec<u64> = self.pending_changes.values().filter(|pending| now >= pending.executable_at.0).map(|pending| pending.id).collect();
+        ready.sort_unstable();
+        for &id in &ready {
+            self.execute_change(id);
+        }
+        ready
+    }
+}
@@ -0,0 +1,307 @@
+use near_sdk::json_types::U64;
+use near_sdk::serde_json::json;
+use near_sdk::{env, near, AccountId};
+use unicode_normalization::UnicodeNormalization;
+
+use crate::{events, messages, Contract, ContractError, ContractExt, FunctionError, ReleaseReason, RequestBound, Role, SocialData, PAUSE_TRANSFER_HANDLE, PAUSE_UNREGISTER_SOCIAL};
+
+/// Handles an account can hold on a platform configured with `multi_handle`.
+pub(crate) const MAX_HANDLES_PER_PLATFORM: usize = 10;
+
+// a handle given up while its platform has a `release_cooldown_ns`, reserved for `account_id` until the
+// cooldown passes. kept until the handle is claimed again.
+#[near(serializers = [borsh])]
+#[derive(Clone)]
+pub struct ReleasedHandle {
+    account_id: AccountId,
+    released_at: u64,
+}
+
+// the form handles are stored and looked up in: trimmed, lowercased and NFC composed
+pub(crate) fn normalize_handle(handle: &str) -> String {
+    handle.trim().to_lowercase().nfc().collect()
+}
+
+#[near]
+impl Contract {
+    /// Removes up to `limit` handle mappings whose claim has expired, and whose cooldown has passed, or was
+    /// replaced by a different handle, resuming where the previous call stopped and wrapping around at the end.
+    /// An expired attestation is removed along with its mapping and its storage refunded to the holder. Returns
+    /// how many were removed.
+    pub fn purge_expired_handles(&mut self, limit: u32) -> u32 {
+        self.assert_role(Role::Operator);
+        let now = env::block_timestamp();
+        let mut cursor = self.handle_purge_cursor;
+        let mut purged = 0;
+        for _ in 0..limit {
+            let Some((key, account_id)) = self.handles.iter().nth(cursor as usize).map(|(key, account_id)| (key.clone(), account_id.clone())) else {
+                break;
+            };
+            if self.handle_is_reserved(&key, &account_id, now) || self.handle_cooldown(&key).is_some_and(|(_, until)| now < until) {
+                cursor += 1;
+                continue;
+            }
+            // the last mapping is swapped into `cursor`, so it is checked next
+            if self.handles_on(&account_id, &key.0).contains(&key.1) {
+                // the expired attestation goes with it, renewing it later would map the handle to two accounts
+                self.remove_handle(&account_id, &key.0, &key.1, ReleaseReason::Expired);
+                self.refresh_cached_score(&account_id);
+            } else {
+                self.remove_stale_mapping(&key, &account_id);
+            }
+            purged += 1;
+        }
+        self.handle_purge_cursor = if cursor >= self.handles.len() { 0 } else { cursor };
+        purged
+    }
+
+    /// Rewrites up to `limit` handles registered before handles were normalized into their normalized form,
+    /// resuming where the previous call stopped and wrapping around at the end. When two spellings of a handle
+    /// were held, the attestation expiring later keeps it and the other is dropped. Returns how many were
+    /// rewritten, keep calling until it returns 0 for a full pass.
+    pub fn normalize_stored_handles(&mut self, limit: u32) -> u32 {
+        self.assert_any_role(&[Role::Owner, Role::Operator]);
+        let mut cursor = self.handle_normalize_cursor;
+        let mut rewritten = 0;
+        for _ in 0..limit {
+            let Some((key, account_id)) = self.handles.iter().nth(cursor as usize).map(|(key, account_id)| (key.clone(), account_id.clone())) else {
+                break;
+            };
+            if normalize_handle(&key.1) == key.1 {
+                cursor += 1;
+                continue;
+            }
+            // the last mapping is swapped into `cursor` and the normalized one appended, so both are checked later
+            self.normalize_handle_mapping(key, account_id);
+            rewritten += 1;
+        }
+        self.handle_normalize_cursor = if cursor >= self.handles.len() { 0 } else { cursor };
+        rewritten
+    }
+
+    /// Disconnects the caller's own `platform`, freeing its handles and refunding the storage they used.
+    pub fn unregister_social(&mut self, platform: String) {
+        self.ensure_not_paused(PAUSE_UNREGISTER_SOCIAL).unwrap_or_else(|err| err.panic());
+        let account_id = env::predecessor_account_id();
+        let handles = self.handles_on(&account_id, &platform);
+        if handles.is_empty() {
+            env::panic_str("no attestation for this platform");
+        }
+        self.remove_social(&account_id, &platform, ReleaseReason::Unregistered);
+        self.refresh_cached_score(&account_id);
+        events::emit("social_unregistered", json!({ "account_id": account_id, "platform": platform, "handles": handles }));
+    }
+
+    /// Moves the signer's attestation of `handle` to `receiver_id` as is, keeping its proof and expiry. The
+    /// verifier co-signs `"transfer_handle,<account_id>,<receiver_id>,<platform>,<handle>,<bound>"`, with the
+    /// handle normalized as it is stored, after
+    /// checking the receiver should hold it. The receiver must pass the same checks as a registration of the
+    /// handle: not banned, within its rate limit and the platform enabled. The storage it takes up for the
+    /// receiver is paid like a registration, from the receiver's storage balance and then the attached deposit.
+    #[payable]
+    #[handle_result]
+    pub fn transfer_handle(&mut self, platform: String, handle: String, receiver_id: AccountId, signature: Vec<u8>, max_block_height: Option<u64>, valid_until_timestamp_ns: Option<U64>) -> Result<(), ContractError> {
+        self.ensure_not_paused(PAUSE_TRANSFER_HANDLE)?;
+        self.ensure_deposit(0)?;
+        let bound = RequestBound::new(max_block_height, valid_until_timestamp_ns)?;
+        bound.ensure_valid()?;
+        let handle = normalize_handle(&handle);
+        let account_id = self.acting_account()?;
+        if receiver_id == account_id {
+            return Err(ContractError::TransferToSelf);
+        }
+        self.ensure_not_banned(&account_id)?;
+        let social = self.socials_on(&account_id, &platform).into_iter().find(|social| social.handle == handle).ok_or(ContractError::NotRegistered)?;
+        let message = messages::transfer_handle_message(&account_id, &receiver_id, &platform, &handle, bound);
+        self.verify_platform_signature(&platform, &message, &signature)?;
+        // the sender is refunded here, an error below reverts it along with everything else
+        self.remove_handle(&account_id, &platform, &handle, ReleaseReason::Transferred);
+        // with the sender's claim gone the receiver has to be able to register the handle itself
+        self.check_claimable(&receiver_id, &platform, &handle)?;
+        let usage_before = env::storage_usage();
+        self.store_social(&receiver_id, &platform, social)?;
+        self.refresh_cached_score(&account_id);
+        self.refresh_cached_score(&receiver_id);
+        self.settle_storage(&receiver_id, usage_before)?;
+        events::emit("handle_transferred", json!({ "platform": platform, "handle": handle, "from": account_id, "to": receiver_id }));
+        Ok(())
+    }
+
+    /// The handles the account holds attestations for on `platform`, more than one only on `multi_handle`
+    /// platforms.
+    pub fn get_handles(&self, account_id: AccountId, platform: String) -> Vec<String> {
+        self.handles_on(&account_id, &platform)
+    }
+
+    /// Frees a handle held by an abandoned account so it can be claimed again, dropping the holder's attestation
+    /// for it if there still is one. Emits a `handle_released` event and returns the prior owner.
+    pub fn force_release_handle(&mut self, platform: String, handle: String) -> AccountId {
+        self.assert_any_role(&[Role::Owner, Role::Operator]);
+        let handle_key = (platform, normalize_handle(&handle));
+        let prior_owner = self.handles.get(&handle_key).cloned().unwrap_or_else(|| env::panic_str("handle is not registered"));
+        if self.handles_on(&prior_owner, &handle_key.0).contains(&handle_key.1) {
+            self.remove_handle(&prior_owner, &handle_key.0, &handle_key.1, ReleaseReason::ForceReleased);
+            self.refresh_cached_score(&prior_owner);
+        } else {
+            self.remove_stale_mapping(&handle_key, &prior_owner);
+        }
+        let (platform, handle) = handle_key;
+        events::emit("handle_released", json!({ "platform": platform, "handle": handle, "prior_owner": prior_owner }));
+        prior_owner
+    }
+}
+
+impl Contract {
+    // whether an attestation of `account_id` on the platform still backs the mapping
+    pub(crate) fn handle_is_live(&self, (platform, handle): &(String, String), account_id: &AccountId, now: u64) -> bool {
+        self.socials_on(account_id, platform).iter().any(|social| &social.handle == handle && social.expiry_date >= now)
+    }
+
+    // whether the handle is still the account's to renew: live, or expired within the platform's grace period
+    pub(crate) fn handle_is_reserved(&self, handle_key: &(String, String), account_id: &AccountId, now: u64) -> bool {
+        let grace = self.platform_config(&handle_key.0).grace_period_ns.0;
+        self.handle_is_live(handle_key, account_id, now.saturating_sub(grace))
+    }
+
+    // whether the account's attestation of the handle was issued at or after `since`
+    pub(crate) fn issued_since(&self, (platform, handle): &(String, String), account_id: &AccountId, since: Option<u64>) -> bool {
+        since.is_some_and(|since| self.socials_on(account_id, platform).iter().any(|social| &social.handle == handle && social.issued_date >= since))
+    }
+
+    // the account a handle is reserved for and until when: its holder, for the platform's cooldown after the
+    // attestation expires, or the account that last released it
+    pub(crate) fn handle_cooldown(&self, handle_key: &(String, String)) -> Option<(AccountId, u64)> {
+        let cooldown = self.platform_config(&handle_key.0).release_cooldown_ns.0;
+        if cooldown == 0 {
+            return None;
+        }
+        if let Some(owner) = self.handles.get(handle_key) {
+            let social = self.socials_on(owner, &handle_key.0).into_iter().find(|social| social.handle == handle_key.1)?;
+            return Some((owner.clone(), social.expiry_date.saturating_add(cooldown)));
+        }
+        let released = self.released_handles.get(handle_key)?;
+        Some((released.account_id.clone(), released.released_at.saturating_add(cooldown)))
+    }
+
+    // starts the cooldown of a handle `account_id` just gave up, if its platform has one
+    pub(crate) fn record_release(&mut self, handle_key: (String, String), account_id: &AccountId) {
+        if self.platform_config(&handle_key.0).release_cooldown_ns.0 > 0 {
+            self.released_handles.insert(handle_key, ReleasedHandle { account_id: account_id.clone(), released_at: env::block_timestamp() });
+        }
+    }
+
+    pub(crate) fn clear_release(&mut self, handle_key: &(String, String), account_id: &AccountId) {
+        if self.released_handles.get(handle_key).is_some_and(|released| &released.account_id == account_id) {
+            self.released_handles.remove(handle_key);
+        }
+    }
+
+    // moves a mapping, and the attestation behind it, to the normalized form of its handle
+    fn normalize_handle_mapping(&mut self, (platform, handle): (String, String), account_id: AccountId) {
+        let normalized = normalize_handle(&handle);
+        self.handles.remove(&(platform.clone(), handle.clone()));
+        let mut socials = self.socials_on(&account_id, &platform);
+        let Some(i) = socials.iter().position(|social| social.handle == handle) else {
+            // a stale mapping, the normalized one is added back if nothing claims it
+            let key = (platform, normalized);
+            if !self.handles.contains_key(&key) {
+                self.handles.insert(key, account_id);
+            }
+            return;
+        };
+        socials[i].handle = normalized.clone();
+        let expiry = socials[i].expiry_date;
+        // an account holding both spellings keeps the later one
+        if let Some(j) = (0..socials.len()).find(|&j| j != i && socials[j].handle == normalized) {
+            let dropped = socials.remove(if socials[j].expiry_date >= expiry { i } else { j });
+            self.record_released_handle(&account_id, &platform, &dropped, ReleaseReason::Replaced);
+        }
+        let expiry = socials.iter().find(|social| social.handle == normalized).map_or(expiry, |social| social.expiry_date);
+        self.socials.insert((account_id.clone(), platform.clone()), socials.into_iter().map(Into::into).collect());
+        let key = (platform.clone(), normalized.clone());
+        let other = self.handles.get(&key).filter(|&other| other != &account_id).cloned();
+        let other_expiry = other.as_ref().and_then(|other| self.socials_on(other, &platform).into_iter().find(|social| social.handle == normalized)).map(|social| social.expiry_date);
+        match (other, other_expiry) {
+            (Some(_), Some(other_expiry)) if other_expiry >= expiry => {
+                self.remove_handle(&account_id, &platform, &normalized, ReleaseReason::TakenOver);
+            }
+            (Some(other), Some(_)) => {
+                self.handles.insert(key, account_id.clone());
+                self.remove_handle(&other, &platform, &normalized, ReleaseReason::TakenOver);
+                self.refresh_cached_score(&other);
+            }
+            _ => {
+                self.handles.insert(key, account_id.clone());
+            }
+        }
+        self.refresh_cached_score(&account_id);
+    }
+
+    // drops a mapping no attestation of `account_id` backs anymore, its attestations being for other handles by
+    // now, refunding the account for it
+    fn remove_stale_mapping(&mut self, handle_key: &(String, String), account_id: &AccountId) {
+        self.flush_collections();
+        let usage_before = env::storage_usage();
+        self.handles.remove(handle_key);
+        self.flush_collections();
+        self.release_storage(account_id, usage_before.saturating_sub(env::storage_usage()));
+    }
+
+    pub(crate) fn handles_on(&self, account_id: &AccountId, platform: &str) -> Vec<String> {
+        self.socials_on(account_id, platform).into_iter().map(|social| social.handle).collect()
+    }
+
+    pub(crate) fn holds_attestations(&self, account_id: &AccountId) -> bool {
+        self.social_platforms.contains_key(account_id) || self.access_key_counts.contains_key(account_id) || self.account_ages.contains_key(account_id)
+            || self.passport_scores.contains_key(account_id) || self.stamps.contains_key(account_id) || self.world_ids.contains_key(account_id)
+    }
+
+    // removes `account_id`'s attestations on `platform` along with their handle mappings and records them in its
+    // handle history, during a call made by someone else, refunding the storage freed. returns the bytes freed.
+    pub(crate) fn remove_social(&mut self, account_id: &AccountId, platform: &str, reason: ReleaseReason) -> u64 {
+        self.remove_socials_where(account_id, platform, reason, |_| true)
+    }
+
+    // `remove_social` for just the attestation of `handle`
+    pub(crate) fn remove_handle(&mut self, account_id: &AccountId, platform: &str, handle: &str, reason: ReleaseReason) -> u64 {
+        self.remove_socials_where(account_id, platform, reason, |social| social.handle == handle)
+    }
+
+    fn remove_socials_where(&mut self, account_id: &AccountId, platform: &str, reason: ReleaseReason, matches: impl Fn(&SocialData) -> bool) -> u64 {
+        self.flush_collections();
+        let usage_before = env::storage_usage();
+        let (removed, kept): (Vec<SocialData>, Vec<SocialData>) = self.socials_on(account_id, platform).into_iter().partition(|social| matches(social));
+        for social in removed {
+            self.record_released_handle(account_id, platform, &social, reason);
+            self.expiry_warnings.remove(&(account_id.clone(), platform.to_string(), social.handle.clone()));
+            let handle_key = (platform.to_string(), social.handle);
+            if self.handles.get(&handle_key) == Some(account_id) {
+                self.handles.remove(&handle_key);
+                if matches!(reason, ReleaseReason::Unregistered | ReleaseReason::Revoked) {
+                    self.record_release(handle_key, account_id);
+                }
+            }
+        }
+        let key = (account_id.clone(), platform.to_string());
+        if kept.is_empty() {
+            self.socials.remove(&key);
+            if let Some(platforms) = self.social_platforms.get_mut(account_id) {
+                platforms.retain(|held| held != platform);
+                if platforms.is_empty() {
+                    self.social_platforms.remove(account_id);
+                }
+            }
+            self.release_holder_slot(platform, account_id);
+            if !self.holds_attestations(account_id) {
+                self.accounts.remove(account_id);
+                self.cached_scores.remove(account_id);
+            }
+        } else {
+            self.socials.insert(key, kept.into_iter().map(Into::into).collect());
+        }
+        self.flush_collections();
+        let freed = usage_before.saturating_sub(env::storage_usage());
+        self.release_storage(account_id, freed);
+        freed
+    }
+}
@@ -0,0 +1,42 @@
+use near_sdk::{env, near, AccountId};
+
+use crate::{Contract, ContractExt, HandleRecord, ReleaseReason, SocialData};
+
+const DEFAULT_PAGE_SIZE: u32 = 50;
+
+#[near]
+impl Contract {
+    /// Handles the account held before, oldest first. Entries are only added, so consumers can spot accounts
+    /// cycling through handles; they are dropped only when the account unregisters its storage or erases its
+    /// data with `delete_my_data`.
+    pub fn get_handle_history(&self, account_id: AccountId, from_index: Option<u32>, limit: Option<u32>) -> Vec<HandleRecord> {
+        let len = self.handle_history_len.get(&account_id).copied().unwrap_or(0);
+        let from = from_index.unwrap_or(0);
+        let to = from.saturating_add(limit.unwrap_or(DEFAULT_PAGE_SIZE)).min(len);
+        (from..to).filter_map(|i| self.handle_history.get(&(account_id.clone(), i)).cloned()).collect()
+    }
+}
+
+impl Contract {
+    pub(crate) fn record_released_handle(&mut self, account_id: &AccountId, platform: &str, social: &SocialData, reason: ReleaseReason) {
+        let len = self.handle_history_len.get(account_id).copied().unwrap_or(0);
+        let record = HandleRecord {
+            platform: platform.to_string(),
+            handle: social.handle.clone(),
+            held_from: social.issued_date.into(),
+            held_until: env::block_timestamp().into(),
+            reason_released: reason,
+        };
+        self.handle_history.insert((account_id.clone(), len), record);
+        self.handle_history_len.insert(account_id.clone(), len + 1);
+    }
+
+    // also ends the cooldowns the released handles started, which would otherwise keep naming the account
+    pub(crate) fn clear_handle_history(&mut self, account_id: &AccountId) {
+        for i in 0..self.handle_history_len.remove(account_id).unwrap_or(0) {
+            if let Some(record) = self.handle_history.remove(&(account_id.clone(), i)) {
+                self.clear_release(&(record.platform, record.handle), account_id);
+            }
+        }
+    }
+}
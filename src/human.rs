@@ -0,0 +1,93 @@
+use near_sdk::env::{self, block_timestamp};
+use near_sdk::{near, require, AccountId};
+
+use crate::sbt::SbtMetadata;
+use crate::{Contract, ContractExt, HumanPolicy};
+
+const DEFAULT_TOKENS_LIMIT: u32 = 100;
+
+/// A token as i-am-human registries list them.
+#[near(serializers = [json])]
+pub struct OwnedToken {
+    pub token: u64,
+    pub metadata: SbtMetadata,
+}
+
+// one yes/no answer under the name human registries use, so integrators don't have to pick a check themselves
+#[near]
+impl Contract {
+    /// Whether the account passes the owner's `HumanPolicy`. Banned accounts never do.
+    pub fn is_human(&self, account_id: AccountId) -> bool {
+        if self.bans.contains_key(&account_id) {
+            return false;
+        }
+        let now = block_timestamp();
+        match &self.human_policy {
+            HumanPolicy::MinScore(min) => self.compute_score(&account_id, now) >= *min,
+            HumanPolicy::Rule(rule_id) => self.rules.get(rule_id).is_some_and(|rule| self.satisfies(&account_id, rule, now)),
+        }
+    }
+
+    pub fn set_human_policy(&mut self, policy: HumanPolicy) {
+        self.assert_owner();
+        if let HumanPolicy::Rule(rule_id) = &policy {
+            require!(self.rules.contains_key(rule_id), format!("unknown rule {}", rule_id));
+        }
+        self.human_policy = policy;
+    }
+
+    pub fn get_human_policy(&self) -> HumanPolicy {
+        self.human_policy.clone()
+    }
+
+    // the queries of the i-am-human registry, so consumers can list this contract as a registry without code
+    // changes. the account holds one token of each `SbtConfig` class whose rule it satisfies right now, its id
+    // the class, expiring with the account's earliest active attestation like the tokens `mint_sbt` mints.
+
+    /// `[(this contract, tokens)]` ordered by class from `from_class`, empty if the account holds none or
+    /// `issuer` names another contract.
+    pub fn sbt_tokens_by_owner(&self, account: AccountId, issuer: Option<AccountId>, from_class: Option<u64>, limit: Option<u32>, with_expired: Option<bool>) -> Vec<(AccountId, Vec<OwnedToken>)> {
+        if issuer.is_some_and(|issuer| issuer != env::current_account_id()) {
+            return Vec::new();
+        }
+        let now_ms = block_timestamp() / 1_000_000;
+        let tokens: Vec<OwnedToken> = self
+            .held_classes(&account)
+            .into_iter()
+            .filter(|(class, expires_at)| *class >= from_class.unwrap_or(0) && (with_expired == Some(true) || expires_at.is_none_or(|expires_at| expires_at > now_ms)))
+            .take(limit.unwrap_or(DEFAULT_TOKENS_LIMIT) as usize)
+            .map(|(class, expires_at)| OwnedToken {
+                token: class,
+                metadata: SbtMetadata { class, issued_at: None, expires_at, reference: None, reference_hash: None },
+            })
+            .collect();
+        if tokens.is_empty() { Vec::new() } else { vec![(env::current_account_id(), tokens)] }
+    }
+
+    /// How many tokens `sbt_tokens_by_owner` lists for the account, only those of `class` if set.
+    pub fn sbt_supply_by_owner(&self, account: AccountId, issuer: AccountId, class: Option<u64>) -> u64 {
+        if issuer != env::current_account_id() {
+            return 0;
+        }
+        self.held_classes(&account).into_iter().filter(|(held, _)| class.is_none_or(|class| class == *held)).count() as u64
+    }
+}
+
+impl Contract {
+    // (class, expiry in milliseconds) of each configured class whose rule the account satisfies, by class
+    fn held_classes(&self, account_id: &AccountId) -> Vec<(u64, Option<u64>)> {
+        let Some(config) = &self.sbt_config else { return Vec::new() };
+        if self.bans.contains_key(account_id) {
+            return Vec::new();
+        }
+        let now = block_timestamp();
+        let mut classes: Vec<u64> = config.classes.iter()
+            .filter(|(rule_id, _)| self.rules.get(rule_id).is_some_and(|rule| self.satisfies(account_id, rule, now)))
+            .map(|(_, class)| *class)
+            .collect();
+        classes.sort_unstable();
+        classes.dedup();
+        let expires_at = self.credential_expiry(account_id, now).map(|expiry| expiry / 1_000_000);
+        classes.into_iter().map(|class| (class, expires_at)).collect()
+    }
+}
@@ -0,0 +1,47 @@
+use near_sdk::serde_json::json;
+use near_sdk::{env, near, require, AccountId};
+
+use crate::{events, Contract, ContractError, ContractExt, FunctionError};
+
+// methods acting for the transaction signer only accept calls the signer made directly, so a contract the user
+// happens to call can't register data in their name. accounts that do use an app contract allow it here.
+#[near]
+impl Contract {
+    /// Lets `intermediary` forward the caller's registrations and other signer-keyed calls. Must be called
+    /// directly. The storage it takes is paid from the caller's storage balance and the attached deposit.
+    #[payable]
+    pub fn allow_intermediary(&mut self, intermediary: AccountId) {
+        let account_id = env::predecessor_account_id();
+        require!(account_id == env::signer_account_id(), "intermediaries can only be allowed in a direct call");
+        require!(account_id != intermediary, "an account can't be its own intermediary");
+        let usage_before = env::storage_usage();
+        self.intermediaries.insert((account_id.clone(), intermediary.clone()));
+        events::emit("intermediary_allowed", json!({ "account_id": account_id, "intermediary": intermediary }));
+        self.settle_storage(&account_id, usage_before).unwrap_or_else(|err| err.panic());
+    }
+
+    pub fn revoke_intermediary(&mut self, intermediary: AccountId) {
+        let account_id = env::predecessor_account_id();
+        let usage_before = env::storage_usage();
+        require!(self.intermediaries.remove(&(account_id.clone(), intermediary.clone())), "intermediary is not allowed");
+        events::emit("intermediary_revoked", json!({ "account_id": account_id, "intermediary": intermediary }));
+        self.release_storage(&account_id, usage_before.saturating_sub(env::storage_usage()));
+    }
+
+    pub fn is_intermediary_allowed(&self, account_id: AccountId, intermediary: AccountId) -> bool {
+        self.intermediaries.contains(&(account_id, intermediary))
+    }
+}
+
+impl Contract {
+    // the signer a call acts for, provided it made the call itself or through an intermediary it allowed
+    pub(crate) fn acting_account(&self) -> Result<AccountId, ContractError> {
+        let signer = env::signer_account_id();
+        let predecessor = env::predecessor_account_id();
+        if predecessor == signer || self.intermediaries.contains(&(signer.clone(), predecessor)) {
+            Ok(signer)
+        } else {
+            Err(ContractError::IndirectCall)
+        }
+    }
+}
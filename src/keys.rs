@@ -0,0 +1,58 @@
+#[cfg(feature = "dalek-verify")]
+use ed25519_dalek::Verifier;
+use near_sdk::{near, CurveType, PublicKey};
+
+use crate::ContractError;
+
+const SIGNATURE_LENGTH: usize = 64;
+
+/// An ed25519 key validated once when it is stored, so verifying against it needs no curve checks or slicing.
+#[near(serializers = [borsh])]
+#[derive(Clone, PartialEq, Eq)]
+pub struct VerifyingKey {
+    public_key: PublicKey,
+    bytes: [u8; 32],
+}
+
+impl VerifyingKey {
+    // rejects secp256k1 keys, and with `dalek-verify` also bytes that aren't a valid ed25519 point. without it an
+    // invalid point is only caught by every verification against it failing.
+    pub(crate) fn new(public_key: PublicKey) -> Result<Self, ContractError> {
+        if public_key.curve_type() != CurveType::ED25519 {
+            return Err(ContractError::UnsupportedKeyType);
+        }
+        let bytes: [u8; 32] = public_key.as_bytes()[1..].try_into().map_err(|_| ContractError::MalformedPublicKey)?;
+        #[cfg(feature = "dalek-verify")]
+        ed25519_dalek::VerifyingKey::from_bytes(&bytes).map_err(|_| ContractError::MalformedPublicKey)?;
+        Ok(Self { public_key, bytes })
+    }
+
+    pub(crate) fn public_key(&self) -> &PublicKey {
+        &self.public_key
+    }
+
+    pub(crate) fn verify(&self, message: &[u8], signature: &[u8]) -> Result<(), ContractError> {
+        let signature: &[u8; SIGNATURE_LENGTH] = signature
+            .try_into()
+            .map_err(|_| ContractError::InvalidSignatureLength { expected: SIGNATURE_LENGTH, got: signature.len() })?;
+        self.verify_signature(message, signature)
+    }
+
+    #[cfg(feature = "dalek-verify")]
+    fn verify_signature(&self, message: &[u8], signature: &[u8; SIGNATURE_LENGTH]) -> Result<(), ContractError> {
+        let signature = ed25519_dalek::Signature::from_slice(signature).map_err(|_| ContractError::MalformedSignature)?;
+        let public_key = ed25519_dalek::VerifyingKey::from_bytes(&self.bytes).map_err(|_| ContractError::MalformedPublicKey)?;
+        public_key.verify(message, &signature).map_err(|_| ContractError::UnverifiedData)
+    }
+
+    // the host function, so the wasm doesn't have to bundle the curve arithmetic
+    #[cfg(not(feature = "dalek-verify"))]
+    fn verify_signature(&self, message: &[u8], signature: &[u8; SIGNATURE_LENGTH]) -> Result<(), ContractError> {
+        if near_sdk::env::ed25519_verify(signature, message, &self.bytes) { Ok(()) } else { Err(ContractError::UnverifiedData) }
+    }
+}
+
+// verifies against a caller supplied key, e.g. a user's own key for NEP-413 or a linked address
+pub(crate) fn verify_ed25519(key: &PublicKey, message: &[u8], signature: &[u8]) -> Result<(), ContractError> {
+    VerifyingKey::new(key.clone())?.verify(message, signature)
+}
@@ -1,271 +1,963 @@
-use std::collections::HashMap;
+// entrypoints take the signed payload fields as flat arguments, which the generated ext wrappers mirror
+#![allow(clippy::too_many_arguments)]
 
-use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
-use near_sdk::collections::UnorderedMap;
+#[cfg(feature = "contract")]
+use std::collections::BTreeMap;
+
+#[cfg(feature = "contract")]
+use near_sdk::store::{IterableMap, IterableSet, LookupMap, LookupSet, Vector};
+#[cfg(feature = "contract")]
 use near_sdk::env::block_timestamp;
-use near_sdk::{env, require, AccountId, PanicOnDefault, PublicKey};
-use near_sdk::near_bindgen;
-use ed25519_dalek::Verifier;
+#[cfg(feature = "contract")]
+use near_sdk::json_types::{Base64VecU8, U64};
+#[cfg(feature = "contract")]
+use near_sdk::serde_json::json;
+#[cfg(feature = "contract")]
+use near_sdk::{env, near, AccountId, FunctionError, NearToken, PanicOnDefault, Promise, PublicKey};
 
-#[near_bindgen]
-#[derive(BorshDeserialize, BorshSerialize, PanicOnDefault)]
-pub struct Contract {
-  records: UnorderedMap<AccountId, UserData>,
-  handles: UnorderedMap<(String, String), AccountId>, // map platform + handle to account_id
-  admin_pub: PublicKey
-}
+#[cfg(feature = "contract")]
+use crate::admin_keys::KeyRotation;
+#[cfg(feature = "contract")]
+use crate::circuit_breaker::BreakerState;
+#[cfg(feature = "contract")]
+use crate::commit_reveal::Commitment;
+#[cfg(feature = "contract")]
+use crate::dashboard::IssuanceStats;
+#[cfg(feature = "contract")]
+use crate::expiry::DEFAULT_EXPIRY_WARNING_NS;
+#[cfg(feature = "contract")]
+use crate::handles::{normalize_handle, ReleasedHandle, MAX_HANDLES_PER_PLATFORM};
+#[cfg(feature = "contract")]
+use crate::keys::VerifyingKey;
+#[cfg(feature = "contract")]
+use crate::migrations::{LegacyCollections, STATE_VERSION};
+#[cfg(feature = "contract")]
+use crate::platforms::HolderSlot;
+#[cfg(feature = "contract")]
+use crate::rate_limit::EpochWrites;
+#[cfg(feature = "contract")]
+use crate::score::YEAR_NS;
+#[cfg(feature = "contract")]
+use crate::snapshot::{RuleSnapshotBuilder, SnapshotBuilder, MEMBER_HASH_LEN};
+#[cfg(feature = "contract")]
+use crate::tiers::default_tiers;
+#[cfg(feature = "contract")]
+use crate::stats::{Activity, PlatformCounts};
+#[cfg(feature = "contract")]
+use crate::storage::{StorageAccount, StorageKey};
+#[cfg(feature = "contract")]
+use crate::timelock::{Timelock, DEFAULT_TIMELOCK_DELAY_NS};
+#[cfg(feature = "contract")]
+use crate::versioned::VersionedSocialData;
+#[cfg(feature = "contract")]
+use crate::vouches::VouchActivity;
+
+#[cfg(feature = "contract")]
+pub use crate::errors::ContractError;
+#[cfg(feature = "contract")]
+pub use crate::messages::RequestBound;
+#[cfg(feature = "types")]
+pub use crate::types::*;
 
-#[derive(BorshSerialize, BorshDeserialize)]
-struct UserData {
-  access_key_count: Option<u32>,
-  account_age: Option<u128> ,
-  socials: HashMap<String, SocialData> //platform_name -> platform_data
-  // other fields
+#[cfg(feature = "contract")]
+mod admin_keys;
+#[cfg(feature = "contract")]
+mod allowlist;
+#[cfg(feature = "contract")]
+mod badges;
+#[cfg(feature = "contract")]
+mod bans;
+#[cfg(feature = "contract")]
+mod cached_score;
+#[cfg(feature = "contract")]
+mod chain_signatures;
+#[cfg(feature = "contract")]
+mod circuit_breaker;
+#[cfg(feature = "contract")]
+mod commit_reveal;
+#[cfg(feature = "contract")]
+mod consumers;
+#[cfg(feature = "contract")]
+mod dashboard;
+#[cfg(feature = "contract")]
+mod deposits;
+#[cfg(feature = "contract")]
+mod disputes;
+#[cfg(feature = "contract")]
+mod ed25519_links;
+#[cfg(feature = "contract")]
+mod errors;
+#[cfg(feature = "contract")]
+mod events;
+#[cfg(feature = "contract")]
+mod evm;
+#[cfg(feature = "contract")]
+mod expiry;
+#[cfg(feature = "contract")]
+mod freshness;
+#[cfg(feature = "contract")]
+mod ft_fees;
+#[cfg(feature = "contract")]
+mod governance;
+#[cfg(feature = "contract")]
+mod handles;
+#[cfg(feature = "contract")]
+mod history;
+#[cfg(feature = "contract")]
+mod human;
+#[cfg(feature = "contract")]
+mod intermediaries;
+#[cfg(feature = "contract")]
+mod keys;
+#[cfg(feature = "contract")]
+mod limits;
+#[cfg(feature = "contract")]
+mod linked_accounts;
+#[cfg(feature = "contract")]
+mod messages;
+#[cfg(feature = "contract")]
+mod migrations;
+#[cfg(feature = "contract")]
+mod near_social;
+#[cfg(feature = "contract")]
+mod nep413;
+#[cfg(feature = "contract")]
+mod nft_badges;
+#[cfg(feature = "contract")]
+mod notify;
+#[cfg(feature = "contract")]
+mod optimistic;
+#[cfg(feature = "contract")]
+mod oracle;
+#[cfg(feature = "contract")]
+mod ownership;
+#[cfg(feature = "contract")]
+mod passport;
+#[cfg(feature = "contract")]
+mod pause;
+#[cfg(feature = "contract")]
+mod platforms;
+#[cfg(feature = "contract")]
+mod private_handles;
+#[cfg(feature = "contract")]
+mod profile;
+#[cfg(feature = "contract")]
+mod proofs;
+#[cfg(feature = "contract")]
+mod quadratic_funding;
+#[cfg(feature = "contract")]
+mod rate_limit;
+#[cfg(feature = "contract")]
+mod revocation;
+#[cfg(feature = "contract")]
+mod roles;
+#[cfg(feature = "contract")]
+mod rules;
+#[cfg(feature = "contract")]
+mod sampling;
+#[cfg(feature = "contract")]
+mod sbt;
+#[cfg(feature = "contract")]
+mod score;
+#[cfg(feature = "contract")]
+mod snapshot;
+#[cfg(feature = "contract")]
+mod social_badges;
+#[cfg(feature = "contract")]
+mod stamps;
+#[cfg(feature = "contract")]
+mod stats;
+#[cfg(feature = "contract")]
+mod storage;
+#[cfg(feature = "contract")]
+mod storage_management;
+#[cfg(feature = "contract")]
+mod subscriptions;
+#[cfg(feature = "test-utils")]
+pub mod test_utils;
+#[cfg(feature = "contract")]
+mod thresholds;
+#[cfg(feature = "contract")]
+mod tiers;
+#[cfg(feature = "contract")]
+mod timelock;
+#[cfg(feature = "contract")]
+mod treasury;
+#[cfg(feature = "contract")]
+mod verifier_bonds;
+#[cfg(feature = "contract")]
+mod verifiers;
+#[cfg(feature = "contract")]
+mod versioned;
+#[cfg(feature = "contract")]
+mod vouches;
+#[cfg(feature = "contract")]
+mod world_id;
+#[cfg(feature = "types")]
+pub mod types;
+
+// number of failed registration attempts kept per account, oldest are dropped first.
+#[cfg(feature = "contract")]
+const MAX_FAILED_ATTEMPTS: usize = 10;
+
+#[cfg(feature = "contract")]
+#[near(contract_state)]
+#[derive(PanicOnDefault)]
+pub struct Contract {
+  state_version: u32, // must stay the first field, see `migrations`
+  accounts: IterableSet<AccountId>, // every account holding at least one attestation
+  handles: IterableMap<(String, String), AccountId>, // map platform + handle to account_id
+  admin_pub: VerifyingKey,
+  failed_attempts: LookupMap<AccountId, Vec<FailedAttempt>>,
+  used_nonces: LookupSet<[u8; 32]>, // NEP-413 nonces consumed by relayed registrations
+  platforms: IterableMap<String, Platform>, // the platform registry, see `add_platform`
+  evm_owners: LookupMap<String, AccountId>, // linked evm address -> account_id
+  ed25519_owners: LookupMap<(String, String), AccountId>, // chain + linked address -> account_id
+  issuance: IssuanceStats,
+  holders: LookupMap<String, Vec<HolderSlot>>, // holders of platforms with a `max_holders` cap
+  key_rotation: KeyRotation,
+  timelock: Timelock,
+  pending_changes: IterableMap<u64, PendingChange>,
+  // attested attributes are stored per attribute so each check only loads what it needs
+  access_key_counts: LookupMap<AccountId, u32>,
+  account_ages: LookupMap<AccountId, u64>, // account creation timestamp in nanoseconds
+  socials: LookupMap<(AccountId, String), Vec<VersionedSocialData>>, // account_id + platform_name -> one entry per handle
+  social_platforms: LookupMap<AccountId, Vec<String>>, // platforms with an entry in `socials`, for iterating an account's socials
+  evm_addresses: LookupMap<AccountId, Vec<String>>, // lowercase 0x-prefixed addresses proven via ecrecover
+  ed25519_addresses: LookupMap<AccountId, Vec<LinkedAddress>>,
+  input_limits: InputLimits,
+  storage_balances: LookupMap<AccountId, StorageAccount>, // NEP-145 deposits, including what backs the account's stored data
+  handle_purge_cursor: u32, // index into `handles` the next `purge_expired_handles` call starts at
+  owner_id: AccountId, // may call the admin methods, see `ownership`
+  pending_owner_id: Option<AccountId>,
+  verifiers: Vec<VerifyingKey>, // accepted alongside `admin_pub`, see `verifiers`
+  roles: LookupSet<(Role, AccountId)>, // verifier and operator grants, the owner is `owner_id`
+  paused: bool,
+  paused_methods: u32, // `PAUSE_*` flags of individually paused entrypoints
+  thresholds: IterableMap<String, u32>, // named minimum access key counts, see `thresholds`
+  revocations: LookupMap<(AccountId, String), Revocation>, // latest revocation per account_id + platform
+  bans: LookupMap<AccountId, Ban>,
+  registration_fee: u128, // yoctoNEAR charged per accepted registration on top of storage
+  treasury: u128, // collected fees not yet withdrawn
+  min_deposit: u128, // yoctoNEAR the payable attestation methods require attached
+  activity: Activity, // registration and revocation counters behind `stats`
+  platform_counts: IterableMap<String, PlatformCounts>,
+  snapshot_builder: Option<SnapshotBuilder>, // snapshot being built by `commit_snapshot`
+  latest_snapshot: Option<MerkleSnapshot>,
+  handle_history: LookupMap<(AccountId, u32), HandleRecord>, // account_id + index -> released handle, see `history`
+  handle_history_len: LookupMap<AccountId, u32>,
+  released_handles: LookupMap<(String, String), ReleasedHandle>, // platform + handle -> last holder, during its cooldown
+  handle_normalize_cursor: u32, // index into `handles` the next `normalize_stored_handles` call starts at
+  optimistic_claims: LookupMap<(String, String), OptimisticClaim>, // platform + normalized handle -> pending claim
+  near_social_id: AccountId, // SocialDB contract `verify_near_social` reads profiles from
+  vouches: LookupMap<AccountId, Vec<Vouch>>, // vouches an account received, see `vouches`
+  vouch_activity: LookupMap<AccountId, VouchActivity>,
+  vouch_config: VouchConfig,
+  link_requests: LookupSet<(AccountId, AccountId)>, // requester + account asked to confirm, see `link_account`
+  identity_of: LookupMap<AccountId, AccountId>, // linked account -> primary account of its identity
+  identity_members: LookupMap<AccountId, Vec<AccountId>>, // primary account -> every account of the identity
+  resolve_linked: bool, // whether checks see the attestations of a whole identity
+  merged_accounts: LookupMap<AccountId, AccountId>, // merged account -> account it merged into, see `merge_into`
+  profile_metadata: LookupMap<AccountId, BTreeMap<String, String>>, // self-set display fields, see `profile`
+  consumers: IterableSet<AccountId>, // contracts allowed to read `get_account_details`
+  consumer_fee: u128, // yoctoNEAR charged per `get_account_details` call
+  intermediaries: LookupSet<(AccountId, AccountId)>, // account + contract allowed to call for it, see `intermediaries`
+  max_writes_per_epoch: u32, // verifier backed writes an account may get accepted per epoch, 0 for no limit
+  epoch_writes: LookupMap<AccountId, EpochWrites>,
+  circuit_breaker: Option<CircuitBreakerConfig>, // registration ceiling, see `circuit_breaker`
+  breaker: BreakerState,
+  disputes: IterableMap<u64, Dispute>, // open disputes by id, see `disputes`
+  next_dispute_id: u64,
+  dispute_bond: u128, // yoctoNEAR `file_dispute` requires attached
+  verifier_bonds: LookupMap<PublicKey, VerifierBond>, // see `verifier_bonds`
+  verifier_bond_config: VerifierBondConfig,
+  commitments: LookupMap<AccountId, Commitment>, // pending `commit_social` of each account
+  expiry_warning_ns: u64, // how long before expiry `expiring_soon` is emitted, 0 when off
+  expiry_warnings: LookupMap<(AccountId, String, String), u64>, // (account, platform, handle) -> expiry last warned about
+  attribute_updates: LookupMap<AccountId, AttributeUpdates>,
+  revocation_ledger: Vector<RevocationRecord>, // every revoked handle, append only
+  account_revocations: LookupMap<AccountId, Vec<u32>>, // indices into `revocation_ledger` per account
+  score_weights: ScoreWeights,
+  cached_scores: LookupMap<AccountId, CachedScore>,
+  rules: IterableMap<String, RuleExpr>, // named eligibility rules, see `rules`
+  badges: LookupMap<AccountId, Vec<Badge>>,
+  tiers: Vec<Tier>, // by increasing `min_score`
+  qf_config: QfConfig,
+  passport_scores: LookupMap<AccountId, PassportScore>,
+  stamps: LookupMap<AccountId, Vec<Stamp>>,
+  stamp_schemas: IterableMap<String, StampSchema>,
+  rule_snapshot_builder: Option<RuleSnapshotBuilder>, // snapshot being built by `commit_rule_snapshot`
+  rule_snapshots: Vector<RuleSnapshot>,
+  rule_snapshot_members: LookupSet<(u32, [u8; MEMBER_HASH_LEN])>, // (snapshot id, truncated sha256 of the account)
+  human_policy: HumanPolicy,
+  sbt_config: Option<SbtConfig>,
+  sbt_tokens: LookupMap<(AccountId, String), u64>, // (account, rule id) -> registry token id
+  subscriptions: LookupMap<AccountId, u128>, // consumer -> prepaid yoctoNEAR for paid queries
+  verify_fee: u128, // yoctoNEAR charged per `verify_for` call
+  attestation_fee: u128, // yoctoNEAR charged per `request_attestation` call
+  receipt_count: u64, // the next `request_attestation` receipt id
+  fee_tokens: IterableMap<AccountId, u128>, // token -> units of it per NEAR of fees
+  ft_fee_balances: LookupMap<(AccountId, AccountId), u128>, // (account, token) -> prepaid fee credit
+  ft_treasury: LookupMap<AccountId, u128>, // token -> fees collected in it
+  nft_badge_config: Option<NftBadgeConfig>,
+  minted_nfts: LookupMap<AccountId, Vec<MintedNft>>,
+  social_badge_publishers: LookupSet<AccountId>, // accounts mirroring their badges into SocialDB
+  mpc_config: Option<MpcConfig>,
+  evm_attestation_nonces: LookupMap<AccountId, u64>, // the next nonce per account
+  pending_evm_attestations: LookupMap<AccountId, EvmAttestation>, // awaiting the signer's response
+  evm_attestations: LookupMap<AccountId, SignedEvmAttestation>, // the latest signed per account
+  world_ids: LookupMap<AccountId, WorldId>,
+  world_id_nullifiers: LookupMap<String, AccountId>, // nullifier hash -> the account it backs
+  legacy_v1: Option<LegacyCollections>, // version 1 data still to be moved, see `migrate_v1_batch`
+  merged_world_id_nullifiers: LookupMap<AccountId, Vec<String>>, // nullifiers of merged accounts the account keeps spent
+  claims_of: LookupMap<AccountId, Vec<(String, String)>>, // platform + handle of each pending claim, by claimant
 }
 
-#[derive(BorshDeserialize, BorshSerialize, PanicOnDefault)]
+#[cfg(feature = "contract")]
+#[near(serializers = [borsh])]
+#[derive(Clone, PanicOnDefault)]
 pub struct SocialData {
     pub issued_date: u64, 
     pub handle: String,
-    pub proof: String,
-    pub expiry_date: u64
+    pub proof_digest: [u8; 32], // sha256 of the proof
+    pub proof_type: String,
+    pub expiry_date: u64,
+    pub provenance: Provenance,
 }
 
-
-#[near_bindgen]
+#[cfg(feature = "contract")]
+#[near]
 impl Contract {
     #[init]
     pub fn new(pub_key: PublicKey) -> Self {
-        Self {
-            records: UnorderedMap::new(b"r".to_vec()),
-            handles: UnorderedMap::new(b"h".to_vec()),
-            admin_pub: pub_key,
+        let admin_pub = VerifyingKey::new(pub_key).unwrap_or_else(|err| err.panic());
+        let mut contract = Self {
+            state_version: STATE_VERSION,
+            accounts: IterableSet::new(StorageKey::Accounts),
+            handles: IterableMap::new(StorageKey::Handles),
+            admin_pub,
+            failed_attempts: LookupMap::new(StorageKey::FailedAttempts),
+            used_nonces: LookupSet::new(StorageKey::UsedNonces),
+            platforms: IterableMap::new(StorageKey::Platforms),
+            evm_owners: LookupMap::new(StorageKey::EvmOwners),
+            ed25519_owners: LookupMap::new(StorageKey::Ed25519Owners),
+            issuance: IssuanceStats::default(),
+            holders: LookupMap::new(StorageKey::Holders),
+            key_rotation: KeyRotation::default(),
+            timelock: Timelock::new(DEFAULT_TIMELOCK_DELAY_NS),
+            pending_changes: IterableMap::new(StorageKey::PendingChanges),
+            access_key_counts: LookupMap::new(StorageKey::AccessKeyCounts),
+            account_ages: LookupMap::new(StorageKey::AccountAges),
+            socials: LookupMap::new(StorageKey::Socials),
+            social_platforms: LookupMap::new(StorageKey::SocialPlatforms),
+            evm_addresses: LookupMap::new(StorageKey::EvmAddresses),
+            ed25519_addresses: LookupMap::new(StorageKey::Ed25519Addresses),
+            input_limits: InputLimits::default(),
+            storage_balances: LookupMap::new(StorageKey::StorageBalances),
+            handle_purge_cursor: 0,
+            owner_id: env::current_account_id(),
+            pending_owner_id: None,
+            verifiers: Vec::new(),
+            roles: LookupSet::new(StorageKey::Roles),
+            paused: false,
+            paused_methods: 0,
+            thresholds: IterableMap::new(StorageKey::Thresholds),
+            revocations: LookupMap::new(StorageKey::Revocations),
+            bans: LookupMap::new(StorageKey::Bans),
+            registration_fee: 0,
+            treasury: 0,
+            min_deposit: 0,
+            activity: Activity::default(),
+            platform_counts: IterableMap::new(StorageKey::PlatformCounts),
+            snapshot_builder: None,
+            latest_snapshot: None,
+            handle_history: LookupMap::new(StorageKey::HandleHistory),
+            handle_history_len: LookupMap::new(StorageKey::HandleHistoryLen),
+            released_handles: LookupMap::new(StorageKey::ReleasedHandles),
+            handle_normalize_cursor: 0,
+            optimistic_claims: LookupMap::new(StorageKey::OptimisticClaims),
+            near_social_id: near_social::DEFAULT_NEAR_SOCIAL_ID.parse().unwrap(),
+            vouches: LookupMap::new(StorageKey::Vouches),
+            vouch_activity: LookupMap::new(StorageKey::VouchActivity),
+            vouch_config: VouchConfig::default(),
+            link_requests: LookupSet::new(StorageKey::LinkRequests),
+            identity_of: LookupMap::new(StorageKey::IdentityOf),
+            identity_members: LookupMap::new(StorageKey::IdentityMembers),
+            resolve_linked: false,
+            merged_accounts: LookupMap::new(StorageKey::MergedAccounts),
+            profile_metadata: LookupMap::new(StorageKey::ProfileMetadata),
+            consumers: IterableSet::new(StorageKey::Consumers),
+            consumer_fee: 0,
+            intermediaries: LookupSet::new(StorageKey::Intermediaries),
+            max_writes_per_epoch: 0,
+            epoch_writes: LookupMap::new(StorageKey::EpochWrites),
+            circuit_breaker: None,
+            breaker: BreakerState::default(),
+            disputes: IterableMap::new(StorageKey::Disputes),
+            next_dispute_id: 0,
+            dispute_bond: 0,
+            verifier_bonds: LookupMap::new(StorageKey::VerifierBonds),
+            verifier_bond_config: VerifierBondConfig::default(),
+            commitments: LookupMap::new(StorageKey::Commitments),
+            expiry_warning_ns: DEFAULT_EXPIRY_WARNING_NS,
+            expiry_warnings: LookupMap::new(StorageKey::ExpiryWarnings),
+            attribute_updates: LookupMap::new(StorageKey::AttributeUpdates),
+            revocation_ledger: Vector::new(StorageKey::RevocationLedger),
+            account_revocations: LookupMap::new(StorageKey::AccountRevocations),
+            score_weights: ScoreWeights::default(),
+            cached_scores: LookupMap::new(StorageKey::CachedScores),
+            rules: IterableMap::new(StorageKey::Rules),
+            badges: LookupMap::new(StorageKey::Badges),
+            tiers: default_tiers(),
+            qf_config: QfConfig::default(),
+            passport_scores: LookupMap::new(StorageKey::PassportScores),
+            stamps: LookupMap::new(StorageKey::Stamps),
+            stamp_schemas: IterableMap::new(StorageKey::StampSchemas),
+            rule_snapshot_builder: None,
+            rule_snapshots: Vector::new(StorageKey::RuleSnapshots),
+            rule_snapshot_members: LookupSet::new(StorageKey::RuleSnapshotMembers),
+            human_policy: HumanPolicy::default(),
+            sbt_config: None,
+            sbt_tokens: LookupMap::new(StorageKey::SbtTokens),
+            subscriptions: LookupMap::new(StorageKey::Subscriptions),
+            verify_fee: 0,
+            attestation_fee: 0,
+            receipt_count: 0,
+            fee_tokens: IterableMap::new(StorageKey::FeeTokens),
+            ft_fee_balances: LookupMap::new(StorageKey::FtFeeBalances),
+            ft_treasury: LookupMap::new(StorageKey::FtTreasury),
+            nft_badge_config: None,
+            minted_nfts: LookupMap::new(StorageKey::MintedNfts),
+            social_badge_publishers: LookupSet::new(StorageKey::SocialBadgePublishers),
+            mpc_config: None,
+            evm_attestation_nonces: LookupMap::new(StorageKey::EvmAttestationNonces),
+            pending_evm_attestations: LookupMap::new(StorageKey::PendingEvmAttestations),
+            evm_attestations: LookupMap::new(StorageKey::EvmAttestations),
+            world_ids: LookupMap::new(StorageKey::WorldIds),
+            world_id_nullifiers: LookupMap::new(StorageKey::WorldIdNullifiers),
+            legacy_v1: None,
+            merged_world_id_nullifiers: LookupMap::new(StorageKey::MergedWorldIdNullifiers),
+            claims_of: LookupMap::new(StorageKey::ClaimsOf),
+        };
+        for (name, min) in thresholds::DEFAULT_THRESHOLDS {
+            contract.thresholds.insert(name.to_string(), min);
         }
+        for (id, display_name) in allowlist::DEFAULT_PLATFORMS {
+            let platform = Platform { id: id.to_string(), display_name: display_name.to_string(), config: PlatformConfig::default(), verifier_key: None, enabled: true, oracle_id: None };
+            contract.platforms.insert(id.to_string(), platform);
+        }
+        contract
     }
 
+    /// The attached deposit must cover `get_min_deposit` plus the registration fee (see `get_fee`). The
+    /// storage the registration adds is paid from the account's available storage balance first, then from the
+    /// rest of the deposit, any excess of which is refunded.
+    /// A failed registration reverts with its error, emitted as `registration_failed` for indexers first. The
+    /// account can keep it in `get_failed_attempts` with `report_failed_attempt`.
+    /// The handle is stored trimmed, lowercased and in Unicode NFC, so spellings differing only in case or
+    /// composition claim the same handle. The verifier signs it as submitted. Once normalized, it must match
+    /// the platform's `handle_rules` if it has any.
+    /// The verifier may grant `validity_ns` in place of the platform's, signing it into the payload as
+    /// `validity:<validity_ns>`, or `validity:0` to keep the platform's. It is bounded by the platform's
+    /// `max_validity_ns`, or without one by its `validity_ns`.
+    /// The attestation is for the signer, so a call forwarded by another contract panics unless the signer
+    /// allowed that contract with `allow_intermediary`. The same holds for the other signer-keyed methods.
     #[payable]
-    pub fn register_social(&mut self, platform: String, signature: Vec<u8>, handle: String, proof: String, max_block_height: u64) {
-        
-        // basically, need to assert early that handle is not already registered nor has it expired, before other computations.
-        require!(max_block_height > env::block_height(), "expired request");
-        let account_id = env::signer_account_id();
-        let user_dat = self.records.get(&account_id); // get user records
-        if user_dat.is_some() { // if record exists, assert that handle is not already registered nor has it expired.
-
-            assert!(!self.handles.get(&(platform.clone(), handle.clone())).is_some() || user_dat.as_ref().unwrap().socials.get(&platform).map_or(false, |x| x.expiry_date < block_timestamp()), "handle already registered");
-        } else {
-            assert!(!self.handles.get(&(platform.clone(), handle.clone())).is_some(), "handle already registered");
-        }
-        let signature = ed25519_dalek::Signature::try_from(signature.as_ref()).expect("invalid SIg.");
-        let public_key = ed25519_dalek::PublicKey::from_bytes(&self.admin_pub.as_bytes()[1..]).unwrap();
-        let message = account_id.to_string() + "," + platform.as_str() + "," + handle.as_str() + "," + proof.as_str() + "," + max_block_height.to_string().as_str();
-        let vfg = public_key.verify(message.as_bytes(), &signature).is_ok();
-        assert!(vfg, "unverified data");
-        let expiry_date = block_timestamp() + 3 * 30 * 24 * 60 * 60 * 1_000_000_000;// 3 months (make more dynamic later)
-        self.handles.insert(&(platform.clone(), handle.clone()), &account_id);
-        if let Some( mut user_data) = user_dat {
-            if let Some(social_data) = user_data.socials.get(&platform) {
-                if social_data.proof == proof {env::panic_str("incorrect proof")};
-            }
-            let sd = SocialData { issued_date: env::block_timestamp(), handle, proof, expiry_date };
-            user_data.socials.insert(platform, sd);
-            self.records.insert(&account_id, &user_data);  
-        } else {
-            let user_data = UserData {
-                access_key_count: None,
-                account_age: None,
-                socials: HashMap::from([
-                    (platform, SocialData { issued_date: env::block_timestamp(), handle, proof, expiry_date })
-                ])
-            };
-            self.records.insert(&account_id, &user_data); 
-        }
-        
+    #[handle_result]
+    pub fn register_social(&mut self, platform: String, signature: Vec<u8>, handle: String, proof: String, max_block_height: Option<u64>, valid_until_timestamp_ns: Option<U64>, validity_ns: Option<U64>) -> Result<(), ContractError> {
+        self.ensure_not_paused(PAUSE_REGISTER_SOCIAL)?;
+        self.ensure_deposit(self.registration_fee_due())?;
+        self.check_input_limits(&platform, &handle, &proof)?;
+        let account_id = self.acting_account()?;
+        let usage_before = env::storage_usage();
+        let res = RequestBound::new(max_block_height, valid_until_timestamp_ns)
+            .and_then(|bound| self.internal_register_social(&account_id, platform.clone(), signature, handle, proof, validity_ns.map(|v| v.0), bound, None));
+        self.finish_registration(&account_id, &platform, res, usage_before)
     }
 
+    /// Gasless variant of `register_social` submitted by a relayer. `user_signature` is a NEP-413 signature by
+    /// `public_key` over the same message the verifier signs for `register_social`, addressed to this contract,
+    /// while the verifier signature additionally covers `public_key` to attest it belongs to `account_id`.
+    /// The record is bound to `account_id` rather than the transaction signer, and so is the storage the
+    /// relayer's deposit pays for.
     #[payable]
-    pub fn update_access_key(&mut self, signature: Vec<u8>, account_info: u32, max_block_height: u64) {
-        require!(max_block_height > env::block_height(), "expired request"); // assert that request is not expired by block height
-        let account_id = env::signer_account_id();
-        let signature = ed25519_dalek::Signature::try_from(signature.as_ref()).expect("invalid SIg.");
-        let public_key = ed25519_dalek::PublicKey::from_bytes(&self.admin_pub.as_bytes()[1..]).unwrap();
-        let message = account_id.to_string() + "," + account_info.to_string().as_str() + "," + max_block_height.to_string().as_str();
-        let vfg = public_key.verify(message.as_bytes(), &signature).is_ok();
-        assert!(vfg, "unverified data");
-        if let Some(mut user_data) = self.records.get(&account_id) {
-            user_data.access_key_count = Some(account_info);
-            self.records.insert(&account_id, &user_data);  
-        } else {
-            let user_data = UserData {
-                access_key_count: Some(account_info),
-                account_age: None,
-                socials: HashMap::new()
-            };
-            self.records.insert(&account_id, &user_data); 
+    #[handle_result]
+    pub fn register_social_relayed(&mut self, account_id: AccountId, public_key: PublicKey, nonce: Base64VecU8, user_signature: Vec<u8>, platform: String, signature: Vec<u8>, handle: String, proof: String, max_block_height: Option<u64>, valid_until_timestamp_ns: Option<U64>, validity_ns: Option<U64>) -> Result<(), ContractError> {
+        self.ensure_not_paused(PAUSE_REGISTER_SOCIAL_RELAYED)?;
+        self.ensure_deposit(self.registration_fee_due())?;
+        self.check_input_limits(&platform, &handle, &proof)?;
+        let bound = RequestBound::new(max_block_height, valid_until_timestamp_ns)?;
+        let nonce: [u8; 32] = nonce.0.try_into().map_err(|_| ContractError::InvalidNonce)?;
+        if self.used_nonces.contains(&nonce) {
+            return Err(ContractError::InvalidNonce);
         }
+        let validity_ns = validity_ns.map(|v| v.0);
+        let user_message = messages::social_message(&account_id, &platform, &handle, &proof, validity_ns, bound);
+        nep413::verify_signed_message(&public_key, &user_signature, &user_message, nonce, &env::current_account_id())?;
+
+        let usage_before = env::storage_usage();
+        let res = self.internal_register_social(&account_id, platform.clone(), signature, handle, proof, validity_ns, bound, Some(&public_key));
+        if res.is_ok() {
+            self.used_nonces.insert(nonce);
+        }
+        self.finish_registration(&account_id, &platform, res, usage_before)
     }
 
+    /// Extends the signer's attestation on `platform` by the platform's validity period, keeping its handle and
+    /// proof. The verifier signs `"renew_social,<account_id>,<platform>,<handle>,<bound>"`, so it only has
+    /// to recheck that the handle is still controlled rather than collect a new proof. `handle` picks the
+    /// attestation on `multi_handle` platforms and defaults to the first one.
+    #[payable]
+    #[handle_result]
+    pub fn renew_social(&mut self, platform: String, handle: Option<String>, signature: Vec<u8>, max_block_height: Option<u64>, valid_until_timestamp_ns: Option<U64>) -> Result<(), ContractError> {
+        self.ensure_not_paused(PAUSE_RENEW_SOCIAL)?;
+        self.ensure_deposit(0)?;
+        let bound = RequestBound::new(max_block_height, valid_until_timestamp_ns)?;
+        bound.ensure_valid()?;
+        let account_id = self.acting_account()?;
+        self.ensure_not_banned(&account_id)?;
+        self.ensure_within_rate_limit(&account_id)?;
+        let handle = handle.map(|handle| normalize_handle(&handle));
+        let mut socials = self.socials_on(&account_id, &platform);
+        let social = socials.iter_mut().find(|social| handle.as_ref().is_none_or(|handle| &social.handle == handle)).ok_or(ContractError::NotRegistered)?;
+        let message = messages::renew_social_message(&account_id, &platform, &social.handle, bound);
+        self.verify_platform_signature(&platform, &message, &signature)?;
+        let usage_before = env::storage_usage();
+        social.expiry_date = block_timestamp() + self.platform_config(&platform).validity_ns.0;
+        self.claim_holder_slot(&platform, &account_id, social.expiry_date)?;
+        env::log_str(&format!("renewed {} on {} until {}", account_id, platform, social.expiry_date));
+        self.socials.insert((account_id.clone(), platform), socials.into_iter().map(VersionedSocialData::from).collect());
+        self.record_issuance(&account_id);
+        self.settle_storage(&account_id, usage_before)?;
+        Ok(())
+    }
 
+    /// Keeps why a `register_social` with these arguments fails in the signer's `get_failed_attempts`, for
+    /// support to look at, since the failed call itself reverts. The registration's checks are run again
+    /// without registering, and the error is returned, or `None` when they pass. The record is paid from the
+    /// account's storage balance and the attached deposit, and is only emitted when they don't cover it. The
+    /// rest of the deposit is refunded.
     #[payable]
-    pub fn update_contract_age(&mut self, signature: Vec<u8>, account_info: u128, max_block_height: u64) {
-        require!(max_block_height > env::block_height(), "expired request"); // assert that request is not expired by block height
-        let account_id = env::signer_account_id();
-        // validate u64 account_age
-        
-        let signature = ed25519_dalek::Signature::try_from(signature.as_ref()).expect("invalid SIg.");
-        let public_key = ed25519_dalek::PublicKey::from_bytes(&self.admin_pub.as_bytes()[1..]).unwrap();
-        let message = account_id.to_string() + "," + account_info.to_string().as_str() + "," + max_block_height.to_string().as_str();
-        let vfg = public_key.verify(message.as_bytes(), &signature).is_ok();
-        assert!(vfg, "unverified data");
-        if let Some(mut data) = self.records.get(&account_id) {
-            data.account_age = Some(account_info);
-            self.records.insert(&account_id, &data);  
-        } else {
-            let user_data = UserData {
-                access_key_count: None,
-                account_age: Some(account_info),
-                socials: HashMap::new()
-            };
-            self.records.insert(&account_id, &user_data);
+    #[handle_result]
+    pub fn report_failed_attempt(&mut self, platform: String, signature: Vec<u8>, handle: String, proof: String, max_block_height: Option<u64>, valid_until_timestamp_ns: Option<U64>, validity_ns: Option<U64>) -> Result<Option<String>, ContractError> {
+        self.ensure_not_paused(PAUSE_REPORT_FAILED_ATTEMPT)?;
+        self.check_input_limits(&platform, &handle, &proof)?;
+        let account_id = self.acting_account()?;
+        let res = RequestBound::new(max_block_height, valid_until_timestamp_ns)
+            .and_then(|bound| self.check_registration(&account_id, &platform, &signature, &handle, &proof, validity_ns.map(|v| v.0), bound, None));
+        let mut deposit = env::attached_deposit().as_yoctonear();
+        let code = match res {
+            Ok(_) => None,
+            Err(err) => {
+                self.emit_registration_failed(&account_id, &platform, &err);
+                deposit = self.record_failed_attempt(&account_id, &platform, &err, deposit);
+                Some(err.code().to_string())
+            }
+        };
+        if deposit > 0 {
+            Promise::new(env::predecessor_account_id()).transfer(NearToken::from_yoctonear(deposit)).detach();
         }
+        Ok(code)
     }
 
-    pub fn connected_to_5_contracts(&self, account_id: AccountId) -> bool {
-        if let Some(data) = self.records.get(&account_id) {
-            return data.access_key_count.unwrap_or(0) >= 5; 
-        }
-        false
+    pub fn get_failed_attempts(&self, account_id: AccountId) -> Vec<FailedAttempt> {
+        self.failed_attempts.get(&account_id).cloned().unwrap_or_default()
     }
 
-    pub fn connected_to_20_contracts(&self, account_id: AccountId) -> bool {
-        if let Some(data) = self.records.get(&account_id) {
-            return data.access_key_count.unwrap_or(0) >= 20; 
-        }
-        false
+    #[payable]
+    #[handle_result]
+    pub fn update_access_key(&mut self, signature: Vec<u8>, account_info: u32, max_block_height: Option<u64>, valid_until_timestamp_ns: Option<U64>) -> Result<(), ContractError> {
+        self.ensure_not_paused(PAUSE_UPDATE_ACCESS_KEY)?;
+        self.ensure_deposit(0)?;
+        let bound = RequestBound::new(max_block_height, valid_until_timestamp_ns)?;
+        bound.ensure_valid()?; // assert that request is not expired
+        let account_id = self.acting_account()?;
+        self.ensure_not_banned(&account_id)?;
+        self.ensure_within_rate_limit(&account_id)?;
+        let message = messages::account_info_message(&account_id, account_info, bound);
+        self.verify_signature(&message, &signature)?;
+        let usage_before = env::storage_usage();
+        self.access_key_counts.insert(account_id.clone(), account_info);
+        self.attribute_updates_mut(&account_id).access_keys = Some(env::block_timestamp().into());
+        self.accounts.insert(account_id.clone());
+        self.record_issuance(&account_id);
+        self.settle_storage(&account_id, usage_before)?;
+        Ok(())
+    }
+
+
+    #[payable]
+    #[handle_result]
+    pub fn update_contract_age(&mut self, signature: Vec<u8>, account_info: u128, max_block_height: Option<u64>, valid_until_timestamp_ns: Option<U64>) -> Result<(), ContractError> {
+        self.ensure_not_paused(PAUSE_UPDATE_CONTRACT_AGE)?;
+        self.ensure_deposit(0)?;
+        let bound = RequestBound::new(max_block_height, valid_until_timestamp_ns)?;
+        bound.ensure_valid()?; // assert that request is not expired
+        let account_id = self.acting_account()?;
+        self.ensure_not_banned(&account_id)?;
+        self.ensure_within_rate_limit(&account_id)?;
+        let account_age = u64::try_from(account_info).map_err(|_| ContractError::InvalidAccountAge)?;
+        let message = messages::account_info_message(&account_id, account_info, bound);
+        self.verify_signature(&message, &signature)?;
+        let usage_before = env::storage_usage();
+        self.account_ages.insert(account_id.clone(), account_age);
+        self.attribute_updates_mut(&account_id).account_age = Some(env::block_timestamp().into());
+        self.accounts.insert(account_id.clone());
+        self.record_issuance(&account_id);
+        self.settle_storage(&account_id, usage_before)?;
+        Ok(())
+    }
+
+    pub fn connected_to_5_contracts(&self, account_id: AccountId, max_staleness_ns: Option<U64>) -> bool {
+        self.attested_key_count(&account_id, max_staleness_ns.map(|ns| ns.0)) >= 5
+    }
+
+    pub fn connected_to_20_contracts(&self, account_id: AccountId, max_staleness_ns: Option<U64>) -> bool {
+        self.attested_key_count(&account_id, max_staleness_ns.map(|ns| ns.0)) >= 20
     }
 
     pub fn connected_to_lens(&self, account_id: AccountId) -> bool {
-        if let Some(data) = self.records.get(&account_id) {
-            let lens_socials = data.socials.get("lens");
-            if lens_socials.is_some() {
-                return lens_socials.unwrap().expiry_date > block_timestamp();
-            }
-        }
-        false
+        self.attested_social(&account_id, "lens").is_some_and(|lens| lens.expiry_date > block_timestamp())
     }
 
     pub fn connected_to_farcaster(&self, account_id: AccountId) -> bool {
-        if let Some(data) = self.records.get(&account_id) {
-            if data.socials.get("farcaster").is_some() {return true}
-        }
-        false
+        self.attested_social(&account_id, "farcaster").is_some()
     }
 
-    pub fn connected_to_10_contracts(&self, account_id: AccountId) -> bool {
-        if let Some(data) = self.records.get(&account_id) {
-            return data.access_key_count.unwrap_or(0) >= 10; 
-        }
-        false
+    pub fn connected_to_10_contracts(&self, account_id: AccountId, max_staleness_ns: Option<U64>) -> bool {
+        self.attested_key_count(&account_id, max_staleness_ns.map(|ns| ns.0)) >= 10
     }
 
-    pub fn six_month_old(&self, account_id: AccountId) -> bool {
-        if let Some(data) = self.records.get(&account_id) {
-            if data.account_age.is_none() {return false}
-            let age_nanoseconds = data.account_age.unwrap();
+    pub fn six_month_old(&self, account_id: AccountId, max_staleness_ns: Option<U64>) -> bool {
+        if let Some(age_nanoseconds) = self.attested_age(&account_id, max_staleness_ns.map(|ns| ns.0)) {
             let now = block_timestamp();
             let six_months = 6 * 30 * 24 * 60 * 60 * 1_000_000_000;
-            return (now - age_nanoseconds as u64) > six_months;
+            return (now - age_nanoseconds) > six_months;
 
         }
         false
     }
 
     pub fn connected_to_platform(&self, account_id: AccountId, platform: String) -> bool {
-        if let Some(data) = self.records.get(&account_id) {
-            return data.socials.get(platform.as_str()).is_some();
-        }
-        false
+        self.attested_social(&account_id, &platform).is_some()
+    }
+
+    /// Where the account's attestation on `platform` stands, telling an expired attestation still in the
+    /// platform's grace period apart from one past it, which the `connected_to_*` views don't.
+    pub fn get_social_status(&self, account_id: AccountId, platform: String) -> SocialStatus {
+        self.attested_social(&account_id, &platform).map_or(SocialStatus::NotRegistered, |social| self.status_of(&platform, &social, block_timestamp()))
     }
 
-    pub fn is_two_year_old(&self, account_id: AccountId) -> bool {
-        if let Some(data) = self.records.get(&account_id) {
-            if data.account_age.is_none() {return false}
-            let age_nanoseconds = data.account_age.unwrap();
+    pub fn is_two_year_old(&self, account_id: AccountId, max_staleness_ns: Option<U64>) -> bool {
+        if let Some(age_nanoseconds) = self.attested_age(&account_id, max_staleness_ns.map(|ns| ns.0)) {
             let now = block_timestamp();
-            let two_years = 2 * 365 * 24 * 60 * 60 * 1_000_000_000;
-            return (now - age_nanoseconds as u64) >= two_years;
+            return (now - age_nanoseconds) >= 2 * YEAR_NS;
         }
         false
     }
 
-    pub fn is_one_year_old(&self, account_id: AccountId) -> bool {
-        if let Some(data) = self.records.get(&account_id) {
-            if data.account_age.is_none() {return false}
-            let age_nanoseconds = data.account_age.unwrap();
+    pub fn is_one_year_old(&self, account_id: AccountId, max_staleness_ns: Option<U64>) -> bool {
+        if let Some(age_nanoseconds) = self.attested_age(&account_id, max_staleness_ns.map(|ns| ns.0)) {
             let now = block_timestamp();
-            let one_year = 365 * 24 * 60 * 60 * 1_000_000_000;
-            return (now - age_nanoseconds as u64) >= one_year;
+            return (now - age_nanoseconds) >= YEAR_NS;
         }
         false
     }
 
-    pub fn is_three_month_old(&self, account_id: AccountId) -> bool {
-        if let Some(data) = self.records.get(&account_id) {
-            if data.account_age.is_none() {return false}
-            let age_nanoseconds = data.account_age.unwrap();
+    pub fn is_three_month_old(&self, account_id: AccountId, max_staleness_ns: Option<U64>) -> bool {
+        if let Some(age_nanoseconds) = self.attested_age(&account_id, max_staleness_ns.map(|ns| ns.0)) {
             let now = block_timestamp();
             let three_months = 3 * 30 * 24 * 60 * 60 * 1_000_000_000;
-            return (now - age_nanoseconds as u64) >= three_months;
+            return (now - age_nanoseconds) >= three_months;
         }
         false
     }
 
-    pub fn is_a_month_old(&self, account_id: AccountId) -> bool {
-        if let Some(data) = self.records.get(&account_id) {
-            if data.account_age.is_none() {return false}
-            let age_nanoseconds = data.account_age.unwrap();
+    pub fn is_a_month_old(&self, account_id: AccountId, max_staleness_ns: Option<U64>) -> bool {
+        if let Some(age_nanoseconds) = self.attested_age(&account_id, max_staleness_ns.map(|ns| ns.0)) {
             let now = block_timestamp();
             let one_month = 30 * 24 * 60 * 60 * 1_000_000_000; // abstract 30 * 24 * 60 * 60 * 1_000_000_000 to a constant
-            return (now - age_nanoseconds as u64) >= one_month;
+            return (now - age_nanoseconds) >= one_month;
         }
         false
     }
 
     pub fn connected_to_twitter(&self, account_id: AccountId) -> bool {
-        if let Some(data) = self.records.get(&account_id) {
-            if data.socials.get("twitter").is_some() {return true}
+        self.attested_social(&account_id, "twitter").is_some()
+    }
+}
+
+#[cfg(feature = "contract")]
+impl Contract {
+    // returns the bytes freed by evicting another account's superseded claim, which that account is refunded
+    fn internal_register_social(&mut self, account_id: &AccountId, platform: String, signature: Vec<u8>, handle: String, proof: String, validity_ns: Option<u64>, bound: RequestBound, user_key: Option<&PublicKey>) -> Result<u64, ContractError> {
+        let (normalized, signer, validity_ns) = self.check_registration(account_id, &platform, &signature, &handle, &proof, validity_ns, bound, user_key)?;
+        self.admit_social(account_id, &platform, normalized, &proof, validity_ns, Provenance::Verifier(Some(signer)))
+    }
+
+    // the checks of a verifier-signed registration that come before anything is written, returning the
+    // normalized handle, the key that signed it and the validity granted
+    fn check_registration(&self, account_id: &AccountId, platform: &str, signature: &[u8], handle: &str, proof: &str, validity_ns: Option<u64>, bound: RequestBound, user_key: Option<&PublicKey>) -> Result<(String, PublicKey, u64), ContractError> {
+        // basically, need to assert early that handle is not already registered nor has it expired, before other computations.
+        bound.ensure_valid()?;
+        // the verifier signs the handle as submitted, it is stored and looked up in its normalized form
+        let normalized = self.check_claimable(account_id, platform, handle)?;
+        let message = match user_key {
+            Some(key) => messages::relayed_social_message(account_id, key, platform, handle, proof, validity_ns, bound),
+            None => messages::social_message(account_id, platform, handle, proof, validity_ns, bound),
+        };
+        let signer = self.verify_platform_signature(platform, &message, signature)?;
+        let validity_ns = self.attestation_validity(platform, validity_ns)?;
+        Ok((normalized, signer, validity_ns))
+    }
+
+    // checks `account_id` may claim `handle` on `platform` before its claim is confirmed, returning the handle
+    // normalized
+    pub(crate) fn check_claimable(&self, account_id: &AccountId, platform: &str, handle: &str) -> Result<String, ContractError> {
+        self.check_claimable_since(account_id, platform, handle, None)
+    }
+
+    // `check_claimable` for a claim with priority from `priority_since`, which takes over the handle from an
+    // attestation issued since then
+    pub(crate) fn check_claimable_since(&self, account_id: &AccountId, platform: &str, handle: &str, priority_since: Option<u64>) -> Result<String, ContractError> {
+        self.ensure_not_banned(account_id)?;
+        self.ensure_within_rate_limit(account_id)?;
+        if !self.platform_enabled(platform) {
+            return Err(ContractError::UnknownPlatform);
         }
-        false
+        let handle_key = (platform.to_string(), normalize_handle(handle));
+        self.check_handle_format(platform, &handle_key.1)?;
+        // a mapped handle may only be registered again, by anyone, once the claim behind it has expired.
+        if self.handles.get(&handle_key).is_some_and(|owner| self.handle_is_reserved(&handle_key, owner, block_timestamp()) && !self.issued_since(&handle_key, owner, priority_since)) {
+            return Err(ContractError::HandleAlreadyRegistered);
+        }
+        if self.optimistic_claims.get(&handle_key).is_some_and(|claim| &claim.account_id != account_id) {
+            return Err(ContractError::ClaimPending);
+        }
+        // and other accounts only after the platform's cooldown on top
+        if let Some((holder, until)) = self.handle_cooldown(&handle_key).filter(|(holder, _)| holder != account_id) {
+            if block_timestamp() < until {
+                env::log_str(&format!("{} is reserved for {} until {}", handle_key.1, holder, until));
+                return Err(ContractError::HandleCoolingDown { until });
+            }
+        }
+        Ok(handle_key.1)
+    }
+
+    // stores the confirmed claim of a `check_claimable` handle, valid for `validity_ns`, returning the bytes freed
+    // by evicting its previous owner
+    pub(crate) fn admit_social(&mut self, account_id: &AccountId, platform: &str, handle: String, proof: &str, validity_ns: u64, provenance: Provenance) -> Result<u64, ContractError> {
+        let previous_owner = self.handles.get(&(platform.to_string(), handle.clone())).cloned();
+        let existing = self.socials_on(account_id, platform); // get user's current claims on the platform
+        // a verifier proof is spent once. an oracle or NEAR Social confirms again on every call, their proof is
+        // only the linked address or the fixed profile claim
+        let proof_reusable = matches!(provenance, Provenance::Oracle(_) | Provenance::NearSocial);
+        if !proof_reusable && self.replaced_social(&existing, platform, &handle).is_some_and(|i| existing[i].proof_digest == proofs::proof_digest(proof)) {
+            return Err(ContractError::IncorrectProof);
+        }
+        let sd = SocialData {
+            issued_date: env::block_timestamp(),
+            handle: handle.clone(),
+            proof_digest: proofs::proof_digest(proof),
+            proof_type: proofs::proof_type(proof),
+            expiry_date: block_timestamp() + validity_ns,
+            provenance,
+        };
+        let logged = format!("registered {} on {} with {} proof {}", account_id, platform, sd.proof_type, proofs::digest_hex(&sd.proof_digest));
+        self.store_social(account_id, platform, sd)?;
+        env::log_str(&logged);
+        // the superseded claim is dropped here so no handle is ever mapped from two attestations
+        let evicted = match previous_owner {
+            Some(owner) if &owner != account_id => {
+                let freed = self.remove_handle(&owner, platform, &handle, ReleaseReason::TakenOver);
+                self.refresh_cached_score(&owner);
+                freed
+            }
+            _ => 0,
+        };
+        Ok(evicted)
+    }
+
+    // the claim storing `handle` supersedes: the same handle, or on single handle platforms any handle
+    fn replaced_social(&self, existing: &[SocialData], platform: &str, handle: &str) -> Option<usize> {
+        let multi_handle = self.platform_config(platform).multi_handle;
+        existing.iter().position(|social| social.handle == handle || !multi_handle)
+    }
+
+    // adds `social` to the account's attestations on `platform` and maps its handle to the account, replacing the
+    // claim it supersedes. nothing is written unless it succeeds.
+    pub(crate) fn store_social(&mut self, account_id: &AccountId, platform: &str, social: SocialData) -> Result<(), ContractError> {
+        let mut existing = self.socials_on(account_id, platform);
+        let replaced = self.replaced_social(&existing, platform, &social.handle);
+        if replaced.is_none() && existing.len() >= MAX_HANDLES_PER_PLATFORM {
+            return Err(ContractError::TooManyHandles { max: MAX_HANDLES_PER_PLATFORM as u32 });
+        }
+        let mut platforms = self.social_platforms.get(account_id).cloned().unwrap_or_default();
+        // bounded so an account's platform index always stays cheap enough to load
+        let max = self.input_limits.max_platforms_per_account;
+        if existing.is_empty() && platforms.len() as u32 >= max {
+            return Err(ContractError::TooManyPlatforms { max });
+        }
+        self.claim_holder_slot(platform, account_id, social.expiry_date)?;
+        if let Some(old) = replaced.map(|i| existing.remove(i)).filter(|old| old.handle != social.handle) {
+            self.record_released_handle(account_id, platform, &old, ReleaseReason::Replaced);
+            let old_key = (platform.to_string(), old.handle);
+            self.handles.remove(&old_key);
+            self.record_release(old_key, account_id);
+        }
+        let handle_key = (platform.to_string(), social.handle.clone());
+        self.released_handles.remove(&handle_key);
+        self.handles.insert(handle_key, account_id.clone());
+        if existing.is_empty() && replaced.is_none() {
+            platforms.push(platform.to_string());
+            self.social_platforms.insert(account_id.clone(), platforms);
+        }
+        existing.push(social);
+        self.socials.insert((account_id.clone(), platform.to_string()), existing.into_iter().map(VersionedSocialData::from).collect());
+        self.accounts.insert(account_id.clone());
+        Ok(())
+    }
+
+    // every attestation the account holds on `platform`, one per handle
+    pub(crate) fn socials_on(&self, account_id: &AccountId, platform: &str) -> Vec<SocialData> {
+        let socials = self.socials.get(&(account_id.clone(), platform.to_string()));
+        socials.map_or_else(Vec::new, |socials| socials.iter().cloned().map(SocialData::from).collect())
+    }
+
+    // the account's longest valid attestation on `platform`, the one the checks go by
+    pub(crate) fn social(&self, account_id: &AccountId, platform: &str) -> Option<SocialData> {
+        self.socials_on(account_id, platform).into_iter().max_by_key(|social| social.expiry_date)
+    }
+
+    // settles a successful registration, a failed one is emitted before it reverts
+    fn finish_registration(&mut self, account_id: &AccountId, platform: &str, res: Result<u64, ContractError>, usage_before: u64) -> Result<(), ContractError> {
+        match res {
+            Ok(evicted) => {
+                let fee = self.take_fee(&env::predecessor_account_id(), self.registration_fee);
+                self.treasury += fee;
+                // the registrant only pays for what it added, not for the other account's data it replaced
+                let deposit = env::attached_deposit().as_yoctonear() - fee;
+                self.record_issuance(account_id);
+                self.settle_storage_from(account_id, usage_before - evicted, deposit)?;
+                self.record_registration(platform);
+                Ok(())
+            }
+            Err(err) => {
+                // the receipt keeps its logs when it fails, so indexers still see the event
+                self.emit_registration_failed(account_id, platform, &err);
+                Err(err)
+            }
+        }
+    }
+
+    pub(crate) fn emit_registration_failed(&self, account_id: &AccountId, platform: &str, err: &ContractError) {
+        events::emit("registration_failed", json!({ "account_id": account_id, "platform": platform, "code": err.code() }));
+    }
+
+    // keeps the error against the account when its storage balance and `deposit` pay for the record, like any
+    // other data of the account. returns what's left of `deposit` to refund.
+    pub(crate) fn record_failed_attempt(&mut self, account_id: &AccountId, platform: &str, err: &ContractError, deposit: u128) -> u128 {
+        self.flush_collections();
+        let usage_before = env::storage_usage();
+        let previous = self.failed_attempts.get(account_id).cloned();
+        let mut attempts = previous.clone().unwrap_or_default();
+        if attempts.len() >= MAX_FAILED_ATTEMPTS {
+            attempts.remove(0);
+        }
+        attempts.push(FailedAttempt {
+            platform: platform.to_string(),
+            code: err.code().to_string(),
+            message: err.to_string(),
+            timestamp: block_timestamp().into(),
+        });
+        self.failed_attempts.insert(account_id.clone(), attempts);
+        self.flush_collections();
+        let usage = env::storage_usage();
+        if usage <= usage_before {
+            self.release_storage(account_id, usage_before - usage);
+            return deposit;
+        }
+        let cost = u128::from(usage - usage_before) * env::storage_byte_cost().as_yoctonear();
+        let mut balance = self.storage_balances.get(account_id).cloned().unwrap_or_default();
+        let from_deposit = cost.saturating_sub(balance.available());
+        if from_deposit > deposit {
+            match previous {
+                Some(previous) => self.failed_attempts.insert(account_id.clone(), previous),
+                None => self.failed_attempts.remove(account_id),
+            };
+            return deposit;
+        }
+        balance.total += from_deposit;
+        balance.locked += cost;
+        self.storage_balances.insert(account_id.clone(), balance);
+        deposit - from_deposit
     }
 }
 
-#[cfg(all(test, not(target_arch = "wasm32")))]
+#[cfg(all(test, feature = "contract", not(target_arch = "wasm32")))]
 mod tests {
+    use ed25519_dalek::Signer;
+    use near_sdk::json_types::U128;
     use near_sdk::test_utils::{accounts, VMContextBuilder};
-    use near_sdk::testing_env;
+    use near_sdk::{testing_env, NearToken};
     use std::str::FromStr;
 
     use super::*;
 
     const MINT_STORAGE_COST: u128 = 2385000000000000000000000;
+    const STORAGE_DEPOSIT: u128 = 1_000_000_000_000_000_000_000_000;
 
+    fn keypair(seed: u8) -> (ed25519_dalek::SigningKey, PublicKey) {
+        let signing_key = ed25519_dalek::SigningKey::from_bytes(&[seed; 32]);
+        let mut key_bytes = vec![0u8];
+        key_bytes.extend_from_slice(signing_key.verifying_key().as_bytes());
+        (signing_key, PublicKey::try_from(key_bytes).unwrap())
+    }
+
+    // attaches `STORAGE_DEPOSIT` for the storage a call adds unless the test attaches its own
     fn get_context(predecessor_account_id: AccountId) -> VMContextBuilder {
         let mut builder = VMContextBuilder::new();
         builder
             .current_account_id(accounts(0))
             .signer_account_id(predecessor_account_id.clone())
-            .predecessor_account_id(predecessor_account_id);
+            .predecessor_account_id(predecessor_account_id)
+            .attached_deposit(NearToken::from_yoctonear(STORAGE_DEPOSIT));
         builder
     }
 
+    // codes of the `registration_failed` events logged in the current context, unpaid failures included
+    fn failure_codes() -> Vec<String> {
+        near_sdk::test_utils::get_logs().iter()
+            .filter_map(|log| log.strip_prefix("EVENT_JSON:"))
+            .map(|event| near_sdk::serde_json::from_str::<near_sdk::serde_json::Value>(event).unwrap())
+            .filter(|event| event["event"] == "registration_failed")
+            .map(|event| event["data"][0]["code"].as_str().unwrap().to_string())
+            .collect()
+    }
+
+    // executes a staged change once its timelock has passed, then turns the clock back for the rest of the test
+    fn execute_after_timelock(contract: &mut Contract, id: u64) {
+        let (caller, now) = (env::predecessor_account_id(), env::block_timestamp());
+        let executable_at = contract.pending_changes.get(&id).unwrap().executable_at.0;
+        testing_env!(get_context(caller.clone()).block_timestamp(executable_at).build());
+        contract.execute_change(id);
+        testing_env!(get_context(caller).block_timestamp(now).build());
+    }
+
+    // gives the account a storage balance for the calls and callbacks that pay storage from it alone
+    fn deposit_storage(contract: &mut Contract, account_id: AccountId) {
+        testing_env!(get_context(account_id).build());
+        contract.storage_deposit(None, None);
+    }
+
     #[test]
     fn test_new() {
         let mut context = get_context(accounts(1));
-        let receiver = AccountId::new_unchecked("genadop.testnet".to_string());
+        let receiver: AccountId = "genadop.testnet".parse().unwrap();
         testing_env!(context.build());
         testing_env!(context
             .storage_usage(env::storage_usage())
-            .attached_deposit(MINT_STORAGE_COST)
+            .attached_deposit(NearToken::from_yoctonear(MINT_STORAGE_COST))
             .predecessor_account_id(receiver.clone())
             .signer_account_id(receiver.clone())
             .block_timestamp(2000)
@@ -279,18 +971,2260 @@ mod tests {
             223, 239,  43,  14, 150, 222,  74, 118,   2
           ].to_vec();
         let mut contract = Contract::new(PublicKey::from_str("ed25519:6BTMQWnxGDrzWizymRMdnRsofDMRJ1assMUrym6kSEj9").unwrap());
-        println!("go ..{:?}", contract.register_social("lens".to_string(), sig.clone(), "genadop.lens".to_string(), "0x11e231e6fbd69343389ba9b6179b0108b914ad3e687172ba5d7748212058477d63e4aa09114e9a9b23b3cae4da7300577809b650bdf8842e0d1fae6cb8144f1c1c".to_string(), 10));
-        // println!("go on osnu.. {:?}", contract.get_user_connected_platforms(receiver.clone()));
+        // signed over the original message format, which no longer verifies
+        assert!(contract.register_social("lens".to_string(), sig.clone(), "genadop.lens".to_string(), "0x11e231e6fbd69343389ba9b6179b0108b914ad3e687172ba5d7748212058477d63e4aa09114e9a9b23b3cae4da7300577809b650bdf8842e0d1fae6cb8144f1c1c".to_string(), Some(10), None, None).is_err());
+        assert_eq!(failure_codes(), vec!["UNVERIFIED_DATA"]);
         testing_env!(context
             .storage_usage(env::storage_usage())
-            .attached_deposit(MINT_STORAGE_COST)
+            .attached_deposit(NearToken::from_yoctonear(MINT_STORAGE_COST))
             .predecessor_account_id(receiver.clone())
             .signer_account_id(receiver.clone())
             .block_timestamp(7876000000002000)
             .build());
-        println!("go ..{:?}", contract.register_social("lens".to_string(), sig.clone(), "genadop.lens".to_string(), "0x11e231e6fbd69343389ba9b6179b0108b914ad3e687172ba5d7748212058477d63e4aa09114e9a9b23b3cae4da7300577809b650bdf8842e0d1fae6cb8144f1c1c".to_string(), 10));
-        // println!("after round 1.. {}", contract.six_month_old(receiver));
+        assert!(contract.register_social("lens".to_string(), sig.clone(), "genadop.lens".to_string(), "0x11e231e6fbd69343389ba9b6179b0108b914ad3e687172ba5d7748212058477d63e4aa09114e9a9b23b3cae4da7300577809b650bdf8842e0d1fae6cb8144f1c1c".to_string(), Some(10), None, None).is_err());
+        assert_eq!(failure_codes(), vec!["UNVERIFIED_DATA"]);
+    }
+
+    #[test]
+    fn test_failed_attempt_recorded() {
+        testing_env!(get_context(accounts(1)).attached_deposit(NearToken::from_yoctonear(0)).build());
+        let mut contract = Contract::new(PublicKey::from_str("ed25519:6BTMQWnxGDrzWizymRMdnRsofDMRJ1assMUrym6kSEj9").unwrap());
+        // without a deposit or storage balance to pay for the record the failure is only emitted
+        let report = contract.report_failed_attempt("lens".to_string(), vec![0; 10], "genadop.lens".to_string(), "0x".to_string(), Some(10), None, None);
+        assert_eq!(report, Ok(Some("INVALID_SIGNATURE_LENGTH".to_string())));
+        assert_eq!(failure_codes(), vec!["INVALID_SIGNATURE_LENGTH"]);
+        assert!(contract.get_failed_attempts(accounts(1)).is_empty());
+        testing_env!(get_context(accounts(1)).attached_deposit(NearToken::from_yoctonear(MINT_STORAGE_COST)).build());
+        contract.report_failed_attempt("lens".to_string(), vec![0; 10], "genadop.lens".to_string(), "0x".to_string(), Some(10), None, None).unwrap();
+        let attempts = contract.get_failed_attempts(accounts(1));
+        assert_eq!(attempts.len(), 1);
+        let balance = contract.storage_balance_of(accounts(1)).unwrap();
+        assert!(balance.total.0 > 0 && balance.available.0 == 0);
+        let refund = near_sdk::test_utils::get_created_receipts().into_iter().flat_map(|receipt| receipt.actions).find_map(|action| match action {
+            near_sdk::mock::MockAction::Transfer { deposit, .. } => Some(deposit.as_yoctonear()),
+            _ => None,
+        });
+        assert_eq!(refund, Some(MINT_STORAGE_COST - balance.total.0));
+        assert_eq!(attempts[0].code, "INVALID_SIGNATURE_LENGTH");
+        assert_eq!(attempts[0].message, "invalid signature length: expected 64 bytes, got 10");
+    }
+
+    #[test]
+    fn test_failed_registration_reverts() {
+        let (verifier, verifier_pk) = keypair(1);
+        testing_env!(get_context(accounts(0)).build());
+        let mut contract = Contract::new(verifier_pk);
+        let signed = |account: &AccountId| verifier.sign(messages::social_message(account, "lens", "bob.lens", "0x01", None, RequestBound::BlockHeight(10)).as_bytes()).to_bytes().to_vec();
+        testing_env!(get_context(accounts(1)).attached_deposit(NearToken::from_yoctonear(MINT_STORAGE_COST)).build());
+        contract.register_social("lens".to_string(), signed(&accounts(1)), "bob.lens".to_string(), "0x01".to_string(), Some(10), None, None).unwrap();
+        // the error reverts the call, only the event is left of it
+        testing_env!(get_context(accounts(2)).attached_deposit(NearToken::from_yoctonear(MINT_STORAGE_COST)).build());
+        let res = contract.register_social("lens".to_string(), signed(&accounts(2)), "bob.lens".to_string(), "0x01".to_string(), Some(10), None, None);
+        assert_eq!(res, Err(ContractError::HandleAlreadyRegistered));
+        assert_eq!(failure_codes(), vec!["HANDLE_ALREADY_REGISTERED"]);
+        assert!(contract.get_failed_attempts(accounts(2)).is_empty());
+        // reporting it keeps it for the account
+        testing_env!(get_context(accounts(2)).attached_deposit(NearToken::from_yoctonear(MINT_STORAGE_COST)).build());
+        let report = contract.report_failed_attempt("lens".to_string(), signed(&accounts(2)), "bob.lens".to_string(), "0x01".to_string(), Some(10), None, None);
+        assert_eq!(report, Ok(Some("HANDLE_ALREADY_REGISTERED".to_string())));
+        assert_eq!(contract.get_failed_attempts(accounts(2))[0].code, "HANDLE_ALREADY_REGISTERED");
+        assert!(contract.get_handles(accounts(2), "lens".to_string()).is_empty());
+        // a registration that would go through has nothing to report
+        testing_env!(get_context(accounts(1)).build());
+        contract.unregister_social("lens".to_string());
+        testing_env!(get_context(accounts(3)).build());
+        let message = messages::social_message(&accounts(3), "lens", "carol.lens", "0x01", None, RequestBound::BlockHeight(10));
+        let signature = verifier.sign(message.as_bytes()).to_bytes().to_vec();
+        assert_eq!(contract.report_failed_attempt("lens".to_string(), signature, "carol.lens".to_string(), "0x01".to_string(), Some(10), None, None), Ok(None));
+        assert!(contract.get_failed_attempts(accounts(3)).is_empty());
+    }
+
+    #[test]
+    fn test_report_failed_attempt_paused() {
+        let (_, verifier_pk) = keypair(1);
+        testing_env!(get_context(accounts(0)).build());
+        let mut contract = Contract::new(verifier_pk);
+        contract.set_paused_methods(PAUSE_REPORT_FAILED_ATTEMPT);
+        testing_env!(get_context(accounts(1)).build());
+        let res = contract.report_failed_attempt("lens".to_string(), vec![0; 64], "bob.lens".to_string(), "0x01".to_string(), Some(10), None, None);
+        assert_eq!(res, Err(ContractError::MethodPaused));
+    }
+
+    #[test]
+    fn test_oversized_handle_rejected() {
+        testing_env!(get_context(accounts(1)).build());
+        let mut contract = Contract::new(PublicKey::from_str("ed25519:6BTMQWnxGDrzWizymRMdnRsofDMRJ1assMUrym6kSEj9").unwrap());
+        let res = contract.register_social("lens".to_string(), vec![0; 64], "a".repeat(65), "0x".to_string(), Some(10), None, None);
+        assert_eq!(res, Err(ContractError::InputTooLong { field: "handle", max: 64 }));
+    }
+
+    #[test]
+    fn test_storage_deposit_and_withdraw() {
+        let (_, verifier_pk) = keypair(1);
+        let mut context = get_context(accounts(1));
+        testing_env!(context.attached_deposit(NearToken::from_yoctonear(MINT_STORAGE_COST)).build());
+        let mut contract = Contract::new(verifier_pk);
+        let balance = contract.storage_deposit(None, None);
+        assert_eq!(balance.total.0, MINT_STORAGE_COST);
+        assert_eq!(balance.available.0, MINT_STORAGE_COST);
+        testing_env!(context.attached_deposit(NearToken::from_yoctonear(1)).build());
+        let balance = contract.storage_withdraw(Some(U128(MINT_STORAGE_COST / 2)));
+        assert_eq!(balance.total.0, MINT_STORAGE_COST - MINT_STORAGE_COST / 2);
+        assert!(contract.storage_unregister(None));
+        assert!(contract.storage_balance_of(accounts(1)).is_none());
+    }
+
+    #[test]
+    fn test_registration_pays_for_storage() {
+        let (verifier, verifier_pk) = keypair(1);
+        let mut context = get_context(accounts(1));
+        testing_env!(context.build());
+        let mut contract = Contract::new(verifier_pk);
+        let message = messages::social_message(&accounts(1), "lens", "bob.lens", "0x01", None, RequestBound::BlockHeight(10));
+        let signature = verifier.sign(message.as_bytes()).to_bytes().to_vec();
+        testing_env!(context.attached_deposit(NearToken::from_yoctonear(MINT_STORAGE_COST)).build());
+        contract.register_social("lens".to_string(), signature, "bob.lens".to_string(), "0x01".to_string(), Some(10), None, None).unwrap();
+        // only the bytes the registration added are kept, the rest of the deposit is refunded
+        let balance = contract.storage_balance_of(accounts(1)).unwrap();
+        assert!(balance.total.0 > 0 && balance.total.0 < MINT_STORAGE_COST);
+        assert_eq!(balance.available.0, 0);
+    }
+
+    #[test]
+    fn test_purge_expired_handles() {
+        let (verifier, verifier_pk) = keypair(1);
+        testing_env!(get_context(accounts(1)).build());
+        let mut contract = Contract::new(verifier_pk);
+        let message = messages::social_message(&accounts(1), "lens", "bob.lens", "0x01", None, RequestBound::BlockHeight(10));
+        let signature = verifier.sign(message.as_bytes()).to_bytes().to_vec();
+        contract.register_social("lens".to_string(), signature, "bob.lens".to_string(), "0x01".to_string(), Some(10), None, None).unwrap();
+        testing_env!(get_context(accounts(0)).build());
+        contract.grant_role(accounts(0), Role::Operator);
+        assert_eq!(contract.purge_expired_handles(10), 0);
+        // past the 3 month expiry the handle is free for another account
+        testing_env!(get_context(accounts(0)).block_timestamp(100 * 24 * 60 * 60 * 1_000_000_000).build());
+        assert_eq!(contract.purge_expired_handles(10), 1);
+        assert!(contract.get_handles(accounts(1), "lens".to_string()).is_empty());
+        assert_eq!(contract.get_handle_history(accounts(1), None, None)[0].reason_released, ReleaseReason::Expired);
+        testing_env!(get_context(accounts(2)).block_timestamp(100 * 24 * 60 * 60 * 1_000_000_000).build());
+        let message = messages::social_message(&accounts(2), "lens", "bob.lens", "0x02", None, RequestBound::BlockHeight(10));
+        let signature = verifier.sign(message.as_bytes()).to_bytes().to_vec();
+        contract.register_social("lens".to_string(), signature, "bob.lens".to_string(), "0x02".to_string(), Some(10), None, None).unwrap();
+        // the purged attestation can't be renewed back onto the handle
+        testing_env!(get_context(accounts(1)).block_timestamp(100 * 24 * 60 * 60 * 1_000_000_000).build());
+        let message = messages::renew_social_message(&accounts(1), "lens", "bob.lens", RequestBound::BlockHeight(10));
+        let signature = verifier.sign(message.as_bytes()).to_bytes().to_vec();
+        assert_eq!(contract.renew_social("lens".to_string(), None, signature, Some(10), None), Err(ContractError::NotRegistered));
+    }
+
+    #[test]
+    fn test_expired_handle_taken_over() {
+        let (verifier, verifier_pk) = keypair(1);
+        testing_env!(get_context(accounts(1)).build());
+        let mut contract = Contract::new(verifier_pk);
+        let message = messages::social_message(&accounts(1), "lens", "bob.lens", "0x01", None, RequestBound::BlockHeight(10));
+        let signature = verifier.sign(message.as_bytes()).to_bytes().to_vec();
+        contract.register_social("lens".to_string(), signature, "bob.lens".to_string(), "0x01".to_string(), Some(10), None, None).unwrap();
+        testing_env!(get_context(accounts(2)).block_timestamp(100 * 24 * 60 * 60 * 1_000_000_000).build());
+        let message = messages::social_message(&accounts(2), "lens", "bob.lens", "0x02", None, RequestBound::BlockHeight(10));
+        let signature = verifier.sign(message.as_bytes()).to_bytes().to_vec();
+        contract.register_social("lens".to_string(), signature, "bob.lens".to_string(), "0x02".to_string(), Some(10), None, None).unwrap();
+        // the previous holder's attestation went with the handle
+        assert!(!contract.connected_to_platform(accounts(1), "lens".to_string()));
+        assert!(contract.connected_to_platform(accounts(2), "lens".to_string()));
+        testing_env!(get_context(accounts(0)).block_timestamp(100 * 24 * 60 * 60 * 1_000_000_000).build());
+        contract.grant_role(accounts(3), Role::Operator);
+        assert!(contract.has_role(accounts(3), Role::Operator));
+        testing_env!(get_context(accounts(3)).block_timestamp(100 * 24 * 60 * 60 * 1_000_000_000).build());
+        assert_eq!(contract.purge_expired_handles(10), 0);
+    }
+
+    #[test]
+    fn test_renewal_grace() {
+        let (verifier, verifier_pk) = keypair(1);
+        testing_env!(get_context(accounts(0)).build());
+        let mut contract = Contract::new(verifier_pk);
+        let day = 24 * 60 * 60 * 1_000_000_000;
+        let id = contract.set_platform_config("lens".to_string(), PlatformConfig { validity_ns: U64(day), grace_period_ns: U64(day), ..Default::default() });
+        execute_after_timelock(&mut contract, id);
+        assert_eq!(contract.get_social_status(accounts(1), "lens".to_string()), SocialStatus::NotRegistered);
+        testing_env!(get_context(accounts(1)).build());
+        let message = messages::social_message(&accounts(1), "lens", "bob.lens", "0x01", None, RequestBound::BlockHeight(10));
+        let signature = verifier.sign(message.as_bytes()).to_bytes().to_vec();
+        contract.register_social("lens".to_string(), signature, "bob.lens".to_string(), "0x01".to_string(), Some(10), None, None).unwrap();
+        assert_eq!(contract.get_social_status(accounts(1), "lens".to_string()), SocialStatus::Active);
+        // in the grace period the handle is still reserved for its holder
+        let message = messages::social_message(&accounts(2), "lens", "bob.lens", "0x02", None, RequestBound::BlockHeight(10));
+        let signature = verifier.sign(message.as_bytes()).to_bytes().to_vec();
+        testing_env!(get_context(accounts(2)).block_timestamp(day + day / 2).build());
+        assert_eq!(contract.get_social_status(accounts(1), "lens".to_string()), SocialStatus::InGrace);
+        assert!(contract.register_social("lens".to_string(), signature.clone(), "bob.lens".to_string(), "0x02".to_string(), Some(10), None, None).is_err());
+        assert_eq!(failure_codes(), vec!["HANDLE_ALREADY_REGISTERED"]);
+        testing_env!(get_context(accounts(2)).block_timestamp(3 * day).build());
+        assert_eq!(contract.get_social_status(accounts(1), "lens".to_string()), SocialStatus::Expired);
+        contract.register_social("lens".to_string(), signature, "bob.lens".to_string(), "0x02".to_string(), Some(10), None, None).unwrap();
+    }
+
+    #[test]
+    fn test_expiring() {
+        let (verifier, verifier_pk) = keypair(1);
+        testing_env!(get_context(accounts(0)).build());
+        let mut contract = Contract::new(verifier_pk);
+        let day = 24 * 60 * 60 * 1_000_000_000;
+        testing_env!(get_context(accounts(1)).build());
+        let message = messages::social_message(&accounts(1), "lens", "bob.lens", "0x01", None, RequestBound::BlockHeight(10));
+        let signature = verifier.sign(message.as_bytes()).to_bytes().to_vec();
+        contract.register_social("lens".to_string(), signature, "bob.lens".to_string(), "0x01".to_string(), Some(10), None, None).unwrap();
+        assert!(contract.get_expiring(accounts(1), U64(day)).is_empty());
+        let expiring = contract.get_expiring(accounts(1), U64(90 * day));
+        assert_eq!((expiring[0].platform.as_str(), expiring[0].handle.as_str(), expiring[0].expires_at.0), ("lens", "bob.lens", DEFAULT_VALIDITY_NS));
+        // the next write of the account inside the warning window emits the warning, once
+        let warned = || near_sdk::test_utils::get_logs().iter().filter(|log| log.contains("expiring_soon")).count();
+        for keys in [5u32, 6] {
+            testing_env!(get_context(accounts(1)).block_timestamp(80 * day).build());
+            let message = messages::account_info_message(&accounts(1), keys, RequestBound::BlockHeight(10));
+            let signature = verifier.sign(message.as_bytes()).to_bytes().to_vec();
+            contract.update_access_key(signature, keys, Some(10), None).unwrap();
+            assert_eq!(warned(), if keys == 5 { 1 } else { 0 });
+        }
+    }
+
+    #[test]
+    fn test_ownership_transfer() {
+        let (_, verifier_pk) = keypair(1);
+        testing_env!(get_context(accounts(0)).build());
+        let mut contract = Contract::new(verifier_pk);
+        assert_eq!(contract.get_owner(), accounts(0));
+        contract.propose_owner(accounts(3));
+        // nothing changes until the proposed owner accepts
+        assert_eq!(contract.get_owner(), accounts(0));
+        testing_env!(get_context(accounts(3)).build());
+        contract.accept_ownership();
+        assert_eq!(contract.get_owner(), accounts(3));
+        assert!(contract.get_pending_owner().is_none());
+        contract.set_input_limits(InputLimits::default());
+    }
+
+    #[test]
+    #[should_panic(expected = "only the owner can call this method")]
+    fn test_admin_method_requires_owner() {
+        let (_, verifier_pk) = keypair(1);
+        testing_env!(get_context(accounts(0)).build());
+        let mut contract = Contract::new(verifier_pk);
+        testing_env!(get_context(accounts(1)).build());
+        contract.set_input_limits(InputLimits::default());
+    }
+
+    #[test]
+    fn test_additional_verifier() {
+        let (_, admin_pk) = keypair(1);
+        let (verifier, verifier_pk) = keypair(7);
+        testing_env!(get_context(accounts(0)).build());
+        let mut contract = Contract::new(admin_pk);
+        let id = contract.add_verifier(verifier_pk.clone());
+        execute_after_timelock(&mut contract, id);
+        assert_eq!(contract.get_verifiers().len(), 2);
+        testing_env!(get_context(accounts(1)).build());
+        let message = messages::account_info_message(&accounts(1), 12u32, RequestBound::BlockHeight(10));
+        let signature = verifier.sign(message.as_bytes()).to_bytes().to_vec();
+        contract.update_access_key(signature.clone(), 12, Some(10), None).unwrap();
+        // a removed key stops validating at once
+        testing_env!(get_context(accounts(0)).build());
+        contract.remove_verifier(verifier_pk);
+        testing_env!(get_context(accounts(1)).build());
+        assert_eq!(contract.update_access_key(signature, 12, Some(10), None), Err(ContractError::UnverifiedData));
+    }
+
+    #[test]
+    fn test_pause_blocks_writes() {
+        let (verifier, verifier_pk) = keypair(1);
+        testing_env!(get_context(accounts(0)).build());
+        let mut contract = Contract::new(verifier_pk);
+        contract.pause();
+        testing_env!(get_context(accounts(1)).build());
+        let message = messages::account_info_message(&accounts(1), 12u32, RequestBound::BlockHeight(10));
+        let signature = verifier.sign(message.as_bytes()).to_bytes().to_vec();
+        assert_eq!(contract.update_access_key(signature.clone(), 12, Some(10), None), Err(ContractError::Paused));
+        assert!(!contract.connected_to_5_contracts(accounts(1), None));
+        testing_env!(get_context(accounts(0)).build());
+        contract.unpause();
+        testing_env!(get_context(accounts(1)).build());
+        contract.update_access_key(signature, 12, Some(10), None).unwrap();
+    }
+
+    #[test]
+    fn test_pause_single_method() {
+        let (verifier, verifier_pk) = keypair(1);
+        testing_env!(get_context(accounts(0)).build());
+        let mut contract = Contract::new(verifier_pk);
+        contract.set_paused_methods(PAUSE_REGISTER_SOCIAL);
+        testing_env!(get_context(accounts(1)).build());
+        let message = messages::account_info_message(&accounts(1), 12u32, RequestBound::BlockHeight(10));
+        let signature = verifier.sign(message.as_bytes()).to_bytes().to_vec();
+        contract.update_access_key(signature, 12, Some(10), None).unwrap();
+    }
+
+    #[test]
+    fn test_paused_method_rejected() {
+        let (_, verifier_pk) = keypair(1);
+        testing_env!(get_context(accounts(0)).build());
+        let mut contract = Contract::new(verifier_pk);
+        contract.set_paused_methods(PAUSE_REGISTER_SOCIAL | PAUSE_UPDATE_CONTRACT_AGE);
+        testing_env!(get_context(accounts(1)).build());
+        let res = contract.register_social("lens".to_string(), vec![0; 64], "bob.lens".to_string(), "0x01".to_string(), Some(10), None, None);
+        assert_eq!(res, Err(ContractError::MethodPaused));
+    }
+
+    #[test]
+    #[should_panic(expected = "this method is paused")]
+    fn test_unregister_social_paused() {
+        let (_, verifier_pk) = keypair(1);
+        testing_env!(get_context(accounts(0)).build());
+        let mut contract = Contract::new(verifier_pk);
+        contract.set_paused_methods(PAUSE_UNREGISTER_SOCIAL);
+        testing_env!(get_context(accounts(1)).build());
+        contract.unregister_social("lens".to_string());
+    }
+
+    #[test]
+    #[should_panic(expected = "this method is paused")]
+    fn test_dispute_claim_paused() {
+        let (_, verifier_pk) = keypair(1);
+        testing_env!(get_context(accounts(0)).build());
+        let mut contract = Contract::new(verifier_pk);
+        contract.set_paused_methods(PAUSE_DISPUTE_CLAIM);
+        testing_env!(get_context(accounts(2)).build());
+        contract.dispute_claim("lens".to_string(), "bob.lens".to_string());
+    }
+
+    #[test]
+    #[should_panic(expected = "this method is paused")]
+    fn test_finalize_claim_paused() {
+        let (_, verifier_pk) = keypair(1);
+        testing_env!(get_context(accounts(0)).build());
+        let mut contract = Contract::new(verifier_pk);
+        contract.set_paused_methods(PAUSE_FINALIZE_CLAIM);
+        testing_env!(get_context(accounts(2)).build());
+        contract.finalize_claim("lens".to_string(), "bob.lens".to_string());
+    }
+
+    #[test]
+    #[should_panic(expected = "this method is paused")]
+    fn test_link_account_paused() {
+        let (_, verifier_pk) = keypair(1);
+        testing_env!(get_context(accounts(0)).build());
+        let mut contract = Contract::new(verifier_pk);
+        contract.set_paused_methods(PAUSE_LINK_ACCOUNT);
+        testing_env!(get_context(accounts(1)).build());
+        contract.link_account(accounts(2));
+    }
+
+    #[test]
+    #[should_panic(expected = "this method is paused")]
+    fn test_merge_into_paused() {
+        let (_, verifier_pk) = keypair(1);
+        testing_env!(get_context(accounts(0)).build());
+        let mut contract = Contract::new(verifier_pk);
+        contract.set_paused_methods(PAUSE_MERGE_INTO);
+        testing_env!(get_context(accounts(1)).build());
+        contract.merge_into(accounts(2));
+    }
+
+    #[test]
+    #[should_panic(expected = "this method is paused")]
+    fn test_file_dispute_paused() {
+        let (_, verifier_pk) = keypair(1);
+        testing_env!(get_context(accounts(0)).build());
+        let mut contract = Contract::new(verifier_pk);
+        contract.set_paused_methods(PAUSE_FILE_DISPUTE);
+        testing_env!(get_context(accounts(2)).build());
+        contract.file_dispute(accounts(1), "lens".to_string(), "ipfs://evidence".to_string());
+    }
+
+    #[test]
+    #[should_panic(expected = "this method is paused")]
+    fn test_bond_verifier_paused() {
+        let (verifier, verifier_pk) = keypair(1);
+        testing_env!(get_context(accounts(0)).build());
+        let mut contract = Contract::new(verifier_pk.clone());
+        contract.set_paused_methods(PAUSE_BOND_VERIFIER);
+        testing_env!(get_context(accounts(2)).attached_deposit(NearToken::from_near(1)).build());
+        let message = messages::bond_verifier_message(&accounts(0), &accounts(2));
+        contract.bond_verifier(verifier_pk, verifier.sign(message.as_bytes()).to_bytes().to_vec());
+    }
+
+    #[test]
+    #[should_panic(expected = "this method is paused")]
+    fn test_verify_for_paused() {
+        let (_, verifier_pk) = keypair(1);
+        testing_env!(get_context(accounts(0)).build());
+        let mut contract = Contract::new(verifier_pk);
+        contract.set_paused_methods(PAUSE_VERIFY_FOR);
+        testing_env!(get_context(accounts(1)).build());
+        contract.verify_for(accounts(1), "is_human".to_string(), accounts(3), String::new()).detach();
+    }
+
+    #[test]
+    #[should_panic(expected = "this method is paused")]
+    fn test_request_attestation_paused() {
+        let (_, verifier_pk) = keypair(1);
+        testing_env!(get_context(accounts(0)).build());
+        let mut contract = Contract::new(verifier_pk);
+        contract.set_paused_methods(PAUSE_REQUEST_ATTESTATION);
+        testing_env!(get_context(accounts(3)).build());
+        contract.request_attestation(accounts(1), "is_human".to_string());
+    }
+
+    #[test]
+    fn test_platform_validity() {
+        let (verifier, verifier_pk) = keypair(1);
+        testing_env!(get_context(accounts(0)).build());
+        let mut contract = Contract::new(verifier_pk);
+        let day = 24 * 60 * 60 * 1_000_000_000;
+        let id = contract.set_platform_config("lens".to_string(), PlatformConfig { validity_ns: U64(day), ..Default::default() });
+        execute_after_timelock(&mut contract, id);
+        testing_env!(get_context(accounts(1)).build());
+        let message = messages::social_message(&accounts(1), "lens", "bob.lens", "0x01", None, RequestBound::BlockHeight(10));
+        let signature = verifier.sign(message.as_bytes()).to_bytes().to_vec();
+        contract.register_social("lens".to_string(), signature, "bob.lens".to_string(), "0x01".to_string(), Some(10), None, None).unwrap();
+        assert!(contract.connected_to_lens(accounts(1)));
+        testing_env!(get_context(accounts(1)).block_timestamp(2 * day).build());
+        assert!(!contract.connected_to_lens(accounts(1)));
+    }
+
+    #[test]
+    fn test_platform_holder_cap() {
+        let (verifier, verifier_pk) = keypair(1);
+        testing_env!(get_context(accounts(0)).build());
+        let mut contract = Contract::new(verifier_pk);
+        let day = 24 * 60 * 60 * 1_000_000_000;
+        let config = PlatformConfig { validity_ns: U64(10 * day), max_holders: Some(1), multi_handle: true, ..Default::default() };
+        let id = contract.set_platform_config("lens".to_string(), config);
+        execute_after_timelock(&mut contract, id);
+        assert_eq!(contract.remaining_slots("lens".to_string()), Some(1));
+        assert_eq!(contract.remaining_slots("twitter".to_string()), None);
+        let register = |contract: &mut Contract, account_id: AccountId, handle: &str, validity_ns: Option<u64>| {
+            let message = messages::social_message(&account_id, "lens", handle, "0x01", validity_ns, RequestBound::BlockHeight(10));
+            let signature = verifier.sign(message.as_bytes()).to_bytes().to_vec();
+            testing_env!(get_context(account_id).build());
+            contract.register_social("lens".to_string(), signature, handle.to_string(), "0x01".to_string(), Some(10), None, validity_ns.map(U64)).is_ok()
+        };
+        assert!(register(&mut contract, accounts(1), "bob.lens", None));
+        assert_eq!(contract.remaining_slots("lens".to_string()), Some(0));
+        assert!(!register(&mut contract, accounts(2), "carol.lens", None));
+        assert_eq!(failure_codes(), vec!["PLATFORM_AT_CAPACITY"]);
+        // a second, shorter lived handle doesn't shorten the slot the first one holds
+        assert!(register(&mut contract, accounts(1), "bobby.lens", Some(day)));
+        testing_env!(get_context(accounts(2)).block_timestamp(2 * day).build());
+        assert_eq!(contract.remaining_slots("lens".to_string()), Some(0));
+    }
+
+    #[test]
+    fn test_signed_validity() {
+        let (verifier, verifier_pk) = keypair(1);
+        testing_env!(get_context(accounts(0)).build());
+        let mut contract = Contract::new(verifier_pk);
+        let day = 24 * 60 * 60 * 1_000_000_000;
+        let id = contract.set_platform_config("lens".to_string(), PlatformConfig { validity_ns: U64(day), max_validity_ns: Some(U64(10 * day)), ..Default::default() });
+        execute_after_timelock(&mut contract, id);
+        testing_env!(get_context(accounts(1)).build());
+        let register = |contract: &mut Contract, proof: &str, validity_ns: u64| {
+            let message = messages::social_message(&accounts(1), "lens", "bob.lens", proof, Some(validity_ns), RequestBound::BlockHeight(10));
+            let signature = verifier.sign(message.as_bytes()).to_bytes().to_vec();
+            contract.register_social("lens".to_string(), signature, "bob.lens".to_string(), proof.to_string(), Some(10), None, Some(U64(validity_ns))).is_ok()
+        };
+        assert!(!register(&mut contract, "0x01", 11 * day));
+        assert_eq!(failure_codes(), vec!["INVALID_VALIDITY"]);
+        assert!(register(&mut contract, "0x01", 5 * day));
+        testing_env!(get_context(accounts(1)).block_timestamp(4 * day).build());
+        assert!(contract.connected_to_lens(accounts(1)));
+        // a verifier can't stretch a signature over a shorter validity
+        let message = messages::social_message(&accounts(1), "lens", "bob.lens", "0x02", Some(day), RequestBound::BlockHeight(10));
+        let signature = verifier.sign(message.as_bytes()).to_bytes().to_vec();
+        assert!(contract.register_social("lens".to_string(), signature, "bob.lens".to_string(), "0x02".to_string(), Some(10), None, Some(U64(5 * day))).is_err());
+        testing_env!(get_context(accounts(1)).block_timestamp(6 * day).build());
+        assert!(!contract.connected_to_lens(accounts(1)));
+    }
+
+    #[test]
+    fn test_validity_not_read_from_proof() {
+        let (verifier, verifier_pk) = keypair(1);
+        let day = 24 * 60 * 60 * 1_000_000_000;
+        testing_env!(get_context(accounts(0)).build());
+        let mut contract = Contract::new(verifier_pk);
+        let id = contract.set_platform_config("lens".to_string(), PlatformConfig { validity_ns: U64(day), max_validity_ns: Some(U64(10 * day)), ..Default::default() });
+        execute_after_timelock(&mut contract, id);
+        // signed for proof "0x01" with a granted validity, resubmitted with the validity moved into the proof
+        let message = messages::social_message(&accounts(1), "lens", "bob.lens", "0x01", Some(day), RequestBound::BlockHeight(10));
+        let signature = verifier.sign(message.as_bytes()).to_bytes().to_vec();
+        testing_env!(get_context(accounts(1)).build());
+        let proof = format!("0x01,validity:{}", day);
+        assert!(contract.register_social("lens".to_string(), signature, "bob.lens".to_string(), proof, Some(10), None, None).is_err());
+        assert_eq!(failure_codes(), vec!["UNVERIFIED_DATA"]);
+    }
+
+    #[test]
+    fn test_attribute_freshness() {
+        let (verifier, verifier_pk) = keypair(1);
+        testing_env!(get_context(accounts(0)).build());
+        let mut contract = Contract::new(verifier_pk);
+        let day = 24 * 60 * 60 * 1_000_000_000;
+        testing_env!(get_context(accounts(1)).block_timestamp(day).build());
+        let message = messages::account_info_message(&accounts(1), 5u32, RequestBound::BlockHeight(10));
+        let signature = verifier.sign(message.as_bytes()).to_bytes().to_vec();
+        contract.update_access_key(signature, 5, Some(10), None).unwrap();
+        assert_eq!(contract.get_attribute_updates(accounts(1)), AttributeUpdates { access_keys: Some(U64(day)), account_age: None });
+        testing_env!(get_context(accounts(1)).block_timestamp(400 * day).build());
+        assert!(contract.connected_to_5_contracts(accounts(1), None));
+        assert!(contract.connected_to_5_contracts(accounts(1), Some(U64(400 * day))));
+        assert!(!contract.connected_to_5_contracts(accounts(1), Some(U64(365 * day))));
+    }
+
+    #[test]
+    fn test_key_thresholds() {
+        let (verifier, verifier_pk) = keypair(1);
+        testing_env!(get_context(accounts(0)).build());
+        let mut contract = Contract::new(verifier_pk);
+        assert_eq!(contract.get_thresholds().get("keys_light"), Some(&5));
+        contract.set_threshold("keys_light".to_string(), 15);
+        testing_env!(get_context(accounts(1)).build());
+        let message = messages::account_info_message(&accounts(1), 12u32, RequestBound::BlockHeight(10));
+        let signature = verifier.sign(message.as_bytes()).to_bytes().to_vec();
+        contract.update_access_key(signature, 12, Some(10), None).unwrap();
+        assert!(!contract.meets_key_threshold(accounts(1), "keys_light".to_string(), None));
+        assert!(contract.meets_key_threshold(accounts(1), "keys_medium".to_string(), None));
+    }
+
+    #[test]
+    fn test_unknown_platform_rejected() {
+        let (verifier, verifier_pk) = keypair(1);
+        testing_env!(get_context(accounts(1)).build());
+        let mut contract = Contract::new(verifier_pk);
+        let message = messages::social_message(&accounts(1), "Lens", "bob.lens", "0x01", None, RequestBound::BlockHeight(10));
+        let signature = verifier.sign(message.as_bytes()).to_bytes().to_vec();
+        assert!(contract.register_social("Lens".to_string(), signature, "bob.lens".to_string(), "0x01".to_string(), Some(10), None, None).is_err());
+        assert_eq!(failure_codes(), vec!["UNKNOWN_PLATFORM"]);
+    }
+
+    #[test]
+    fn test_revoke_social() {
+        let (verifier, verifier_pk) = keypair(1);
+        testing_env!(get_context(accounts(0)).build());
+        let mut contract = Contract::new(verifier_pk);
+        contract.grant_role(accounts(3), Role::Verifier);
+        testing_env!(get_context(accounts(1)).build());
+        let message = messages::social_message(&accounts(1), "lens", "bob.lens", "0x01", None, RequestBound::BlockHeight(10));
+        let signature = verifier.sign(message.as_bytes()).to_bytes().to_vec();
+        contract.register_social("lens".to_string(), signature, "bob.lens".to_string(), "0x01".to_string(), Some(10), None, None).unwrap();
+        testing_env!(get_context(accounts(3)).build());
+        contract.revoke_social(accounts(1), "lens".to_string(), "account deleted".to_string(), Some(RevocationCode::AccountDeleted));
+        assert!(!contract.connected_to_lens(accounts(1)));
+        assert_eq!(contract.get_revocation(accounts(1), "lens".to_string()).unwrap().reason, "account deleted");
+        assert!(near_sdk::test_utils::get_logs().iter().any(|log| log.starts_with("EVENT_JSON:") && log.contains("social_revoked")));
+        let history = contract.get_revocation_history(accounts(1));
+        assert_eq!((history[0].handle.as_str(), history[0].code), ("bob.lens", RevocationCode::AccountDeleted));
+        assert_eq!(contract.get_revocation_ledger(None, None).len(), 1);
+        // the handle is free again right away
+        testing_env!(get_context(accounts(2)).build());
+        let message = messages::social_message(&accounts(2), "lens", "bob.lens", "0x02", None, RequestBound::BlockHeight(10));
+        let signature = verifier.sign(message.as_bytes()).to_bytes().to_vec();
+        contract.register_social("lens".to_string(), signature, "bob.lens".to_string(), "0x02".to_string(), Some(10), None, None).unwrap();
+    }
+
+    #[test]
+    fn test_banned_account() {
+        let (verifier, verifier_pk) = keypair(1);
+        testing_env!(get_context(accounts(1)).build());
+        let mut contract = Contract::new(verifier_pk);
+        let message = messages::account_info_message(&accounts(1), 12u32, RequestBound::BlockHeight(10));
+        let signature = verifier.sign(message.as_bytes()).to_bytes().to_vec();
+        contract.update_access_key(signature.clone(), 12, Some(10), None).unwrap();
+        assert!(contract.connected_to_5_contracts(accounts(1), None));
+        testing_env!(get_context(accounts(0)).build());
+        contract.ban_account(accounts(1), "SYBIL_FARM".to_string());
+        assert!(!contract.connected_to_5_contracts(accounts(1), None));
+        assert_eq!(contract.score(accounts(1)), 0);
+        assert_eq!(contract.get_ban(accounts(1)).unwrap().reason, "SYBIL_FARM");
+        testing_env!(get_context(accounts(1)).build());
+        assert_eq!(contract.update_access_key(signature, 12, Some(10), None), Err(ContractError::AccountBanned));
+    }
+
+    #[test]
+    fn test_force_release_handle() {
+        let (verifier, verifier_pk) = keypair(1);
+        testing_env!(get_context(accounts(1)).build());
+        let mut contract = Contract::new(verifier_pk);
+        let message = messages::social_message(&accounts(1), "lens", "bob.lens", "0x01", None, RequestBound::BlockHeight(10));
+        let signature = verifier.sign(message.as_bytes()).to_bytes().to_vec();
+        contract.register_social("lens".to_string(), signature, "bob.lens".to_string(), "0x01".to_string(), Some(10), None, None).unwrap();
+        testing_env!(get_context(accounts(0)).build());
+        assert_eq!(contract.force_release_handle("lens".to_string(), "bob.lens".to_string()), accounts(1));
+        assert!(!contract.connected_to_platform(accounts(1), "lens".to_string()));
+        testing_env!(get_context(accounts(2)).build());
+        let message = messages::social_message(&accounts(2), "lens", "bob.lens", "0x02", None, RequestBound::BlockHeight(10));
+        let signature = verifier.sign(message.as_bytes()).to_bytes().to_vec();
+        contract.register_social("lens".to_string(), signature, "bob.lens".to_string(), "0x02".to_string(), Some(10), None, None).unwrap();
+    }
+
+    #[test]
+    fn test_dao_owner_config_change() {
+        let (_, admin_pk) = keypair(1);
+        let (_, verifier_pk) = keypair(7);
+        testing_env!(get_context(accounts(0)).build());
+        let mut contract = Contract::new(admin_pk);
+        let dao: AccountId = "dao.sputnik-dao.near".parse().unwrap();
+        contract.propose_owner(dao.clone());
+        testing_env!(get_context(dao.clone()).build());
+        contract.accept_ownership();
+        // the args a `FunctionCall` proposal would carry
+        let args: near_sdk::serde_json::Value = near_sdk::serde_json::json!({ "change": { "AddVerifier": String::from(&verifier_pk) } });
+        let change = near_sdk::serde_json::from_value(args["change"].clone()).unwrap();
+        assert_eq!(contract.propose_config_change(change), Some(0));
+        assert!(contract.execute_ready_changes().is_empty());
+        testing_env!(get_context(dao).block_timestamp(DEFAULT_TIMELOCK_DELAY_NS).build());
+        assert_eq!(contract.execute_ready_changes(), vec![0]);
+        assert_eq!(contract.get_verifiers().len(), 2);
+    }
+
+    #[test]
+    fn test_registration_fee() {
+        let (verifier, verifier_pk) = keypair(1);
+        let context = get_context(accounts(0));
+        testing_env!(context.build());
+        let mut contract = Contract::new(verifier_pk);
+        contract.set_fee(U128(1_000));
+        let message = messages::social_message(&accounts(1), "lens", "bob.lens", "0x01", None, RequestBound::BlockHeight(10));
+        let signature = verifier.sign(message.as_bytes()).to_bytes().to_vec();
+        testing_env!(get_context(accounts(1)).attached_deposit(NearToken::from_yoctonear(1_000 + MINT_STORAGE_COST)).build());
+        contract.register_social("lens".to_string(), signature, "bob.lens".to_string(), "0x01".to_string(), Some(10), None, None).unwrap();
+        assert_eq!(contract.get_treasury_balance(), U128(1_000));
+        testing_env!(context.build());
+        contract.withdraw_treasury(U128(600), accounts(3));
+        assert_eq!(contract.get_treasury_balance(), U128(400));
+    }
+
+    #[test]
+    fn test_ft_fees() {
+        let (verifier, verifier_pk) = keypair(1);
+        testing_env!(get_context(accounts(0)).build());
+        let mut contract = Contract::new(verifier_pk);
+        contract.set_fee(U128(NearToken::from_near(1).as_yoctonear()));
+        // 5 USDC with 6 decimals per NEAR
+        contract.set_fee_token(accounts(5), Some(U128(5_000_000)));
+        deposit_storage(&mut contract, accounts(1));
+        testing_env!(get_context(accounts(4)).build());
+        assert!(matches!(contract.ft_on_transfer(accounts(1), U128(8_000_000), String::new()), near_sdk::PromiseOrValue::Value(U128(8_000_000))));
+        testing_env!(get_context(accounts(5)).build());
+        assert!(matches!(contract.ft_on_transfer(accounts(1), U128(8_000_000), String::new()), near_sdk::PromiseOrValue::Value(U128(0))));
+        assert_eq!(contract.get_fee_balance(accounts(1), accounts(5)), U128(8_000_000));
+        // the credit pays the fee, the deposit only storage
+        let message = messages::social_message(&accounts(1), "lens", "bob.lens", "0x01", None, RequestBound::BlockHeight(10));
+        let signature = verifier.sign(message.as_bytes()).to_bytes().to_vec();
+        testing_env!(get_context(accounts(1)).attached_deposit(NearToken::from_yoctonear(MINT_STORAGE_COST)).build());
+        contract.register_social("lens".to_string(), signature, "bob.lens".to_string(), "0x01".to_string(), Some(10), None, None).unwrap();
+        assert_eq!(contract.get_fee_balance(accounts(1), accounts(5)), U128(3_000_000));
+        assert_eq!(contract.get_ft_treasury_balance(accounts(5)), U128(5_000_000));
+        assert_eq!(contract.get_treasury_balance(), U128(0));
+        // too little credit left, so the fee is due in NEAR again
+        assert_eq!(contract.registration_fee_due(), NearToken::from_near(1).as_yoctonear());
+    }
+
+    #[test]
+    fn test_insufficient_deposit() {
+        let (verifier, verifier_pk) = keypair(1);
+        testing_env!(get_context(accounts(0)).build());
+        let mut contract = Contract::new(verifier_pk);
+        contract.set_min_deposit(U128(1_000));
+        contract.set_fee(U128(500));
+        let message = messages::account_info_message(&accounts(1), 12u32, RequestBound::BlockHeight(10));
+        let signature = verifier.sign(message.as_bytes()).to_bytes().to_vec();
+        testing_env!(get_context(accounts(1)).attached_deposit(NearToken::from_yoctonear(999)).build());
+        let err = contract.update_access_key(signature, 12, Some(10), None).unwrap_err();
+        assert_eq!(err.to_string(), "insufficient deposit: need 1000 yoctoNEAR, got 999");
+        // a registration needs the fee on top of the minimum
+        let message = messages::social_message(&accounts(1), "lens", "bob.lens", "0x01", None, RequestBound::BlockHeight(10));
+        let signature = verifier.sign(message.as_bytes()).to_bytes().to_vec();
+        testing_env!(get_context(accounts(1)).attached_deposit(NearToken::from_yoctonear(1_499)).build());
+        let res = contract.register_social("lens".to_string(), signature, "bob.lens".to_string(), "0x01".to_string(), Some(10), None, None);
+        assert_eq!(res, Err(ContractError::InsufficientDeposit { need: 1_500, got: 1_499 }));
+    }
+
+    #[test]
+    fn test_stats() {
+        let (verifier, verifier_pk) = keypair(1);
+        testing_env!(get_context(accounts(1)).build());
+        let mut contract = Contract::new(verifier_pk);
+        let message = messages::social_message(&accounts(1), "lens", "bob.lens", "0x01", None, RequestBound::BlockHeight(10));
+        let signature = verifier.sign(message.as_bytes()).to_bytes().to_vec();
+        contract.register_social("lens".to_string(), signature, "bob.lens".to_string(), "0x01".to_string(), Some(10), None, None).unwrap();
+        // only the proof's digest is logged
+        let logged = format!("registered {} on lens with hex proof {}", accounts(1), proofs::digest_hex(&env::sha256_array(b"0x01")));
+        assert!(near_sdk::test_utils::get_logs().contains(&logged));
+        testing_env!(get_context(accounts(0)).build());
+        contract.revoke_social(accounts(1), "lens".to_string(), "deleted".to_string(), None);
+        let stats = contract.stats(None);
+        assert_eq!((stats.registrations.0, stats.revocations.0, stats.recent_registrations.0), (1, 1, 1));
+        assert_eq!(stats.platforms[0].platform, "lens");
+        // the registration drops out of the window after `days`
+        testing_env!(get_context(accounts(0)).block_timestamp(2 * 24 * 60 * 60 * 1_000_000_000).build());
+        assert_eq!(contract.stats(Some(2)).recent_registrations.0, 0);
+        assert_eq!(contract.stats(Some(3)).recent_registrations.0, 1);
+    }
+
+    #[test]
+    fn test_commit_snapshot() {
+        let (verifier, verifier_pk) = keypair(1);
+        testing_env!(get_context(accounts(0)).build());
+        let mut contract = Contract::new(verifier_pk);
+        for (account, keys) in [(accounts(1), 12u32), (accounts(2), 3), (accounts(3), 12)] {
+            testing_env!(get_context(account.clone()).build());
+            let message = messages::account_info_message(&account, keys, RequestBound::BlockHeight(10));
+            let signature = verifier.sign(message.as_bytes()).to_bytes().to_vec();
+            contract.update_access_key(signature, keys, Some(10), None).unwrap();
+        }
+        testing_env!(get_context(accounts(0)).build());
+        assert!(!contract.commit_snapshot("keys_light".to_string(), 2));
+        assert!(contract.get_latest_snapshot().is_none());
+        assert!(contract.commit_snapshot("keys_light".to_string(), 2));
+        let snapshot = contract.get_latest_snapshot().unwrap();
+        assert_eq!(snapshot.leaf_count, 2);
+        // both leaves pair up, then the root climbs the remaining levels next to empty subtrees
+        let hash = |left: [u8; 32], right: [u8; 32]| env::sha256_array([left, right].concat());
+        let (mut node, mut zero) = (hash(env::sha256_array(b"bob"), env::sha256_array(b"danny")), [0; 32]);
+        for _ in 1..32 {
+            zero = hash(zero, zero);
+            node = hash(node, zero);
+        }
+        assert_eq!(snapshot.root.0, node.to_vec());
+    }
+
+    #[test]
+    fn test_rule_snapshot() {
+        let (verifier, verifier_pk) = keypair(1);
+        testing_env!(get_context(accounts(0)).build());
+        let mut contract = Contract::new(verifier_pk);
+        contract.set_rule("keys".to_string(), RuleExpr::MinAccessKeys(10));
+        for (account, keys) in [(accounts(1), 12u32), (accounts(2), 3), (accounts(3), 12)] {
+            testing_env!(get_context(account.clone()).build());
+            let message = messages::account_info_message(&account, keys, RequestBound::BlockHeight(10));
+            let signature = verifier.sign(message.as_bytes()).to_bytes().to_vec();
+            contract.update_access_key(signature, keys, Some(10), None).unwrap();
+        }
+        testing_env!(get_context(accounts(0)).build());
+        assert_eq!(contract.commit_rule_snapshot("keys".to_string(), 2), None);
+        // registering while the snapshot is built doesn't get an account in
+        testing_env!(get_context(accounts(4)).build());
+        let message = messages::account_info_message(&accounts(4), 12u32, RequestBound::BlockHeight(10));
+        let signature = verifier.sign(message.as_bytes()).to_bytes().to_vec();
+        contract.update_access_key(signature, 12, Some(10), None).unwrap();
+        testing_env!(get_context(accounts(0)).build());
+        assert_eq!(contract.commit_rule_snapshot("keys".to_string(), 2), Some(0));
+        assert_eq!(contract.get_rule_snapshot(0).unwrap().passed, 2);
+        assert!(contract.passed_at_snapshot(0, accounts(1)));
+        assert!(!contract.passed_at_snapshot(0, accounts(2)));
+        assert!(contract.passed_at_snapshot(0, accounts(3)));
+        assert!(!contract.passed_at_snapshot(0, accounts(4)));
+        // danny is the second leaf, so bob's leaf is its first sibling
+        let hash = |left: [u8; 32], right: [u8; 32]| env::sha256_array([left, right].concat());
+        let mut proof = vec![env::sha256_array(b"bob")];
+        let mut zero = [0; 32];
+        for _ in 1..32 {
+            zero = hash(zero, zero);
+            proof.push(zero);
+        }
+        assert!(contract.verify_snapshot_proof(0, accounts(3), 1, proof.clone()));
+        assert!(!contract.verify_snapshot_proof(0, accounts(3), 0, proof.clone()));
+        assert!(!contract.verify_snapshot_proof(0, accounts(4), 1, proof));
+    }
+
+    #[test]
+    fn test_unregister_social() {
+        let (verifier, verifier_pk) = keypair(1);
+        let mut context = get_context(accounts(1));
+        testing_env!(context.build());
+        let mut contract = Contract::new(verifier_pk);
+        // as writing the state at the end of `new` would, so the seeded defaults aren't charged to the registration
+        contract.flush_collections();
+        let message = messages::social_message(&accounts(1), "lens", "bob.lens", "0x01", None, RequestBound::BlockHeight(10));
+        let signature = verifier.sign(message.as_bytes()).to_bytes().to_vec();
+        testing_env!(context.attached_deposit(NearToken::from_yoctonear(MINT_STORAGE_COST)).build());
+        contract.register_social("lens".to_string(), signature, "bob.lens".to_string(), "0x01".to_string(), Some(10), None, None).unwrap();
+        testing_env!(context.attached_deposit(NearToken::from_yoctonear(0)).storage_usage(env::storage_usage()).build());
+        contract.unregister_social("lens".to_string());
+        assert!(!contract.connected_to_lens(accounts(1)));
+        // the storage the registration paid for is refunded, except what its handle history entry keeps using
+        let balance = contract.storage_balance_of(accounts(1)).unwrap();
+        assert!(balance.total.0 < 200 * env::storage_byte_cost().as_yoctonear());
+        let history = contract.get_handle_history(accounts(1), None, None);
+        assert_eq!((history[0].handle.as_str(), history[0].reason_released), ("bob.lens", ReleaseReason::Unregistered));
+    }
+
+    #[test]
+    fn test_renew_social() {
+        let (verifier, verifier_pk) = keypair(1);
+        testing_env!(get_context(accounts(1)).build());
+        let mut contract = Contract::new(verifier_pk);
+        let message = messages::social_message(&accounts(1), "lens", "bob.lens", "0x01", None, RequestBound::BlockHeight(10));
+        let signature = verifier.sign(message.as_bytes()).to_bytes().to_vec();
+        contract.register_social("lens".to_string(), signature, "bob.lens".to_string(), "0x01".to_string(), Some(10), None, None).unwrap();
+        // a month before the 3 month expiry
+        let now = 60 * 24 * 60 * 60 * 1_000_000_000;
+        testing_env!(get_context(accounts(1)).block_timestamp(now).build());
+        let message = messages::renew_social_message(&accounts(1), "lens", "bob.lens", RequestBound::BlockHeight(10));
+        let signature = verifier.sign(message.as_bytes()).to_bytes().to_vec();
+        contract.renew_social("lens".to_string(), None, signature, Some(10), None).unwrap();
+        testing_env!(get_context(accounts(1)).block_timestamp(now + 80 * 24 * 60 * 60 * 1_000_000_000).build());
+        assert!(contract.connected_to_lens(accounts(1)));
+        assert!(contract.verify_proof_digest(accounts(1), "lens".to_string(), "0x01".to_string()));
+        let message = messages::renew_social_message(&accounts(1), "twitter", "bob", RequestBound::BlockHeight(10));
+        let signature = verifier.sign(message.as_bytes()).to_bytes().to_vec();
+        assert_eq!(contract.renew_social("twitter".to_string(), None, signature, Some(10), None), Err(ContractError::NotRegistered));
+    }
+
+    #[test]
+    fn test_multiple_handles() {
+        let (verifier, verifier_pk) = keypair(1);
+        testing_env!(get_context(accounts(0)).build());
+        let mut contract = Contract::new(verifier_pk);
+        let id = contract.set_platform_config("lens".to_string(), PlatformConfig { multi_handle: true, ..Default::default() });
+        execute_after_timelock(&mut contract, id);
+        testing_env!(get_context(accounts(1)).build());
+        for (handle, proof) in [("bob.lens", "0x01"), ("bob2.lens", "0x02")] {
+            let message = messages::social_message(&accounts(1), "lens", handle, proof, None, RequestBound::BlockHeight(10));
+            let signature = verifier.sign(message.as_bytes()).to_bytes().to_vec();
+            contract.register_social("lens".to_string(), signature, handle.to_string(), proof.to_string(), Some(10), None, None).unwrap();
+        }
+        assert_eq!(contract.get_handles(accounts(1), "lens".to_string()), vec!["bob.lens", "bob2.lens"]);
+        // single handle platforms still replace the previous handle
+        for (handle, proof) in [("bob", "0x01"), ("bob2", "0x02")] {
+            let message = messages::social_message(&accounts(1), "twitter", handle, proof, None, RequestBound::BlockHeight(10));
+            let signature = verifier.sign(message.as_bytes()).to_bytes().to_vec();
+            contract.register_social("twitter".to_string(), signature, handle.to_string(), proof.to_string(), Some(10), None, None).unwrap();
+        }
+        assert_eq!(contract.get_handles(accounts(1), "twitter".to_string()), vec!["bob2"]);
+        testing_env!(get_context(accounts(0)).build());
+        contract.force_release_handle("lens".to_string(), "bob.lens".to_string());
+        assert_eq!(contract.get_handles(accounts(1), "lens".to_string()), vec!["bob2.lens"]);
+        assert!(contract.connected_to_lens(accounts(1)));
+        let reasons: Vec<_> = contract.get_handle_history(accounts(1), None, None).into_iter().map(|record| record.reason_released).collect();
+        assert_eq!(reasons, vec![ReleaseReason::Replaced, ReleaseReason::ForceReleased]);
+    }
+
+    #[test]
+    fn test_transfer_handle() {
+        let (verifier, verifier_pk) = keypair(1);
+        testing_env!(get_context(accounts(1)).build());
+        let mut contract = Contract::new(verifier_pk);
+        let message = messages::social_message(&accounts(1), "lens", "bob.lens", "0x01", None, RequestBound::BlockHeight(10));
+        let signature = verifier.sign(message.as_bytes()).to_bytes().to_vec();
+        contract.register_social("lens".to_string(), signature, "bob.lens".to_string(), "0x01".to_string(), Some(10), None, None).unwrap();
+        let message = messages::transfer_handle_message(&accounts(1), &accounts(2), "lens", "bob.lens", RequestBound::BlockHeight(10));
+        let signature = verifier.sign(message.as_bytes()).to_bytes().to_vec();
+        contract.transfer_handle("lens".to_string(), "bob.lens".to_string(), accounts(2), signature.clone(), Some(10), None).unwrap();
+        assert!(!contract.connected_to_lens(accounts(1)));
+        assert!(contract.connected_to_lens(accounts(2)));
+        assert!(contract.verify_proof_digest(accounts(2), "lens".to_string(), "0x01".to_string()));
+        assert_eq!(contract.get_handle_history(accounts(1), None, None)[0].reason_released, ReleaseReason::Transferred);
+        // the co-signature is bound to the sender
+        testing_env!(get_context(accounts(2)).build());
+        let res = contract.transfer_handle("lens".to_string(), "bob.lens".to_string(), accounts(3), signature, Some(10), None);
+        assert_eq!(res, Err(ContractError::UnverifiedData));
+        let res = contract.transfer_handle("lens".to_string(), "bob.lens".to_string(), accounts(2), vec![0; 64], Some(10), None);
+        assert_eq!(res, Err(ContractError::TransferToSelf));
+        // the receiver has to be able to register the handle itself
+        testing_env!(get_context(accounts(0)).build());
+        contract.ban_account(accounts(3), "sybil".to_string());
+        testing_env!(get_context(accounts(2)).build());
+        let message = messages::transfer_handle_message(&accounts(2), &accounts(3), "lens", "bob.lens", RequestBound::BlockHeight(10));
+        let signature = verifier.sign(message.as_bytes()).to_bytes().to_vec();
+        let res = contract.transfer_handle("lens".to_string(), "bob.lens".to_string(), accounts(3), signature, Some(10), None);
+        assert_eq!(res, Err(ContractError::AccountBanned));
+    }
+
+    #[test]
+    fn test_transfer_handle_paused() {
+        let (_, verifier_pk) = keypair(1);
+        testing_env!(get_context(accounts(0)).build());
+        let mut contract = Contract::new(verifier_pk);
+        contract.set_paused_methods(PAUSE_TRANSFER_HANDLE);
+        testing_env!(get_context(accounts(1)).build());
+        let res = contract.transfer_handle("lens".to_string(), "bob.lens".to_string(), accounts(2), vec![0; 64], Some(10), None);
+        assert_eq!(res, Err(ContractError::MethodPaused));
+    }
+
+    #[test]
+    fn test_handle_cooldown() {
+        let (verifier, verifier_pk) = keypair(1);
+        let day = 24 * 60 * 60 * 1_000_000_000;
+        testing_env!(get_context(accounts(0)).build());
+        let mut contract = Contract::new(verifier_pk);
+        let id = contract.set_platform_config("lens".to_string(), PlatformConfig { release_cooldown_ns: U64(day), ..Default::default() });
+        execute_after_timelock(&mut contract, id);
+        testing_env!(get_context(accounts(1)).build());
+        let message = messages::social_message(&accounts(1), "lens", "bob.lens", "0x01", None, RequestBound::BlockHeight(10));
+        let signature = verifier.sign(message.as_bytes()).to_bytes().to_vec();
+        contract.register_social("lens".to_string(), signature, "bob.lens".to_string(), "0x01".to_string(), Some(10), None, None).unwrap();
+        contract.unregister_social("lens".to_string());
+        let message = messages::social_message(&accounts(2), "lens", "bob.lens", "0x02", None, RequestBound::BlockHeight(10));
+        let signature = verifier.sign(message.as_bytes()).to_bytes().to_vec();
+        testing_env!(get_context(accounts(2)).build());
+        assert!(contract.register_social("lens".to_string(), signature.clone(), "bob.lens".to_string(), "0x02".to_string(), Some(10), None, None).is_err());
+        assert_eq!(failure_codes(), vec!["HANDLE_COOLING_DOWN"]);
+        testing_env!(get_context(accounts(2)).block_timestamp(day).build());
+        contract.register_social("lens".to_string(), signature, "bob.lens".to_string(), "0x02".to_string(), Some(10), None, None).unwrap();
+        assert!(contract.connected_to_lens(accounts(2)));
+    }
+
+    #[test]
+    fn test_handle_normalization() {
+        let (verifier, verifier_pk) = keypair(1);
+        testing_env!(get_context(accounts(1)).build());
+        let mut contract = Contract::new(verifier_pk);
+        let message = messages::social_message(&accounts(1), "lens", " Bob.Lens", "0x01", None, RequestBound::BlockHeight(10));
+        let signature = verifier.sign(message.as_bytes()).to_bytes().to_vec();
+        contract.register_social("lens".to_string(), signature, " Bob.Lens".to_string(), "0x01".to_string(), Some(10), None, None).unwrap();
+        assert_eq!(contract.get_handles(accounts(1), "lens".to_string()), vec!["bob.lens"]);
+        let message = messages::social_message(&accounts(2), "lens", "BOB.LENS", "0x02", None, RequestBound::BlockHeight(10));
+        let signature = verifier.sign(message.as_bytes()).to_bytes().to_vec();
+        testing_env!(get_context(accounts(2)).build());
+        assert!(contract.register_social("lens".to_string(), signature, "BOB.LENS".to_string(), "0x02".to_string(), Some(10), None, None).is_err());
+        assert_eq!(failure_codes(), vec!["HANDLE_ALREADY_REGISTERED"]);
+        // a decomposed "é" is stored composed
+        let message = messages::social_message(&accounts(2), "twitter", "Jose\u{301}", "0x02", None, RequestBound::BlockHeight(10));
+        let signature = verifier.sign(message.as_bytes()).to_bytes().to_vec();
+        contract.register_social("twitter".to_string(), signature, "Jose\u{301}".to_string(), "0x02".to_string(), Some(10), None, None).unwrap();
+        assert_eq!(contract.get_handles(accounts(2), "twitter".to_string()), vec!["jos\u{e9}"]);
+
+        // a handle stored before normalization, held by another account with a later expiry
+        let mut legacy = contract.socials_on(&accounts(1), "lens");
+        legacy[0].handle = "Bob.Lens".to_string();
+        legacy[0].expiry_date += 1;
+        contract.socials.insert((accounts(3), "lens".to_string()), legacy.into_iter().map(Into::into).collect());
+        contract.social_platforms.insert(accounts(3), vec!["lens".to_string()]);
+        contract.handles.insert(("lens".to_string(), "Bob.Lens".to_string()), accounts(3));
+        testing_env!(get_context(accounts(0)).build());
+        assert_eq!(contract.normalize_stored_handles(10), 1);
+        assert_eq!(contract.normalize_stored_handles(10), 0);
+        assert_eq!(contract.get_handles(accounts(3), "lens".to_string()), vec!["bob.lens"]);
+        assert!(contract.get_handles(accounts(1), "lens".to_string()).is_empty());
+        assert_eq!(contract.get_handle_history(accounts(1), None, None)[0].reason_released, ReleaseReason::TakenOver);
+    }
+
+    #[test]
+    fn test_handle_rules() {
+        let (verifier, verifier_pk) = keypair(1);
+        testing_env!(get_context(accounts(0)).build());
+        let mut contract = Contract::new(verifier_pk);
+        let rules = HandleRules { charset: Some("abcdefghijklmnopqrstuvwxyz0123456789._".to_string()), min_len: 6, max_len: Some(31), required_suffix: Some(".lens".to_string()) };
+        let id = contract.set_platform_config("lens".to_string(), PlatformConfig { handle_rules: Some(rules), ..Default::default() });
+        execute_after_timelock(&mut contract, id);
+        testing_env!(get_context(accounts(1)).build());
+        for (handle, accepted) in [("bob", false), ("bob!.lens", false), (".lens", false), ("Bob.lens", true)] {
+            let message = messages::social_message(&accounts(1), "lens", handle, "0x01", None, RequestBound::BlockHeight(10));
+            let signature = verifier.sign(message.as_bytes()).to_bytes().to_vec();
+            assert_eq!(contract.register_social("lens".to_string(), signature, handle.to_string(), "0x01".to_string(), Some(10), None, None).is_ok(), accepted, "{}", handle);
+        }
+        assert_eq!(failure_codes(), vec!["MALFORMED_HANDLE"; 3]);
+    }
+
+    #[test]
+    fn test_platform_registry() {
+        let (verifier, verifier_pk) = keypair(1);
+        let (platform_verifier, platform_pk) = keypair(2);
+        testing_env!(get_context(accounts(0)).build());
+        let mut contract = Contract::new(verifier_pk);
+        contract.add_platform("github".to_string(), "GitHub".to_string());
+        assert_eq!(contract.get_platform("github".to_string()).unwrap().display_name, "GitHub");
+        let id = contract.set_platform_verifier("github".to_string(), Some(platform_pk));
+        execute_after_timelock(&mut contract, id);
+        // added disabled, the platform only takes registrations once enabling it executes
+        testing_env!(get_context(accounts(1)).build());
+        let message = messages::social_message(&accounts(1), "github", "bob", "0x01", None, RequestBound::BlockHeight(10));
+        let signature = platform_verifier.sign(message.as_bytes()).to_bytes().to_vec();
+        assert_eq!(contract.register_social("github".to_string(), signature, "bob".to_string(), "0x01".to_string(), Some(10), None, None), Err(ContractError::UnknownPlatform));
+        testing_env!(get_context(accounts(0)).build());
+        let id = contract.allow_platform("github".to_string());
+        execute_after_timelock(&mut contract, id);
+        testing_env!(get_context(accounts(1)).build());
+        let signature = verifier.sign(message.as_bytes()).to_bytes().to_vec();
+        assert!(contract.register_social("github".to_string(), signature, "bob".to_string(), "0x01".to_string(), Some(10), None, None).is_err());
+        assert_eq!(failure_codes(), vec!["UNVERIFIED_DATA"]);
+        let signature = platform_verifier.sign(message.as_bytes()).to_bytes().to_vec();
+        contract.register_social("github".to_string(), signature, "bob".to_string(), "0x01".to_string(), Some(10), None, None).unwrap();
+        testing_env!(get_context(accounts(0)).build());
+        let id = contract.disallow_platform("github".to_string());
+        // staged behind the timelock, the platform stays enabled until the change executes
+        assert!(contract.get_platform("github".to_string()).unwrap().enabled);
+        execute_after_timelock(&mut contract, id);
+        assert_eq!(contract.get_allowed_platforms(), vec!["lens", "farcaster", "twitter", "near_social"]);
+        assert!(!contract.get_platform("github".to_string()).unwrap().enabled);
+        let id = contract.allow_platform("github".to_string());
+        execute_after_timelock(&mut contract, id);
+        assert!(contract.get_platform("github".to_string()).unwrap().enabled);
+    }
+
+    #[test]
+    fn test_register_social_via_oracle() {
+        let (_, verifier_pk) = keypair(1);
+        let address = "0x000000000000000000000000000000000000dead".to_string();
+        testing_env!(get_context(accounts(0)).build());
+        let mut contract = Contract::new(verifier_pk);
+        let id = contract.set_platform_oracle("lens".to_string(), Some(accounts(4)));
+        execute_after_timelock(&mut contract, id);
+        contract.evm_addresses.insert(accounts(1), vec![address.clone()]);
+        testing_env!(get_context(accounts(1)).build());
+        contract.register_social_via_oracle("lens".to_string(), "bob.lens".to_string(), address.clone()).detach();
+        deposit_storage(&mut contract, accounts(1));
+        // the callback, once with the oracle rejecting and once confirming
+        testing_env!(get_context(env::current_account_id()).build());
+        assert!(!contract.on_oracle_confirmed(accounts(1), "lens".to_string(), "bob.lens".to_string(), address.clone(), accounts(4), U128(0), Ok(false)));
+        assert_eq!(failure_codes(), vec!["ORACLE_REJECTED"]);
+        assert!(contract.on_oracle_confirmed(accounts(1), "lens".to_string(), "bob.lens".to_string(), address.clone(), accounts(4), U128(0), Ok(true)));
+        assert!(contract.connected_to_lens(accounts(1)));
+        assert_eq!(contract.get_provenance(accounts(1), "lens".to_string(), "bob.lens".to_string()), Some(Provenance::Oracle(accounts(4))));
+        // once it expired, the oracle confirming the same address again renews it
+        let expiry = contract.social(&accounts(1), "lens").unwrap().expiry_date;
+        testing_env!(get_context(env::current_account_id()).block_timestamp(expiry + 1).build());
+        assert!(contract.on_oracle_confirmed(accounts(1), "lens".to_string(), "bob.lens".to_string(), address, accounts(4), U128(0), Ok(true)));
+        assert!(contract.social(&accounts(1), "lens").unwrap().expiry_date > expiry + 1);
+    }
+
+    #[test]
+    fn test_mint_sbt() {
+        let (verifier, verifier_pk) = keypair(1);
+        testing_env!(get_context(accounts(0)).build());
+        let mut contract = Contract::new(verifier_pk);
+        contract.set_rule("lens".to_string(), RuleExpr::Platform("lens".to_string()));
+        contract.set_sbt_config(Some(SbtConfig { registry: accounts(4), classes: vec![("lens".to_string(), 1)] }));
+        testing_env!(get_context(accounts(1)).attached_deposit(NearToken::from_yoctonear(MINT_STORAGE_COST)).build());
+        let message = messages::social_message(&accounts(1), "lens", "bob.lens", "0x01", None, RequestBound::BlockHeight(10));
+        let signature = verifier.sign(message.as_bytes()).to_bytes().to_vec();
+        contract.register_social("lens".to_string(), signature, "bob.lens".to_string(), "0x01".to_string(), Some(10), None, None).unwrap();
+        // the token expires with the attestation
+        let expiry = contract.social(&accounts(1), "lens").unwrap().expiry_date;
+        assert_eq!(contract.credential_expiry(&accounts(1), 0), Some(expiry));
+        deposit_storage(&mut contract, accounts(1));
+        contract.mint_sbt("lens".to_string()).detach();
+        testing_env!(get_context(env::current_account_id()).build());
+        assert_eq!(contract.on_sbt_minted(accounts(1), "lens".to_string(), U128(0), Ok(vec![7])), Some(7));
+        assert_eq!(contract.get_sbt_token(accounts(1), "lens".to_string()), Some(7));
+        // i-am-human consumers see the class the account qualifies for
+        let tokens = contract.sbt_tokens_by_owner(accounts(1), None, None, None, None);
+        assert_eq!(tokens.len(), 1);
+        assert_eq!(tokens[0].0, env::current_account_id());
+        assert_eq!((tokens[0].1[0].token, tokens[0].1[0].metadata.expires_at), (1, Some(expiry / 1_000_000)));
+        assert_eq!(contract.sbt_supply_by_owner(accounts(1), env::current_account_id(), Some(2)), 0);
+        assert!(contract.sbt_tokens_by_owner(accounts(2), None, None, None, None).is_empty());
+    }
+
+    #[test]
+    fn test_optimistic_claims() {
+        let (verifier, verifier_pk) = keypair(1);
+        let day = 24 * 60 * 60 * 1_000_000_000;
+        let bond = 1_000_000;
+        testing_env!(get_context(accounts(0)).build());
+        let mut contract = Contract::new(verifier_pk);
+        let optimistic = OptimisticConfig { bond: U128(bond), challenge_window_ns: U64(day) };
+        let id = contract.set_platform_config("twitter".to_string(), PlatformConfig { optimistic: Some(optimistic), ..Default::default() });
+        execute_after_timelock(&mut contract, id);
+        let deposit = NearToken::from_yoctonear(bond + MINT_STORAGE_COST);
+        for (account, handle) in [(accounts(1), "bob"), (accounts(2), "carol")] {
+            testing_env!(get_context(account).attached_deposit(deposit).build());
+            contract.submit_claim("twitter".to_string(), handle.to_string(), "https://x.com/status/1".to_string());
+        }
+        // a pending claim holds the handle against regular registrations
+        let message = messages::social_message(&accounts(3), "twitter", "carol", "0x03", None, RequestBound::BlockHeight(10));
+        let signature = verifier.sign(message.as_bytes()).to_bytes().to_vec();
+        testing_env!(get_context(accounts(3)).build());
+        assert!(contract.register_social("twitter".to_string(), signature, "carol".to_string(), "0x03".to_string(), Some(10), None, None).is_err());
+        assert_eq!(failure_codes(), vec!["CLAIM_PENDING"]);
+        testing_env!(get_context(accounts(3)).attached_deposit(NearToken::from_yoctonear(bond)).build());
+        contract.dispute_claim("twitter".to_string(), "carol".to_string());
+        deposit_storage(&mut contract, accounts(1));
+        testing_env!(get_context(accounts(0)).block_timestamp(day).build());
+        assert!(contract.finalize_claim("twitter".to_string(), "bob".to_string()));
+        assert!(!contract.resolve_claim("twitter".to_string(), "carol".to_string(), false));
+        assert_eq!(contract.get_handles(accounts(1), "twitter".to_string()), vec!["bob"]);
+        assert_eq!(contract.get_provenance(accounts(1), "twitter".to_string(), "bob".to_string()), Some(Provenance::Optimistic));
+        assert!(contract.get_handles(accounts(2), "twitter".to_string()).is_empty());
+        assert!(contract.get_claim("twitter".to_string(), "carol".to_string()).is_none());
+    }
+
+    #[test]
+    fn test_verify_near_social() {
+        let (_, verifier_pk) = keypair(1);
+        testing_env!(get_context(accounts(1)).build());
+        let mut contract = Contract::new(verifier_pk);
+        contract.verify_near_social().detach();
+        let claim = contract.get_near_social_claim(accounts(1));
+        deposit_storage(&mut contract, accounts(1));
+        let profile = |claim: &str| Ok(near_sdk::serde_json::json!({ accounts(1).as_str(): { "profile": { "sybil_claim": claim } } }));
+        testing_env!(get_context(env::current_account_id()).build());
+        assert!(!contract.on_near_social_profile(accounts(1), U128(0), profile("near_social_claim,other.near,bob")));
+        assert_eq!(failure_codes(), vec!["PROFILE_CLAIM_MISSING"]);
+        assert!(contract.on_near_social_profile(accounts(1), U128(0), profile(&claim)));
+        assert_eq!(contract.get_handles(accounts(1), "near_social".to_string()), vec![accounts(1).to_string()]);
+        assert_eq!(contract.get_provenance(accounts(1), "near_social".to_string(), accounts(1).to_string()), Some(Provenance::NearSocial));
+        // the same claim still in the profile renews the attestation once it expired
+        let expiry = contract.social(&accounts(1), "near_social").unwrap().expiry_date;
+        testing_env!(get_context(env::current_account_id()).block_timestamp(expiry + 1).build());
+        assert!(contract.on_near_social_profile(accounts(1), U128(0), profile(&claim)));
+        assert!(contract.social(&accounts(1), "near_social").unwrap().expiry_date > expiry + 1);
+    }
+
+    #[test]
+    fn test_vouches() {
+        let (verifier, verifier_pk) = keypair(1);
+        testing_env!(get_context(accounts(0)).build());
+        let mut contract = Contract::new(verifier_pk);
+        contract.set_vouch_config(VouchConfig { min_score: 0, full_stake: U128(100), max_per_day: 5 });
+        // two vouchers with 20 access keys, a score of 30 each
+        for voucher in [accounts(1), accounts(4)] {
+            testing_env!(get_context(voucher.clone()).build());
+            let message = messages::account_info_message(&voucher, 20u32, RequestBound::BlockHeight(10));
+            let signature = verifier.sign(message.as_bytes()).to_bytes().to_vec();
+            contract.update_access_key(signature, 20, Some(10), None).unwrap();
+            deposit_storage(&mut contract, voucher.clone());
+            testing_env!(get_context(voucher).attached_deposit(NearToken::from_yoctonear(100)).build());
+            contract.vouch_for(accounts(2));
+        }
+        deposit_storage(&mut contract, accounts(2));
+        testing_env!(get_context(accounts(2)).attached_deposit(NearToken::from_yoctonear(100)).build());
+        contract.vouch_for(accounts(3));
+        assert_eq!(contract.vouch_count(accounts(2)), 2);
+        assert_eq!(contract.trust_score(accounts(2)), 12);
+        // accounts(2) has no score of its own, so passes on a share of half its trust
+        assert_eq!(contract.trust_score(accounts(3)), 1);
+        testing_env!(get_context(accounts(1)).build());
+        contract.revoke_vouch(accounts(2));
+        assert_eq!(contract.vouched_by(accounts(2))[0].voucher, accounts(4));
+        assert_eq!(contract.trust_score(accounts(2)), 6);
+    }
+
+    #[test]
+    fn test_vouch_points_per_identity() {
+        let (_, verifier_pk) = keypair(1);
+        testing_env!(get_context(accounts(0)).build());
+        let mut contract = Contract::new(verifier_pk);
+        contract.set_vouch_config(VouchConfig { min_score: 0, full_stake: U128(100), max_per_day: 5 });
+        contract.set_score_weights(ScoreWeights { per_vouch: 5, ..Default::default() });
+        testing_env!(get_context(accounts(1)).attached_deposit(NearToken::from_yoctonear(MINT_STORAGE_COST)).build());
+        contract.link_account(accounts(4));
+        testing_env!(get_context(accounts(4)).attached_deposit(NearToken::from_yoctonear(MINT_STORAGE_COST)).build());
+        contract.link_account(accounts(1));
+        for voucher in [accounts(1), accounts(4), accounts(3)] {
+            deposit_storage(&mut contract, voucher.clone());
+            testing_env!(get_context(voucher).attached_deposit(NearToken::from_yoctonear(100)).build());
+            contract.vouch_for(accounts(2));
+        }
+        // accounts(1) and accounts(4) are one identity, so their vouches count once
+        assert_eq!(contract.score(accounts(2)), 10);
+        testing_env!(get_context(accounts(0)).build());
+        contract.set_rule("two_vouches".to_string(), RuleExpr::MinVouches(2));
+        contract.set_rule("three_vouches".to_string(), RuleExpr::MinVouches(3));
+        assert!(contract.evaluate_rule("two_vouches".to_string(), accounts(2)));
+        assert!(!contract.evaluate_rule("three_vouches".to_string(), accounts(2)));
+    }
+
+    #[test]
+    fn test_score_weights() {
+        let (verifier, verifier_pk) = keypair(1);
+        testing_env!(get_context(accounts(0)).build());
+        let mut contract = Contract::new(verifier_pk);
+        testing_env!(get_context(accounts(1)).build());
+        let message = messages::account_info_message(&accounts(1), 20u32, RequestBound::BlockHeight(10));
+        let signature = verifier.sign(message.as_bytes()).to_bytes().to_vec();
+        contract.update_access_key(signature, 20, Some(10), None).unwrap();
+        assert_eq!(contract.score(accounts(1)), 30);
+        testing_env!(get_context(accounts(0)).build());
+        contract.set_score_weights(ScoreWeights { keys_light: 40, keys_heavy: 0, per_vouch: 5, ..Default::default() });
+        assert_eq!(contract.score(accounts(1)), 40);
+        assert!(contract.has_min_score(accounts(1), 40));
+        assert!(!contract.has_min_score(accounts(1), 41));
+    }
+
+    #[test]
+    fn test_sample_verified_accounts() {
+        let (verifier, verifier_pk) = keypair(1);
+        testing_env!(get_context(accounts(0)).build());
+        let mut contract = Contract::new(verifier_pk);
+        for (i, keys) in [(1, 20u32), (2, 20), (3, 20), (4, 1)] {
+            testing_env!(get_context(accounts(i)).build());
+            let message = messages::account_info_message(&accounts(i), keys, RequestBound::BlockHeight(10));
+            let signature = verifier.sign(message.as_bytes()).to_bytes().to_vec();
+            contract.update_access_key(signature, keys, Some(10), None).unwrap();
+        }
+        let sample = |seed: &[u8]| contract.sample_verified_accounts(Base64VecU8(seed.to_vec()), 2, 30);
+        let first = sample(b"raffle-1");
+        assert_eq!(first, sample(b"raffle-1"));
+        assert!(!first.is_empty() && first.len() <= 2);
+        assert!(first.iter().all(|account_id| contract.score(account_id.clone()) >= 30 && *account_id != accounts(4)));
+        assert!(first.len() < 2 || first[0] != first[1]);
+    }
+
+    #[test]
+    fn test_score_decay() {
+        let (verifier, verifier_pk) = keypair(1);
+        testing_env!(get_context(accounts(0)).build());
+        let mut contract = Contract::new(verifier_pk);
+        let day = 24 * 60 * 60 * 1_000_000_000;
+        contract.set_score_weights(ScoreWeights { social_half_life_ns: U64(7 * day), ..Default::default() });
+        testing_env!(get_context(accounts(1)).build());
+        let message = messages::social_message(&accounts(1), "lens", "bob.lens", "0x01", None, RequestBound::BlockHeight(10));
+        let signature = verifier.sign(message.as_bytes()).to_bytes().to_vec();
+        contract.register_social("lens".to_string(), signature, "bob.lens".to_string(), "0x01".to_string(), Some(10), None, None).unwrap();
+        assert_eq!(contract.score(accounts(1)), 20);
+        testing_env!(get_context(accounts(1)).block_timestamp(7 * day).build());
+        assert_eq!(contract.score(accounts(1)), 10);
+        testing_env!(get_context(accounts(1)).block_timestamp(80 * day).build());
+        assert_eq!(contract.score(accounts(1)), 1);
     }
 
+    #[test]
+    fn test_cached_score() {
+        let (verifier, verifier_pk) = keypair(1);
+        testing_env!(get_context(accounts(0)).build());
+        let mut contract = Contract::new(verifier_pk);
+        contract.grant_role(accounts(3), Role::Verifier);
+        assert_eq!(contract.get_cached_score(accounts(1)), None);
+        testing_env!(get_context(accounts(1)).block_timestamp(5).build());
+        let message = messages::account_info_message(&accounts(1), 5u32, RequestBound::BlockHeight(10));
+        let signature = verifier.sign(message.as_bytes()).to_bytes().to_vec();
+        contract.update_access_key(signature, 5, Some(10), None).unwrap();
+        let message = messages::social_message(&accounts(1), "lens", "bob.lens", "0x01", None, RequestBound::BlockHeight(10));
+        let signature = verifier.sign(message.as_bytes()).to_bytes().to_vec();
+        contract.register_social("lens".to_string(), signature, "bob.lens".to_string(), "0x01".to_string(), Some(10), None, None).unwrap();
+        assert_eq!(contract.get_cached_score(accounts(1)), Some(CachedScore { score: 40, computed_at: U64(5) }));
+        testing_env!(get_context(accounts(3)).block_timestamp(6).build());
+        contract.revoke_social(accounts(1), "lens".to_string(), "fraud".to_string(), Some(RevocationCode::Fraud));
+        assert_eq!(contract.get_cached_score(accounts(1)), Some(CachedScore { score: 20, computed_at: U64(6) }));
+        // so is an account whose handle is taken from it
+        testing_env!(get_context(accounts(1)).block_timestamp(7).build());
+        let message = messages::social_message(&accounts(1), "lens", "bob2.lens", "0x02", None, RequestBound::BlockHeight(10));
+        let signature = verifier.sign(message.as_bytes()).to_bytes().to_vec();
+        contract.register_social("lens".to_string(), signature, "bob2.lens".to_string(), "0x02".to_string(), Some(10), None, None).unwrap();
+        assert_eq!(contract.get_cached_score(accounts(1)), Some(CachedScore { score: 40, computed_at: U64(7) }));
+        testing_env!(get_context(accounts(0)).block_timestamp(8).build());
+        contract.force_release_handle("lens".to_string(), "bob2.lens".to_string());
+        assert_eq!(contract.get_cached_score(accounts(1)), Some(CachedScore { score: 20, computed_at: U64(8) }));
+        // accounts that never wrote anything only get an entry when someone pays for it
+        testing_env!(get_context(accounts(2)).attached_deposit(NearToken::from_yoctonear(MINT_STORAGE_COST)).build());
+        assert_eq!(contract.recompute_score(accounts(4)), 0);
+        assert!(contract.get_cached_score(accounts(4)).is_some());
+    }
+
+    #[test]
+    fn test_rules() {
+        let (verifier, verifier_pk) = keypair(1);
+        testing_env!(get_context(accounts(0)).build());
+        let mut contract = Contract::new(verifier_pk);
+        let rule = RuleExpr::All(vec![
+            RuleExpr::Any(vec![RuleExpr::Platform("lens".to_string()), RuleExpr::Platform("farcaster".to_string())]),
+            RuleExpr::MinAccessKeys(5),
+        ]);
+        contract.set_rule("lens_or_farcaster".to_string(), rule.clone());
+        assert_eq!(contract.get_rule("lens_or_farcaster".to_string()), Some(rule));
+        testing_env!(get_context(accounts(1)).build());
+        let message = messages::social_message(&accounts(1), "lens", "bob.lens", "0x01", None, RequestBound::BlockHeight(10));
+        let signature = verifier.sign(message.as_bytes()).to_bytes().to_vec();
+        contract.register_social("lens".to_string(), signature, "bob.lens".to_string(), "0x01".to_string(), Some(10), None, None).unwrap();
+        assert!(!contract.evaluate_rule("lens_or_farcaster".to_string(), accounts(1)));
+        let message = messages::account_info_message(&accounts(1), 5u32, RequestBound::BlockHeight(10));
+        let signature = verifier.sign(message.as_bytes()).to_bytes().to_vec();
+        contract.update_access_key(signature, 5, Some(10), None).unwrap();
+        assert!(contract.evaluate_rule("lens_or_farcaster".to_string(), accounts(1)));
+    }
+
+    #[test]
+    fn test_badges() {
+        let (verifier, verifier_pk) = keypair(1);
+        testing_env!(get_context(accounts(0)).build());
+        let mut contract = Contract::new(verifier_pk);
+        contract.set_rule("lens_user".to_string(), RuleExpr::Platform("lens".to_string()));
+        testing_env!(get_context(accounts(1)).attached_deposit(NearToken::from_yoctonear(MINT_STORAGE_COST)).build());
+        let message = messages::social_message(&accounts(1), "lens", "bob.lens", "0x01", None, RequestBound::BlockHeight(10));
+        let signature = verifier.sign(message.as_bytes()).to_bytes().to_vec();
+        contract.register_social("lens".to_string(), signature, "bob.lens".to_string(), "0x01".to_string(), Some(10), None, None).unwrap();
+        contract.claim_badge("lens_user".to_string());
+        assert!(near_sdk::test_utils::get_logs().iter().any(|log| log.contains("badge_granted")));
+        // the badge outlives the attestation that earned it
+        contract.unregister_social("lens".to_string());
+        assert!(!contract.evaluate_rule("lens_user".to_string(), accounts(1)));
+        assert!(contract.has_badge(accounts(1), "lens_user".to_string()));
+        assert_eq!(contract.get_badges(accounts(1))[0].rule_id, "lens_user");
+    }
+
+    #[test]
+    fn test_social_badge_publishing() {
+        let (verifier, verifier_pk) = keypair(1);
+        testing_env!(get_context(accounts(0)).build());
+        let mut contract = Contract::new(verifier_pk);
+        contract.set_rule("lens_user".to_string(), RuleExpr::Platform("lens".to_string()));
+        testing_env!(get_context(accounts(1)).attached_deposit(NearToken::from_yoctonear(MINT_STORAGE_COST)).build());
+        let message = messages::social_message(&accounts(1), "lens", "bob.lens", "0x01", None, RequestBound::BlockHeight(10));
+        let signature = verifier.sign(message.as_bytes()).to_bytes().to_vec();
+        contract.register_social("lens".to_string(), signature, "bob.lens".to_string(), "0x01".to_string(), Some(10), None, None).unwrap();
+        contract.set_social_badge_publishing(true);
+        assert!(contract.is_publishing_social_badges(accounts(1)));
+        contract.claim_badge("lens_user".to_string());
+        // opting in and the new badge each write the profile
+        let writes = near_sdk::test_utils::get_created_receipts().into_iter().filter(|receipt| receipt.receiver_id.as_str() == "social.near").count();
+        assert_eq!(writes, 2);
+        contract.set_social_badge_publishing(false);
+        assert!(!contract.is_publishing_social_badges(accounts(1)));
+    }
+
+    #[test]
+    fn test_nft_badges() {
+        let (verifier, verifier_pk) = keypair(1);
+        testing_env!(get_context(accounts(0)).build());
+        let mut contract = Contract::new(verifier_pk);
+        contract.set_rule("lens_user".to_string(), RuleExpr::Platform("lens".to_string()));
+        contract.set_fee(U128(100));
+        contract.set_nft_badge_config(Some(NftBadgeConfig { collection: accounts(5), mint_deposit: U128(60), badges: true, tiers: false }));
+        testing_env!(get_context(accounts(1)).attached_deposit(NearToken::from_yoctonear(100 + MINT_STORAGE_COST)).build());
+        let message = messages::social_message(&accounts(1), "lens", "bob.lens", "0x01", None, RequestBound::BlockHeight(10));
+        let signature = verifier.sign(message.as_bytes()).to_bytes().to_vec();
+        contract.register_social("lens".to_string(), signature, "bob.lens".to_string(), "0x01".to_string(), Some(10), None, None).unwrap();
+        contract.claim_badge("lens_user".to_string());
+        deposit_storage(&mut contract, accounts(1));
+        // the treasury pays the mint deposit, and gets it back if the mint fails
+        assert_eq!(contract.get_treasury_balance(), U128(40));
+        testing_env!(get_context(env::current_account_id()).build());
+        assert!(!contract.on_nft_minted(accounts(1), "badge:lens_user".to_string(), U128(60), Err(near_sdk::PromiseError::Failed)));
+        assert_eq!(contract.get_treasury_balance(), U128(100));
+        assert!(contract.on_nft_minted(accounts(1), "badge:lens_user".to_string(), U128(60), Ok(nft_badges::NftToken { token_id: "badge:lens_user:bob".to_string() })));
+        assert_eq!(contract.get_user_data(accounts(1)).unwrap().nfts[0].token_id, "badge:lens_user:bob");
+    }
 
-}
\ No newline at end of file
+    #[test]
+    fn test_evm_attestation_export() {
+        let (verifier, verifier_pk) = keypair(1);
+        testing_env!(get_context(accounts(0)).build());
+        let mut contract = Contract::new(verifier_pk);
+        contract.set_mpc_config(Some(MpcConfig { signer: "v1.signer".parse().unwrap(), path: "sybil".to_string(), key_version: 0 }));
+        let address = "0x00000000000000000000000000000000000000aa".to_string();
+        contract.evm_addresses.insert(accounts(1), vec![address.clone()]);
+        testing_env!(get_context(accounts(1)).attached_deposit(NearToken::from_yoctonear(MINT_STORAGE_COST)).build());
+        let message = messages::social_message(&accounts(1), "lens", "bob.lens", "0x01", None, RequestBound::BlockHeight(10));
+        let signature = verifier.sign(message.as_bytes()).to_bytes().to_vec();
+        contract.register_social("lens".to_string(), signature, "bob.lens".to_string(), "0x01".to_string(), Some(10), None, None).unwrap();
+        deposit_storage(&mut contract, accounts(1));
+        testing_env!(get_context(accounts(1)).attached_deposit(NearToken::from_yoctonear(1)).build());
+        let _ = contract.request_evm_attestation(address.to_uppercase().replace("0X", "0x"), U64(1));
+        let pending = contract.get_pending_evm_attestation(accounts(1)).unwrap();
+        assert_eq!((pending.evm_address.as_str(), pending.nonce, pending.score), (address.as_str(), U64(0), 20));
+        assert_eq!(contract.get_evm_attestation_nonce(accounts(1)), U64(1));
+        // a failed signature releases the request but not the nonce
+        testing_env!(get_context(env::current_account_id()).build());
+        assert_eq!(contract.on_attestation_signed(accounts(1), U128(1), Err(near_sdk::PromiseError::Failed)), None);
+        assert!(contract.get_pending_evm_attestation(accounts(1)).is_none());
+        testing_env!(get_context(accounts(1)).attached_deposit(NearToken::from_yoctonear(1)).build());
+        let _ = contract.request_evm_attestation(address.clone(), U64(1));
+        testing_env!(get_context(env::current_account_id()).build());
+        let response = chain_signatures::SignatureResponse {
+            big_r: chain_signatures::AffinePoint { affine_point: "02ab".to_string() },
+            s: chain_signatures::Scalar { scalar: "cd".to_string() },
+            recovery_id: 1,
+        };
+        let signed = contract.on_attestation_signed(accounts(1), U128(1), Ok(response)).unwrap();
+        assert_eq!((signed.attestation.nonce, signed.recovery_id), (U64(1), 1));
+        assert_eq!(contract.get_evm_attestation(accounts(1)), Some(signed));
+    }
+
+    #[test]
+    #[should_panic(expected = "rules have at most 32 nodes nested 8 deep")]
+    fn test_rule_too_deep() {
+        let (_, verifier_pk) = keypair(1);
+        testing_env!(get_context(accounts(0)).build());
+        let mut contract = Contract::new(verifier_pk);
+        let rule = (0..8).fold(RuleExpr::MinScore(10), |rule, _| RuleExpr::Not(Box::new(rule)));
+        contract.set_rule("deep".to_string(), rule);
+    }
+
+    #[test]
+    fn test_tiers() {
+        let (verifier, verifier_pk) = keypair(1);
+        testing_env!(get_context(accounts(0)).build());
+        let mut contract = Contract::new(verifier_pk);
+        assert_eq!(contract.get_tier(accounts(1)), None);
+        testing_env!(get_context(accounts(1)).build());
+        let message = messages::social_message(&accounts(1), "lens", "bob.lens", "0x01", None, RequestBound::BlockHeight(10));
+        let signature = verifier.sign(message.as_bytes()).to_bytes().to_vec();
+        contract.register_social("lens".to_string(), signature, "bob.lens".to_string(), "0x01".to_string(), Some(10), None, None).unwrap();
+        assert_eq!(contract.get_tier(accounts(1)), Some("bronze".to_string()));
+        assert!(near_sdk::test_utils::get_logs().iter().any(|log| log.contains("tier_changed") && log.contains("bronze")));
+        testing_env!(get_context(accounts(0)).build());
+        contract.set_tiers(vec![Tier { name: "member".to_string(), min_score: 10 }, Tier { name: "trusted".to_string(), min_score: 20 }]);
+        assert_eq!(contract.get_tier(accounts(1)), Some("trusted".to_string()));
+    }
+
+    #[test]
+    fn test_qf_weight() {
+        let (verifier, verifier_pk) = keypair(1);
+        testing_env!(get_context(accounts(0)).build());
+        let mut contract = Contract::new(verifier_pk);
+        testing_env!(get_context(accounts(1)).build());
+        assert_eq!(contract.qf_weight(accounts(1)), U128(0));
+        let message = messages::social_message(&accounts(1), "lens", "bob.lens", "0x01", None, RequestBound::BlockHeight(10));
+        let signature = verifier.sign(message.as_bytes()).to_bytes().to_vec();
+        contract.register_social("lens".to_string(), signature, "bob.lens".to_string(), "0x01".to_string(), Some(10), None, None).unwrap();
+        let message = messages::account_info_message(&accounts(1), 5u32, RequestBound::BlockHeight(10));
+        let signature = verifier.sign(message.as_bytes()).to_bytes().to_vec();
+        contract.update_access_key(signature, 5, Some(10), None).unwrap();
+        // a score of 40 is sqrt(0.4) of the full weight
+        assert_eq!(contract.qf_weight(accounts(1)), U128(630_000));
+        testing_env!(get_context(accounts(0)).build());
+        contract.set_qf_config(QfConfig { curve: QfCurve::Linear, min_score: 50, max_weight: U128(1_000_000) });
+        assert_eq!(contract.qf_weight(accounts(1)), U128(0));
+        // a max weight below 100 still scales with the score
+        contract.set_qf_config(QfConfig { curve: QfCurve::Linear, min_score: 0, max_weight: U128(50) });
+        assert_eq!(contract.qf_weight(accounts(1)), U128(20));
+        contract.set_qf_config(QfConfig { curve: QfCurve::Linear, min_score: 0, max_weight: U128(u128::MAX) });
+        assert_eq!(contract.qf_weight(accounts(1)), U128(u128::MAX / 100 * 40));
+    }
+
+    #[test]
+    fn test_is_human() {
+        let (verifier, verifier_pk) = keypair(1);
+        testing_env!(get_context(accounts(0)).build());
+        let mut contract = Contract::new(verifier_pk);
+        testing_env!(get_context(accounts(1)).build());
+        let message = messages::account_info_message(&accounts(1), 5u32, RequestBound::BlockHeight(10));
+        let signature = verifier.sign(message.as_bytes()).to_bytes().to_vec();
+        contract.update_access_key(signature, 5, Some(10), None).unwrap();
+        assert!(!contract.is_human(accounts(1)));
+        testing_env!(get_context(accounts(0)).build());
+        contract.set_rule("keys".to_string(), RuleExpr::MinAccessKeys(5));
+        contract.set_human_policy(HumanPolicy::Rule("keys".to_string()));
+        assert!(contract.is_human(accounts(1)));
+        contract.ban_account(accounts(1), "SYBIL".to_string());
+        assert!(!contract.is_human(accounts(1)));
+    }
+
+    #[test]
+    fn test_verify_for() {
+        let (verifier, verifier_pk) = keypair(1);
+        testing_env!(get_context(accounts(0)).build());
+        let mut contract = Contract::new(verifier_pk);
+        testing_env!(get_context(accounts(1)).build());
+        let message = messages::account_info_message(&accounts(1), 5u32, RequestBound::BlockHeight(10));
+        let signature = verifier.sign(message.as_bytes()).to_bytes().to_vec();
+        contract.update_access_key(signature, 5, Some(10), None).unwrap();
+        assert_eq!(contract.evaluate_check(&accounts(1), "keys_light"), Some(true));
+        assert_eq!(contract.evaluate_check(&accounts(1), "is_human"), Some(false));
+        assert_eq!(contract.evaluate_check(&accounts(1), "unknown"), None);
+        contract.verify_for(accounts(1), "keys_light".to_string(), accounts(4), "round-7".to_string()).detach();
+    }
+
+    #[test]
+    #[should_panic(expected = "only the account or the receiver can request a result")]
+    fn test_verify_for_third_party() {
+        let (_, verifier_pk) = keypair(1);
+        testing_env!(get_context(accounts(0)).build());
+        let mut contract = Contract::new(verifier_pk);
+        testing_env!(get_context(accounts(2)).build());
+        contract.verify_for(accounts(1), "keys_light".to_string(), accounts(4), String::new()).detach();
+    }
+
+    #[test]
+    fn test_passport_score() {
+        let (verifier, verifier_pk) = keypair(1);
+        testing_env!(get_context(accounts(0)).build());
+        let mut contract = Contract::new(verifier_pk);
+        testing_env!(get_context(accounts(1)).build());
+        let message = messages::passport_score_message(&accounts(1), 1000, RequestBound::BlockHeight(10));
+        let signature = verifier.sign(message.as_bytes()).to_bytes().to_vec();
+        assert_eq!(contract.import_passport_score(1500, signature.clone(), Some(10), None), Err(ContractError::UnverifiedData));
+        contract.import_passport_score(1000, signature, Some(10), None).unwrap();
+        assert_eq!(contract.get_passport_score(accounts(1)).unwrap().score, 1000);
+        // half of the full score is half of the passport points
+        assert_eq!(contract.score(accounts(1)), 10);
+        assert_eq!(contract.get_user_data(accounts(1)).unwrap().stamps[0].value, "1000");
+        testing_env!(get_context(accounts(1)).block_timestamp(passport::PASSPORT_VALIDITY_NS + 1).build());
+        assert_eq!(contract.score(accounts(1)), 0);
+    }
+
+    #[test]
+    fn test_world_id() {
+        let (verifier, verifier_pk) = keypair(1);
+        testing_env!(get_context(accounts(0)).build());
+        let mut contract = Contract::new(verifier_pk);
+        let proof = WorldIdProof {
+            nullifier_hash: format!("0x{}", "2b".repeat(32)),
+            merkle_root: format!("0x{}", "1f".repeat(32)),
+            verification_level: "orb".to_string(),
+        };
+        let sign = |account_id: &AccountId| verifier.sign(messages::world_id_message(account_id, &proof, RequestBound::BlockHeight(10)).as_bytes()).to_bytes().to_vec();
+        testing_env!(get_context(accounts(1)).build());
+        let uppercase = WorldIdProof { nullifier_hash: proof.nullifier_hash.to_uppercase().replace("0X", "0x"), ..proof.clone() };
+        contract.verify_world_id(uppercase, sign(&accounts(1)), Some(10), None).unwrap();
+        assert_eq!(contract.world_id_owner(proof.nullifier_hash.clone()), Some(accounts(1)));
+        let stamps = contract.get_stamps(accounts(1));
+        assert_eq!((stamps[0].stamp_type.as_str(), &stamps[0].value), (stamps::STAMP_WORLD_ID, &proof.nullifier_hash));
+        // the same World ID can't back a second account
+        testing_env!(get_context(accounts(2)).build());
+        assert_eq!(contract.verify_world_id(proof.clone(), sign(&accounts(2)), Some(10), None), Err(ContractError::WorldIdAlreadyUsed));
+        let invalid = WorldIdProof { verification_level: "phone".to_string(), ..proof.clone() };
+        assert_eq!(contract.verify_world_id(invalid, sign(&accounts(2)), Some(10), None), Err(ContractError::InvalidWorldIdProof));
+        // until the first account deletes its data
+        testing_env!(get_context(accounts(1)).attached_deposit(NearToken::from_yoctonear(1)).build());
+        contract.delete_my_data();
+        testing_env!(get_context(accounts(2)).build());
+        contract.verify_world_id(proof.clone(), sign(&accounts(2)), Some(10), None).unwrap();
+        assert_eq!(contract.get_world_id(accounts(2)).unwrap().verification_level, "orb");
+    }
+
+    #[test]
+    fn test_stamps() {
+        let (verifier, verifier_pk) = keypair(1);
+        testing_env!(get_context(accounts(0)).build());
+        let mut contract = Contract::new(verifier_pk.clone());
+        contract.set_stamp_schema("poap_count".to_string(), StampSchema { value_kind: StampValueKind::Integer, max_validity_ns: None, issuer_role: None });
+        testing_env!(get_context(accounts(1)).build());
+        let message = messages::account_info_message(&accounts(1), 5u32, RequestBound::BlockHeight(10));
+        let signature = verifier.sign(message.as_bytes()).to_bytes().to_vec();
+        contract.update_access_key(signature, 5, Some(10), None).unwrap();
+        let message = messages::stamp_message(&accounts(1), "poap_count", "7", Some(1_000), RequestBound::BlockHeight(10));
+        let signature = verifier.sign(message.as_bytes()).to_bytes().to_vec();
+        assert_eq!(contract.add_stamp("poap_count".to_string(), "7".to_string(), None, signature.clone(), Some(10), None), Err(ContractError::UnverifiedData));
+        contract.add_stamp("poap_count".to_string(), "7".to_string(), Some(U64(1_000)), signature, Some(10), None).unwrap();
+        let stamps = contract.get_stamps(accounts(1));
+        assert_eq!(stamps.iter().map(|stamp| (stamp.stamp_type.as_str(), stamp.value.as_str())).collect::<Vec<_>>(), vec![("access_key_count", "5"), ("poap_count", "7")]);
+        assert_eq!(stamps[1].issuer, Some(StampIssuer::Verifier(verifier_pk)));
+        assert_eq!(contract.get_user_data(accounts(1)).unwrap().stamps, stamps);
+        // built-in types have their own methods
+        let message = messages::stamp_message(&accounts(1), "access_key_count", "50", None, RequestBound::BlockHeight(10));
+        let signature = verifier.sign(message.as_bytes()).to_bytes().to_vec();
+        assert_eq!(contract.add_stamp("access_key_count".to_string(), "50".to_string(), None, signature, Some(10), None), Err(ContractError::UnknownStampType));
+    }
+
+    #[test]
+    fn test_stamp_schemas() {
+        let (verifier, verifier_pk) = keypair(1);
+        testing_env!(get_context(accounts(0)).build());
+        let mut contract = Contract::new(verifier_pk);
+        let day = 24 * 60 * 60 * 1_000_000_000;
+        contract.set_stamp_schema("kyc".to_string(), StampSchema { value_kind: StampValueKind::Boolean, max_validity_ns: Some(U64(day)), issuer_role: Some(Role::Operator) });
+        contract.grant_role(accounts(3), Role::Operator);
+        let message = messages::stamp_message(&accounts(1), "kyc", "true", Some(day), RequestBound::BlockHeight(10));
+        let signature = verifier.sign(message.as_bytes()).to_bytes().to_vec();
+        testing_env!(get_context(accounts(1)).build());
+        assert_eq!(contract.add_stamp("kyc".to_string(), "true".to_string(), Some(U64(day)), signature, Some(10), None), Err(ContractError::StampIssuerNotAllowed));
+        testing_env!(get_context(accounts(2)).build());
+        assert_eq!(contract.issue_stamp(accounts(1), "kyc".to_string(), "true".to_string(), Some(U64(day))), Err(ContractError::StampIssuerNotAllowed));
+        testing_env!(get_context(accounts(3)).build());
+        assert_eq!(contract.issue_stamp(accounts(1), "kyc".to_string(), "yes".to_string(), Some(U64(day))), Err(ContractError::InvalidStamp));
+        assert_eq!(contract.issue_stamp(accounts(1), "kyc".to_string(), "true".to_string(), None), Err(ContractError::InvalidStamp));
+        assert_eq!(contract.issue_stamp(accounts(1), "kyc".to_string(), "true".to_string(), Some(U64(2 * day))), Err(ContractError::InvalidStamp));
+        contract.issue_stamp(accounts(1), "kyc".to_string(), "true".to_string(), Some(U64(day))).unwrap();
+        assert_eq!(contract.get_stamps(accounts(1))[0].issuer, Some(StampIssuer::Account(accounts(3))));
+    }
+
+    #[test]
+    fn test_issue_stamp_storage() {
+        let (_, verifier_pk) = keypair(1);
+        testing_env!(get_context(accounts(0)).build());
+        let mut contract = Contract::new(verifier_pk);
+        contract.set_stamp_schema("kyc".to_string(), StampSchema { value_kind: StampValueKind::Boolean, max_validity_ns: None, issuer_role: Some(Role::Operator) });
+        contract.grant_role(accounts(3), Role::Operator);
+        testing_env!(get_context(accounts(1)).attached_deposit(NearToken::from_yoctonear(MINT_STORAGE_COST)).build());
+        contract.storage_deposit(None, None);
+        let available = contract.storage_balance_of(accounts(1)).unwrap().available;
+        assert!(available.0 > 0);
+        testing_env!(get_context(accounts(0)).build());
+        contract.set_min_deposit(U128(1));
+        testing_env!(get_context(accounts(3)).attached_deposit(NearToken::from_yoctonear(0)).build());
+        assert_eq!(contract.issue_stamp(accounts(1), "kyc".to_string(), "true".to_string(), None), Err(ContractError::InsufficientDeposit { need: 1, got: 0 }));
+        // the issuer pays, the account's storage balance is left alone
+        testing_env!(get_context(accounts(3)).attached_deposit(NearToken::from_yoctonear(MINT_STORAGE_COST)).build());
+        contract.issue_stamp(accounts(1), "kyc".to_string(), "true".to_string(), None).unwrap();
+        assert_eq!(contract.storage_balance_of(accounts(1)).unwrap().available, available);
+        assert!(contract.storage_balance_of(accounts(3)).unwrap().total.0 > 0);
+    }
+
+    #[test]
+    fn test_issue_stamp_paused() {
+        let (_, verifier_pk) = keypair(1);
+        testing_env!(get_context(accounts(0)).build());
+        let mut contract = Contract::new(verifier_pk);
+        contract.set_paused_methods(PAUSE_ADD_STAMP);
+        testing_env!(get_context(accounts(3)).build());
+        assert_eq!(contract.issue_stamp(accounts(1), "kyc".to_string(), "true".to_string(), None), Err(ContractError::MethodPaused));
+    }
+
+    #[test]
+    fn test_link_accounts() {
+        let (verifier, verifier_pk) = keypair(1);
+        testing_env!(get_context(accounts(0)).build());
+        let mut contract = Contract::new(verifier_pk);
+        testing_env!(get_context(accounts(2)).build());
+        let message = messages::account_info_message(&accounts(2), 20u32, RequestBound::BlockHeight(10));
+        let signature = verifier.sign(message.as_bytes()).to_bytes().to_vec();
+        contract.update_access_key(signature, 20, Some(10), None).unwrap();
+        // accounts(1) asks, the vault accounts(2) confirms
+        testing_env!(get_context(accounts(1)).attached_deposit(NearToken::from_yoctonear(MINT_STORAGE_COST)).build());
+        contract.link_account(accounts(2));
+        assert_eq!(contract.get_primary_account(accounts(2)), accounts(2));
+        testing_env!(get_context(accounts(2)).attached_deposit(NearToken::from_yoctonear(MINT_STORAGE_COST)).build());
+        contract.link_account(accounts(1));
+        assert_eq!(contract.get_primary_account(accounts(2)), accounts(1));
+        assert_eq!(contract.get_linked_accounts(accounts(1)), vec![accounts(1), accounts(2)]);
+        // checks only resolve through the identity once enabled
+        assert!(!contract.connected_to_5_contracts(accounts(1), None));
+        testing_env!(get_context(accounts(0)).build());
+        contract.set_resolve_linked_accounts(true);
+        assert!(contract.connected_to_5_contracts(accounts(1), None));
+        assert_eq!(contract.score(accounts(1)), 30);
+        testing_env!(get_context(accounts(1)).build());
+        contract.unlink_account();
+        assert_eq!(contract.get_primary_account(accounts(2)), accounts(2));
+        assert!(!contract.connected_to_5_contracts(accounts(1), None));
+    }
+
+    #[test]
+    fn test_merge_into() {
+        let (verifier, verifier_pk) = keypair(1);
+        testing_env!(get_context(accounts(0)).build());
+        let mut contract = Contract::new(verifier_pk);
+        for (account, platform, handle) in [(accounts(1), "lens", "bob.lens"), (accounts(2), "lens", "alice.lens"), (accounts(2), "twitter", "alice")] {
+            testing_env!(get_context(account.clone()).build());
+            let message = messages::social_message(&account, platform, handle, "0x01", None, RequestBound::BlockHeight(10));
+            let signature = verifier.sign(message.as_bytes()).to_bytes().to_vec();
+            contract.register_social(platform.to_string(), signature, handle.to_string(), "0x01".to_string(), Some(10), None, None).unwrap();
+        }
+        let message = messages::account_info_message(&accounts(2), 20u32, RequestBound::BlockHeight(10));
+        let signature = verifier.sign(message.as_bytes()).to_bytes().to_vec();
+        contract.update_access_key(signature, 20, Some(10), None).unwrap();
+        contract.link_account(accounts(1));
+        testing_env!(get_context(accounts(1)).build());
+        contract.link_account(accounts(2));
+        testing_env!(get_context(accounts(2)).build());
+        assert!(contract.get_cached_score(accounts(2)).is_some());
+        contract.merge_into(accounts(1));
+        // the merged account's score is the primary's from now on
+        assert!(contract.get_cached_score(accounts(2)).is_none());
+        // both lens attestations expire together, so the primary's is kept
+        assert_eq!(contract.get_handles(accounts(1), "lens".to_string()), vec!["bob.lens"]);
+        assert_eq!(contract.get_handles(accounts(1), "twitter".to_string()), vec!["alice"]);
+        assert_eq!(contract.get_handle_history(accounts(2), None, None)[0].reason_released, ReleaseReason::Merged);
+        assert!(contract.connected_to_5_contracts(accounts(1), None));
+        // checks for the merged account answer for the primary
+        assert_eq!(contract.get_merged_into(accounts(2)), Some(accounts(1)));
+        assert_eq!(contract.get_primary_account(accounts(2)), accounts(1));
+        assert!(contract.connected_to_lens(accounts(2)));
+        assert_eq!(contract.get_linked_accounts(accounts(1)), vec![accounts(1)]);
+    }
+
+    #[test]
+    fn test_merge_world_ids() {
+        let (verifier, verifier_pk) = keypair(1);
+        testing_env!(get_context(accounts(0)).build());
+        let mut contract = Contract::new(verifier_pk);
+        let proof = |byte: &str| WorldIdProof { nullifier_hash: format!("0x{}", byte.repeat(32)), merkle_root: format!("0x{}", "1f".repeat(32)), verification_level: "orb".to_string() };
+        let sign = |account_id: &AccountId, proof: &WorldIdProof| verifier.sign(messages::world_id_message(account_id, proof, RequestBound::BlockHeight(10)).as_bytes()).to_bytes().to_vec();
+        for (account, byte) in [(accounts(1), "2b"), (accounts(2), "3c")] {
+            testing_env!(get_context(account.clone()).build());
+            contract.verify_world_id(proof(byte), sign(&account, &proof(byte)), Some(10), None).unwrap();
+        }
+        testing_env!(get_context(accounts(2)).build());
+        contract.link_account(accounts(1));
+        testing_env!(get_context(accounts(1)).build());
+        contract.link_account(accounts(2));
+        testing_env!(get_context(accounts(2)).build());
+        contract.merge_into(accounts(1));
+        // the primary keeps its own World ID, the merged one stays spent on it
+        assert_eq!(contract.get_world_id(accounts(1)).unwrap().nullifier_hash, proof("2b").nullifier_hash);
+        assert_eq!(contract.world_id_owner(proof("3c").nullifier_hash), Some(accounts(1)));
+        // and is freed with the primary's data
+        testing_env!(get_context(accounts(1)).attached_deposit(NearToken::from_yoctonear(1)).build());
+        contract.delete_my_data();
+        assert_eq!(contract.world_id_owner(proof("2b").nullifier_hash), None);
+        assert_eq!(contract.world_id_owner(proof("3c").nullifier_hash), None);
+        testing_env!(get_context(accounts(3)).build());
+        contract.verify_world_id(proof("3c"), sign(&accounts(3), &proof("3c")), Some(10), None).unwrap();
+    }
+
+    #[test]
+    fn test_profile_metadata() {
+        let (verifier, verifier_pk) = keypair(1);
+        testing_env!(get_context(accounts(1)).attached_deposit(NearToken::from_yoctonear(MINT_STORAGE_COST)).build());
+        let mut contract = Contract::new(verifier_pk);
+        assert!(contract.get_user_data(accounts(1)).is_none());
+        let message = messages::social_message(&accounts(1), "lens", "bob.lens", "0x01", None, RequestBound::BlockHeight(10));
+        let signature = verifier.sign(message.as_bytes()).to_bytes().to_vec();
+        contract.register_social("lens".to_string(), signature, "bob.lens".to_string(), "0x01".to_string(), Some(10), None, None).unwrap();
+        let metadata = BTreeMap::from([("name".to_string(), "Bob".to_string()), ("avatar".to_string(), "https://example.com/bob.png".to_string())]);
+        contract.set_profile_metadata(metadata.clone()).unwrap();
+        let data = contract.get_user_data(accounts(1)).unwrap();
+        assert_eq!(data.metadata, metadata);
+        assert_eq!(data.socials["lens"], vec!["bob.lens"]);
+        let too_many = (0..9).map(|i| (i.to_string(), String::new())).collect();
+        assert_eq!(contract.set_profile_metadata(too_many), Err(ContractError::TooManyMetadataEntries { max: 8 }));
+        let too_long = BTreeMap::from([("bio".to_string(), "x".repeat(257))]);
+        assert_eq!(contract.set_profile_metadata(too_long).unwrap_err().code(), "INPUT_TOO_LONG");
+        contract.set_profile_metadata(BTreeMap::new()).unwrap();
+        assert!(contract.get_profile_metadata(accounts(1)).is_empty());
+    }
+
+    #[test]
+    fn test_register_social_private() {
+        let (verifier, verifier_pk) = keypair(1);
+        testing_env!(get_context(accounts(1)).build());
+        let mut contract = Contract::new(verifier_pk);
+        let hash = contract.get_private_handle_hash("twitter".to_string(), "Bob".to_string(), "s4lt".to_string());
+        let handle = format!("sha256:{}", hash);
+        let message = messages::social_message(&accounts(1), "twitter", &handle, "0x01", None, RequestBound::BlockHeight(10));
+        let signature = verifier.sign(message.as_bytes()).to_bytes().to_vec();
+        contract.register_social_private("twitter".to_string(), signature, hash, "0x01".to_string(), Some(10), None, None).unwrap();
+        assert_eq!(contract.get_handles(accounts(1), "twitter".to_string()), vec![handle]);
+        assert!(contract.connected_to_twitter(accounts(1)));
+        // the handle normalized, and only with the right salt
+        assert!(contract.confirm_private_handle(accounts(1), "twitter".to_string(), "bob".to_string(), "s4lt".to_string()));
+        assert!(!contract.confirm_private_handle(accounts(1), "twitter".to_string(), "bob".to_string(), "salt".to_string()));
+        let message = messages::social_message(&accounts(1), "twitter", "sha256:xyz", "0x02", None, RequestBound::BlockHeight(10));
+        let signature = verifier.sign(message.as_bytes()).to_bytes().to_vec();
+        assert!(contract.register_social("twitter".to_string(), signature, "sha256:xyz".to_string(), "0x02".to_string(), Some(10), None, None).is_err());
+        assert_eq!(failure_codes(), vec!["MALFORMED_HANDLE"]);
+    }
+
+    #[test]
+    fn test_delete_my_data() {
+        let (verifier, verifier_pk) = keypair(1);
+        testing_env!(get_context(accounts(0)).build());
+        let mut contract = Contract::new(verifier_pk);
+        let id = contract.set_platform_config("lens".to_string(), PlatformConfig { release_cooldown_ns: U64(1_000_000_000), ..Default::default() });
+        execute_after_timelock(&mut contract, id);
+        testing_env!(get_context(accounts(1)).attached_deposit(NearToken::from_yoctonear(MINT_STORAGE_COST)).build());
+        for (handle, proof) in [("bob.lens", "0x01"), ("alice.lens", "0x02")] {
+            let message = messages::social_message(&accounts(1), "lens", handle, proof, None, RequestBound::BlockHeight(10));
+            let signature = verifier.sign(message.as_bytes()).to_bytes().to_vec();
+            contract.register_social("lens".to_string(), signature, handle.to_string(), proof.to_string(), Some(10), None, None).unwrap();
+        }
+        contract.set_profile_metadata(BTreeMap::from([("name".to_string(), "Bob".to_string())])).unwrap();
+        testing_env!(get_context(accounts(1)).attached_deposit(NearToken::from_yoctonear(1)).build());
+        contract.delete_my_data();
+        assert!(contract.get_user_data(accounts(1)).is_none());
+        assert!(contract.get_handle_history(accounts(1), None, None).is_empty());
+        assert!(!contract.connected_to_lens(accounts(1)));
+        // the handle replaced before the erasure is no longer reserved for the account
+        testing_env!(get_context(accounts(2)).build());
+        let message = messages::social_message(&accounts(2), "lens", "bob.lens", "0x02", None, RequestBound::BlockHeight(10));
+        let signature = verifier.sign(message.as_bytes()).to_bytes().to_vec();
+        contract.register_social("lens".to_string(), signature, "bob.lens".to_string(), "0x02".to_string(), Some(10), None, None).unwrap();
+    }
+
+    #[test]
+    fn test_delete_my_data_pending_requests() {
+        let (verifier, verifier_pk) = keypair(1);
+        let bond = 1_000_000;
+        testing_env!(get_context(accounts(0)).build());
+        let mut contract = Contract::new(verifier_pk);
+        contract.set_rate_limit(5);
+        contract.set_mpc_config(Some(MpcConfig { signer: "v1.signer".parse().unwrap(), path: "sybil".to_string(), key_version: 0 }));
+        let optimistic = OptimisticConfig { bond: U128(bond), challenge_window_ns: U64(1_000) };
+        let id = contract.set_platform_config("twitter".to_string(), PlatformConfig { optimistic: Some(optimistic), ..Default::default() });
+        execute_after_timelock(&mut contract, id);
+        let address = "0x00000000000000000000000000000000000000aa".to_string();
+        contract.evm_addresses.insert(accounts(1), vec![address.clone()]);
+        testing_env!(get_context(accounts(1)).build());
+        let message = messages::social_message(&accounts(1), "lens", "bob.lens", "0x01", None, RequestBound::BlockHeight(10));
+        let signature = verifier.sign(message.as_bytes()).to_bytes().to_vec();
+        contract.register_social("lens".to_string(), signature, "bob.lens".to_string(), "0x01".to_string(), Some(10), None, None).unwrap();
+        let hash = contract.get_commitment_hash(accounts(1), "lens".to_string(), "carol.lens".to_string(), "0x02".to_string(), "salt".to_string());
+        contract.commit_social(hash);
+        testing_env!(get_context(accounts(1)).attached_deposit(NearToken::from_yoctonear(bond + MINT_STORAGE_COST)).build());
+        contract.submit_claim("twitter".to_string(), "bob".to_string(), "https://x.com/status/1".to_string());
+        deposit_storage(&mut contract, accounts(1));
+        testing_env!(get_context(accounts(1)).attached_deposit(NearToken::from_yoctonear(1)).build());
+        let _ = contract.request_evm_attestation(address, U64(1));
+        contract.delete_my_data();
+        assert!(!contract.commitments.contains_key(&accounts(1)));
+        assert!(!contract.optimistic_claims.contains_key(&("twitter".to_string(), "bob".to_string())));
+        assert!(!contract.claims_of.contains_key(&accounts(1)));
+        assert!(!contract.pending_evm_attestations.contains_key(&accounts(1)));
+        assert!(!contract.epoch_writes.contains_key(&accounts(1)));
+        // the undisputed claim's bond goes back to the account
+        let refunded = near_sdk::test_utils::get_created_receipts().into_iter()
+            .filter(|receipt| receipt.receiver_id == accounts(1))
+            .flat_map(|receipt| receipt.actions)
+            .any(|action| matches!(action, near_sdk::mock::MockAction::Transfer { deposit, .. } if deposit.as_yoctonear() == bond));
+        assert!(refunded);
+    }
+
+    #[test]
+    fn test_account_details() {
+        let (verifier, verifier_pk) = keypair(1);
+        testing_env!(get_context(accounts(0)).build());
+        let mut contract = Contract::new(verifier_pk);
+        contract.add_consumer(accounts(3));
+        contract.set_consumer_fee(U128(10));
+        testing_env!(get_context(accounts(1)).build());
+        let message = messages::social_message(&accounts(1), "lens", "bob.lens", "0x01", None, RequestBound::BlockHeight(10));
+        let signature = verifier.sign(message.as_bytes()).to_bytes().to_vec();
+        contract.register_social("lens".to_string(), signature, "bob.lens".to_string(), "0x01".to_string(), Some(10), None, None).unwrap();
+        testing_env!(get_context(accounts(3)).attached_deposit(NearToken::from_yoctonear(15)).build());
+        let details = contract.get_account_details(accounts(1));
+        let digest: String = env::sha256_array(b"0x01").iter().map(|byte| format!("{:02x}", byte)).collect();
+        assert_eq!(details.socials[0].proof_digest, digest);
+        assert_eq!(contract.get_treasury_balance(), U128(10));
+    }
+
+    #[test]
+    fn test_subscriptions() {
+        let (verifier, verifier_pk) = keypair(1);
+        testing_env!(get_context(accounts(0)).build());
+        let mut contract = Contract::new(verifier_pk);
+        contract.add_consumer(accounts(3));
+        contract.set_consumer_fee(U128(10));
+        contract.set_verify_fee(U128(5));
+        testing_env!(get_context(accounts(1)).build());
+        let message = messages::account_info_message(&accounts(1), 5u32, RequestBound::BlockHeight(10));
+        let signature = verifier.sign(message.as_bytes()).to_bytes().to_vec();
+        contract.update_access_key(signature, 5, Some(10), None).unwrap();
+        testing_env!(get_context(accounts(3)).attached_deposit(NearToken::from_yoctonear(MINT_STORAGE_COST)).build());
+        contract.register_consumer();
+        let balance = contract.get_subscription_balance(accounts(3)).unwrap().0;
+        assert!(balance > 15);
+        // queries without a deposit are paid from the balance
+        testing_env!(get_context(accounts(3)).attached_deposit(NearToken::from_yoctonear(0)).build());
+        contract.get_account_details(accounts(1));
+        contract.verify_for(accounts(1), "keys_light".to_string(), accounts(3), String::new()).detach();
+        assert_eq!(contract.get_subscription_balance(accounts(3)), Some(U128(balance - 15)));
+        assert_eq!(contract.get_treasury_balance(), U128(15));
+        assert_eq!(contract.withdraw_subscription(None), U128(balance - 15));
+    }
+
+    #[test]
+    fn test_request_attestation() {
+        let (verifier, verifier_pk) = keypair(1);
+        testing_env!(get_context(accounts(0)).build());
+        let mut contract = Contract::new(verifier_pk);
+        contract.set_attestation_fee(U128(7));
+        testing_env!(get_context(accounts(1)).build());
+        let message = messages::account_info_message(&accounts(1), 5u32, RequestBound::BlockHeight(10));
+        let signature = verifier.sign(message.as_bytes()).to_bytes().to_vec();
+        contract.update_access_key(signature, 5, Some(10), None).unwrap();
+        testing_env!(get_context(accounts(3)).attached_deposit(NearToken::from_yoctonear(7)).build());
+        let receipt = contract.request_attestation(accounts(1), "keys_light".to_string());
+        assert!(receipt.result);
+        assert_eq!((receipt.receipt_id, receipt.consumer, receipt.fee), (0, accounts(3), U128(7)));
+        assert!(near_sdk::test_utils::get_logs().iter().any(|log| log.contains("attestation_receipt")));
+        assert_eq!(contract.request_attestation(accounts(1), "keys_medium".to_string()).receipt_id, 1);
+        assert_eq!(contract.get_treasury_balance(), U128(14));
+    }
+
+    #[test]
+    #[should_panic(expected = "insufficient deposit")]
+    fn test_subscription_exhausted() {
+        let (_, verifier_pk) = keypair(1);
+        testing_env!(get_context(accounts(0)).build());
+        let mut contract = Contract::new(verifier_pk);
+        contract.set_verify_fee(U128(5));
+        testing_env!(get_context(accounts(3)).attached_deposit(NearToken::from_yoctonear(MINT_STORAGE_COST)).build());
+        contract.register_consumer();
+        contract.withdraw_subscription(None);
+        testing_env!(get_context(accounts(3)).attached_deposit(NearToken::from_yoctonear(0)).build());
+        contract.verify_for(accounts(1), "keys_light".to_string(), accounts(3), String::new()).detach();
+    }
+
+    #[test]
+    #[should_panic(expected = "only authorized consumers can call this method")]
+    fn test_account_details_unauthorized() {
+        let (_, verifier_pk) = keypair(1);
+        testing_env!(get_context(accounts(2)).attached_deposit(NearToken::from_yoctonear(10)).build());
+        let mut contract = Contract::new(verifier_pk);
+        contract.get_account_details(accounts(1));
+    }
+
+    #[test]
+    fn test_intermediaries() {
+        let (verifier, verifier_pk) = keypair(1);
+        testing_env!(get_context(accounts(1)).build());
+        let mut contract = Contract::new(verifier_pk);
+        let message = messages::account_info_message(&accounts(1), 20u32, RequestBound::BlockHeight(10));
+        let signature = verifier.sign(message.as_bytes()).to_bytes().to_vec();
+        // accounts(1) calls a contract accounts(3) that forwards the attestation
+        testing_env!(get_context(accounts(3)).signer_account_id(accounts(1)).build());
+        assert_eq!(contract.update_access_key(signature.clone(), 20, Some(10), None), Err(ContractError::IndirectCall));
+        testing_env!(get_context(accounts(1)).build());
+        contract.allow_intermediary(accounts(3));
+        assert!(contract.is_intermediary_allowed(accounts(1), accounts(3)));
+        testing_env!(get_context(accounts(3)).signer_account_id(accounts(1)).build());
+        contract.update_access_key(signature, 20, Some(10), None).unwrap();
+        assert!(contract.connected_to_20_contracts(accounts(1), None));
+    }
+
+    #[test]
+    fn test_rate_limit() {
+        let (verifier, verifier_pk) = keypair(1);
+        testing_env!(get_context(accounts(0)).build());
+        let mut contract = Contract::new(verifier_pk);
+        contract.set_rate_limit(2);
+        testing_env!(get_context(accounts(1)).build());
+        for keys in [5u32, 10] {
+            let message = messages::account_info_message(&accounts(1), keys, RequestBound::BlockHeight(10));
+            let signature = verifier.sign(message.as_bytes()).to_bytes().to_vec();
+            contract.update_access_key(signature, keys, Some(10), None).unwrap();
+        }
+        assert_eq!(contract.get_epoch_writes(accounts(1)), 2);
+        let message = messages::social_message(&accounts(1), "lens", "bob.lens", "0x01", None, RequestBound::BlockHeight(10));
+        let signature = verifier.sign(message.as_bytes()).to_bytes().to_vec();
+        assert!(contract.register_social("lens".to_string(), signature.clone(), "bob.lens".to_string(), "0x01".to_string(), Some(10), None, None).is_err());
+        assert_eq!(failure_codes(), vec!["RATE_LIMITED"]);
+        // the count starts over in the next epoch
+        testing_env!(get_context(accounts(1)).epoch_height(1).build());
+        contract.register_social("lens".to_string(), signature, "bob.lens".to_string(), "0x01".to_string(), Some(10), None, None).unwrap();
+    }
+
+    #[test]
+    fn test_commit_reveal() {
+        let (verifier, verifier_pk) = keypair(1);
+        testing_env!(get_context(accounts(0)).build());
+        let mut contract = Contract::new(verifier_pk);
+        testing_env!(get_context(accounts(1)).block_timestamp(0).build());
+        let hash = contract.get_commitment_hash(accounts(1), "lens".to_string(), "bob.lens".to_string(), "0x01".to_string(), "salt".to_string());
+        contract.commit_social(hash);
+        let message = messages::social_message(&accounts(1), "lens", "bob.lens", "0x01", None, RequestBound::BlockHeight(10));
+        let signature = verifier.sign(message.as_bytes()).to_bytes().to_vec();
+        let reveal = |contract: &mut Contract, salt: &str| contract.reveal_social("lens".to_string(), signature.clone(), "bob.lens".to_string(), "0x01".to_string(), salt.to_string(), Some(10), None, None).is_ok();
+        assert!(!reveal(&mut contract, "salt"));
+        assert_eq!(failure_codes(), vec!["COMMITMENT_NOT_READY"]);
+        // a front-runner registering the handle after the commitment loses it at the reveal
+        testing_env!(get_context(accounts(2)).block_timestamp(10_000_000_000).build());
+        let message = messages::social_message(&accounts(2), "lens", "bob.lens", "0x02", None, RequestBound::BlockHeight(10));
+        let front_run = verifier.sign(message.as_bytes()).to_bytes().to_vec();
+        contract.register_social("lens".to_string(), front_run, "bob.lens".to_string(), "0x02".to_string(), Some(10), None, None).unwrap();
+        testing_env!(get_context(accounts(1)).block_timestamp(40_000_000_000).build());
+        assert!(contract.has_ripe_commitment(accounts(1)));
+        assert!(!reveal(&mut contract, "other salt"));
+        assert_eq!(failure_codes(), vec!["NO_COMMITMENT"]);
+        assert!(reveal(&mut contract, "salt"));
+        assert_eq!(contract.get_handles(accounts(1), "lens".to_string()), vec!["bob.lens".to_string()]);
+        assert!(contract.get_handles(accounts(2), "lens".to_string()).is_empty());
+        assert!(!contract.has_ripe_commitment(accounts(1)));
+    }
+
+    #[test]
+    fn test_circuit_breaker() {
+        let (verifier, verifier_pk) = keypair(1);
+        testing_env!(get_context(accounts(0)).build());
+        let mut contract = Contract::new(verifier_pk);
+        contract.set_circuit_breaker(Some(CircuitBreakerConfig { window_ns: U64(1_000_000_000), max_registrations: 1 }));
+        let register = |contract: &mut Contract, account: AccountId, handle: &str| {
+            testing_env!(get_context(account.clone()).build());
+            let message = messages::social_message(&account, "lens", handle, "0x01", None, RequestBound::BlockHeight(10));
+            let signature = verifier.sign(message.as_bytes()).to_bytes().to_vec();
+            contract.register_social("lens".to_string(), signature, handle.to_string(), "0x01".to_string(), Some(10), None, None).is_ok()
+        };
+        assert!(register(&mut contract, accounts(1), "bob.lens"));
+        assert!(register(&mut contract, accounts(2), "charlie.lens"));
+        assert!(contract.get_circuit_breaker().tripped);
+        assert_eq!(contract.ensure_not_paused(PAUSE_REGISTER_SOCIAL), Err(ContractError::CircuitBreakerTripped));
+        assert_eq!(contract.ensure_not_paused(PAUSE_UPDATE_ACCESS_KEY), Ok(()));
+        testing_env!(get_context(accounts(0)).build());
+        contract.reset_circuit_breaker();
+        assert!(register(&mut contract, accounts(3), "danny.lens"));
+    }
+
+    #[test]
+    fn test_disputes() {
+        let (verifier, verifier_pk) = keypair(1);
+        testing_env!(get_context(accounts(0)).build());
+        let mut contract = Contract::new(verifier_pk);
+        contract.set_dispute_bond(U128(100));
+        testing_env!(get_context(accounts(1)).build());
+        let message = messages::social_message(&accounts(1), "lens", "bob.lens", "0x01", None, RequestBound::BlockHeight(10));
+        let signature = verifier.sign(message.as_bytes()).to_bytes().to_vec();
+        contract.register_social("lens".to_string(), signature, "bob.lens".to_string(), "0x01".to_string(), Some(10), None, None).unwrap();
+        testing_env!(get_context(accounts(2)).attached_deposit(NearToken::from_yoctonear(MINT_STORAGE_COST)).build());
+        let dismissed = contract.file_dispute(accounts(1), "lens".to_string(), "ipfs://weak".to_string());
+        let upheld = contract.file_dispute(accounts(1), "lens".to_string(), "ipfs://strong".to_string());
+        assert_eq!(contract.get_disputes(None, None).len(), 2);
+        testing_env!(get_context(accounts(0)).build());
+        contract.resolve_dispute(dismissed, DisputeOutcome::Dismiss);
+        assert_eq!(contract.get_treasury_balance(), U128(100));
+        assert!(contract.connected_to_lens(accounts(1)));
+        contract.resolve_dispute(upheld, DisputeOutcome::Revoke);
+        assert!(!contract.connected_to_lens(accounts(1)));
+        assert_eq!(contract.get_revocation(accounts(1), "lens".to_string()).unwrap().reason, "dispute 1: ipfs://strong");
+        assert_eq!(contract.get_revocation_history(accounts(1))[0].code, RevocationCode::Disputed);
+        assert!(contract.get_disputes(None, None).is_empty());
+    }
+
+    #[test]
+    fn test_verifier_bonds() {
+        let (_, admin_pk) = keypair(1);
+        let (verifier, verifier_pk) = keypair(5);
+        testing_env!(get_context(accounts(0)).build());
+        let mut contract = Contract::new(admin_pk);
+        let id = contract.add_verifier(verifier_pk.clone());
+        execute_after_timelock(&mut contract, id);
+        contract.set_verifier_bond_config(VerifierBondConfig { min_bond: U128(1000), slash_pct: 50 });
+        let message = messages::social_message(&accounts(1), "lens", "bob.lens", "0x01", None, RequestBound::BlockHeight(10));
+        let signature = verifier.sign(message.as_bytes()).to_bytes().to_vec();
+        // not accepted until the key is bonded
+        testing_env!(get_context(accounts(1)).build());
+        assert!(contract.register_social("lens".to_string(), signature.clone(), "bob.lens".to_string(), "0x01".to_string(), Some(10), None, None).is_err());
+        deposit_storage(&mut contract, accounts(3));
+        testing_env!(get_context(accounts(3)).attached_deposit(NearToken::from_yoctonear(1000)).build());
+        let message = messages::bond_verifier_message(&accounts(0), &accounts(3));
+        contract.bond_verifier(verifier_pk.clone(), verifier.sign(message.as_bytes()).to_bytes().to_vec());
+        testing_env!(get_context(accounts(1)).build());
+        contract.register_social("lens".to_string(), signature, "bob.lens".to_string(), "0x01".to_string(), Some(10), None, None).unwrap();
+        assert_eq!(contract.get_provenance(accounts(1), "lens".to_string(), "bob.lens".to_string()), Some(Provenance::Verifier(Some(verifier_pk.clone()))));
+        // a dispute revoking the attestation slashes half the bond, leaving the key below the minimum
+        testing_env!(get_context(accounts(2)).build());
+        let id = contract.file_dispute(accounts(1), "lens".to_string(), "ipfs://fraud".to_string());
+        testing_env!(get_context(accounts(0)).build());
+        contract.resolve_dispute(id, DisputeOutcome::Revoke);
+        assert_eq!(contract.get_verifier_bond(verifier_pk.clone()).unwrap().amount, U128(500));
+        assert!(!contract.is_bonded(&verifier_pk));
+    }
+
+    #[test]
+    #[should_panic(expected = "unverified data")]
+    fn test_bond_verifier_requires_key_signature() {
+        let (_, admin_pk) = keypair(1);
+        let (_, verifier_pk) = keypair(5);
+        let (other, _) = keypair(6);
+        testing_env!(get_context(accounts(3)).attached_deposit(NearToken::from_yoctonear(1000)).build());
+        let mut contract = Contract::new(admin_pk);
+        // signed by a key other than the one being bonded
+        let message = messages::bond_verifier_message(&accounts(0), &accounts(3));
+        contract.bond_verifier(verifier_pk, other.sign(message.as_bytes()).to_bytes().to_vec());
+    }
+
+    #[test]
+    fn test_register_social_relayed() {
+        let (verifier, verifier_pk) = keypair(1);
+        let (user, user_pk) = keypair(2);
+        // accounts(2) relays on behalf of accounts(1)
+        let context = get_context(accounts(2));
+        testing_env!(context.build());
+        let mut contract = Contract::new(verifier_pk);
+        let bound = RequestBound::BlockHeight(10);
+        let nonce = [3u8; 32];
+        let user_message = messages::social_message(&accounts(1), "lens", "bob.lens", "0x01", None, bound);
+        let user_signature = user.sign(&nep413::payload_hash(&user_message, nonce, &accounts(0))).to_bytes().to_vec();
+        let verifier_message = messages::relayed_social_message(&accounts(1), &user_pk, "lens", "bob.lens", "0x01", None, bound);
+        let signature = verifier.sign(verifier_message.as_bytes()).to_bytes().to_vec();
+        contract.register_social_relayed(accounts(1), user_pk, nonce.to_vec().into(), user_signature, "lens".to_string(), signature, "bob.lens".to_string(), "0x01".to_string(), Some(10), None, None).unwrap();
+        assert!(contract.connected_to_platform(accounts(1), "lens".to_string()));
+        assert!(!contract.connected_to_platform(accounts(2), "lens".to_string()));
+    }
+
+    #[test]
+    fn test_link_evm_address() {
+        let (_, verifier_pk) = keypair(1);
+        testing_env!(get_context(accounts(1)).attached_deposit(NearToken::from_yoctonear(MINT_STORAGE_COST)).build());
+        let mut contract = Contract::new(verifier_pk);
+        // `personal_sign` by the key [7; 32] over "link_evm_address,alice,bob,10"
+        let address = "0x4A62316623AD457F02CDC5D997DED67A383EC569".to_string();
+        let signature: Vec<u8> = (0..65).map(|i| u8::from_str_radix(&"cdba7f835e8dbe9a7b9fb20ae121b84f8e9f9a4400e3b03d2897f3948d8320ec2534d174d87b19cc3a75cfca48f9cf50e37da49157acd3661da3d39effb531a71c"[2 * i..2 * i + 2], 16).unwrap()).collect();
+        contract.link_evm_address(address.clone(), signature.clone(), 10).unwrap();
+        assert_eq!(contract.evm_address_owner(address.clone()), Some(accounts(1)));
+        assert!(contract.storage_balance_of(accounts(1)).unwrap().total.0 > 0);
+        // the signature names the account, the contract and the bound
+        testing_env!(get_context(accounts(2)).build());
+        assert_eq!(contract.link_evm_address(address.clone(), signature.clone(), 10), Err(ContractError::InvalidEvmSignature));
+        testing_env!(get_context(accounts(1)).current_account_id(accounts(3)).build());
+        assert_eq!(contract.link_evm_address(address, signature, 10), Err(ContractError::InvalidEvmSignature));
+    }
+
+    #[test]
+    fn test_link_ed25519_address() {
+        let (_, verifier_pk) = keypair(1);
+        let (solana, _) = keypair(4);
+        testing_env!(get_context(accounts(1)).attached_deposit(NearToken::from_yoctonear(MINT_STORAGE_COST)).build());
+        let mut contract = Contract::new(verifier_pk);
+        let address = near_sdk::bs58::encode(solana.verifying_key().as_bytes()).into_string();
+        let message = ed25519_links::link_message(&accounts(0), &accounts(1), "solana", &address, 10);
+        let signature = solana.sign(message.as_bytes()).to_bytes().to_vec();
+        // the chain is signed, the same signature doesn't link the key under another label
+        assert_eq!(contract.link_ed25519_address("aptos".to_string(), address.clone(), signature.clone(), 10), Err(ContractError::UnverifiedData));
+        assert_eq!(contract.link_ed25519_address("solana,x".to_string(), address.clone(), signature.clone(), 10), Err(ContractError::InvalidChain));
+        contract.link_ed25519_address("solana".to_string(), address.clone(), signature.clone(), 10).unwrap();
+        assert_eq!(contract.ed25519_address_owner("solana".to_string(), address.clone()), Some(accounts(1)));
+        assert!(contract.is_ed25519_address_linked(accounts(1), "solana".to_string(), address.clone()));
+        // the same signature can't be replayed by another account
+        testing_env!(get_context(accounts(2)).build());
+        assert_eq!(contract.link_ed25519_address("solana".to_string(), address, signature, 10), Err(ContractError::UnverifiedData));
+    }
+
+    #[test]
+    fn test_migrate_from_v1() {
+        use migrations::{ContractV1, SocialDataV1, UserDataV1};
+        use near_sdk::collections::UnorderedMap;
+        let (_, admin_pk) = keypair(1);
+        testing_env!(get_context(accounts(0)).build());
+        // the first deployed contract, as its `new` laid it out
+        let mut old = ContractV1 { records: UnorderedMap::new(b"r".to_vec()), handles: UnorderedMap::new(b"h".to_vec()), admin_pub: admin_pk };
+        for (i, handle) in [(1, "bob.lens"), (2, "carol.lens")] {
+            let social = SocialDataV1 { issued_date: 1, handle: handle.to_string(), proof: "0x01".to_string(), expiry_date: u64::MAX };
+            let data = UserDataV1 { access_key_count: Some(3), account_age: None, socials: [("lens".to_string(), social)].into() };
+            old.records.insert(&accounts(i), &data);
+            old.handles.insert(&("lens".to_string(), handle.to_string()), &accounts(i));
+        }
+        let state = near_sdk::borsh::to_vec(&old).unwrap();
+        // reads as the current version by its first bytes alone
+        assert_eq!(state[..4], STATE_VERSION.to_le_bytes());
+        env::storage_write(b"STATE", &state);
+        let mut contract = Contract::migrate();
+        assert_eq!(contract.get_state_version(), STATE_VERSION);
+        assert_eq!(contract.get_timelock_delay(), U64(DEFAULT_TIMELOCK_DELAY_NS));
+        assert!(contract.is_paused());
+        // unpausing doesn't end the migration's pause, and finishing the migration keeps the owner's
+        contract.unpause();
+        assert!(contract.is_paused());
+        assert_eq!(contract.migrate_v1_batch(3), 1);
+        contract.pause();
+        assert_eq!(contract.migrate_v1_batch(3), 0);
+        assert!(contract.is_paused());
+        contract.unpause();
+        assert!(!contract.is_paused());
+        assert_eq!(contract.get_handles(accounts(2), "lens".to_string()), vec!["carol.lens"]);
+        assert_eq!(contract.handles.get(&("lens".to_string(), "bob.lens".to_string())), Some(&accounts(1)));
+        assert_eq!(contract.access_key_counts.get(&accounts(1)), Some(&3));
+    }
+
+    #[test]
+    fn test_timelock() {
+        let (_, admin_pk) = keypair(1);
+        let (_, verifier_pk) = keypair(5);
+        let day = 24 * 60 * 60 * 1_000_000_000;
+        testing_env!(get_context(accounts(0)).build());
+        let mut contract = Contract::new(admin_pk);
+        assert_eq!(contract.get_timelock_delay(), U64(2 * day));
+        assert_eq!(contract.set_timelock_delay(U64(3 * day)), None);
+        let cancelled = contract.add_verifier(verifier_pk.clone());
+        contract.cancel_pending_change(cancelled);
+        assert!(contract.get_pending_changes().is_empty());
+        // lowering the delay waits out the current one
+        let id = contract.set_timelock_delay(U64(day)).unwrap();
+        assert_eq!(contract.get_timelock_delay(), U64(3 * day));
+        testing_env!(get_context(accounts(0)).block_timestamp(3 * day).build());
+        contract.execute_change(id);
+        assert_eq!(contract.get_timelock_delay(), U64(day));
+        assert!(contract.get_pending_changes().is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "change is still timelocked")]
+    fn test_timelocked_change_not_executable_early() {
+        let (_, admin_pk) = keypair(1);
+        let (_, verifier_pk) = keypair(5);
+        testing_env!(get_context(accounts(0)).build());
+        let mut contract = Contract::new(admin_pk);
+        let id = contract.add_verifier(verifier_pk);
+        testing_env!(get_context(accounts(0)).block_timestamp(DEFAULT_TIMELOCK_DELAY_NS - 1).build());
+        contract.execute_change(id);
+    }
+
+    #[test]
+    #[should_panic(expected = "timelock delay must be at most")]
+    fn test_timelock_delay_bounded() {
+        let (_, admin_pk) = keypair(1);
+        testing_env!(get_context(accounts(0)).build());
+        let mut contract = Contract::new(admin_pk);
+        contract.set_timelock_delay(U64(u64::MAX));
+    }
+
+    #[test]
+    fn test_rotate_admin_key() {
+        let (admin, admin_pk) = keypair(1);
+        let (new_admin, new_pk) = keypair(6);
+        testing_env!(get_context(accounts(0)).build());
+        let mut contract = Contract::new(admin_pk.clone());
+        let id = contract.propose_admin_key(new_pk.clone());
+        assert!(contract.get_admin_keys().pending.is_none());
+        testing_env!(get_context(accounts(0)).block_timestamp(DEFAULT_TIMELOCK_DELAY_NS).build());
+        contract.execute_change(id);
+        assert_eq!(contract.get_admin_keys().pending, Some(new_pk.clone()));
+        // only the proposed key can accept
+        let message = messages::accept_admin_key_message(&accounts(0), &new_pk);
+        assert_eq!(contract.accept_admin_key(admin.sign(message.as_bytes()).to_bytes().to_vec()), Err(ContractError::UnverifiedData));
+        contract.accept_admin_key(new_admin.sign(message.as_bytes()).to_bytes().to_vec()).unwrap();
+        let keys = contract.get_admin_keys();
+        assert_eq!((keys.current, keys.previous, keys.previous_valid_until), (new_pk, Some(admin_pk.clone()), U64(DEFAULT_TIMELOCK_DELAY_NS + admin_keys::ADMIN_KEY_OVERLAP_NS)));
+        // the replaced key keeps validating until the overlap ends
+        let signature = admin.sign(b"payload").to_bytes().to_vec();
+        assert_eq!(contract.verify_signature("payload", &signature), Ok(admin_pk));
+        testing_env!(get_context(accounts(0)).block_timestamp(DEFAULT_TIMELOCK_DELAY_NS + admin_keys::ADMIN_KEY_OVERLAP_NS).build());
+        assert_eq!(contract.verify_signature("payload", &signature), Err(ContractError::UnverifiedData));
+    }
+
+    #[test]
+    fn test_recovery_key_not_accepted_as_admin_key() {
+        let (_, admin_pk) = keypair(1);
+        let (recovery, recovery_pk) = keypair(5);
+        testing_env!(get_context(accounts(0)).build());
+        let mut contract = Contract::new(admin_pk);
+        let id = contract.propose_admin_key(recovery_pk.clone());
+        execute_after_timelock(&mut contract, id);
+        // made the recovery key after it was proposed, it can no longer take over signing
+        let id = contract.set_recovery_key(recovery_pk.clone());
+        execute_after_timelock(&mut contract, id);
+        let message = messages::accept_admin_key_message(&accounts(0), &recovery_pk);
+        assert_eq!(contract.accept_admin_key(recovery.sign(message.as_bytes()).to_bytes().to_vec()), Err(ContractError::RecoveryKeyReserved));
+    }
+
+    #[test]
+    #[should_panic(expected = "the recovery key can only replace the admin key")]
+    fn test_recovery_key_not_a_platform_verifier() {
+        let (_, admin_pk) = keypair(1);
+        let (_, recovery_pk) = keypair(5);
+        testing_env!(get_context(accounts(0)).build());
+        let mut contract = Contract::new(admin_pk);
+        let id = contract.set_recovery_key(recovery_pk.clone());
+        execute_after_timelock(&mut contract, id);
+        contract.set_platform_verifier("lens".to_string(), Some(recovery_pk));
+    }
+
+    #[test]
+    fn test_recover_admin_key() {
+        let (_, admin_pk) = keypair(1);
+        let (recovery, recovery_pk) = keypair(5);
+        let (_, new_pk) = keypair(6);
+        testing_env!(get_context(accounts(0)).build());
+        let mut contract = Contract::new(admin_pk);
+        let id = contract.set_recovery_key(recovery_pk);
+        execute_after_timelock(&mut contract, id);
+        let message = messages::recover_admin_key_message(&accounts(0), &new_pk, RequestBound::Timestamp(10));
+        let signature = recovery.sign(message.as_bytes()).to_bytes().to_vec();
+        assert_eq!(contract.recover_admin_key(new_pk.clone(), signature.clone(), Some(10), Some(U64(10))), Err(ContractError::InvalidRequestBound));
+        assert_eq!(contract.recover_admin_key(new_pk.clone(), signature.clone(), Some(10), None), Err(ContractError::UnverifiedData));
+        contract.recover_admin_key(new_pk.clone(), signature, None, Some(U64(10))).unwrap();
+        let keys = contract.get_admin_keys();
+        assert_eq!(keys.current, new_pk);
+        assert!(keys.previous.is_none());
+    }
+
+    #[test]
+    #[should_panic(expected = "recovery key must differ from the verifier keys")]
+    fn test_platform_verifier_not_a_recovery_key() {
+        let (_, admin_pk) = keypair(1);
+        let (_, platform_pk) = keypair(5);
+        testing_env!(get_context(accounts(0)).build());
+        let mut contract = Contract::new(admin_pk);
+        let id = contract.set_platform_verifier("lens".to_string(), Some(platform_pk.clone()));
+        execute_after_timelock(&mut contract, id);
+        contract.set_recovery_key(platform_pk);
+    }
+
+    #[test]
+    #[should_panic(expected = "recovery key must differ from the verifier keys")]
+    fn test_replaced_admin_key_not_a_recovery_key() {
+        let (_, admin_pk) = keypair(1);
+        let (new_admin, new_pk) = keypair(6);
+        testing_env!(get_context(accounts(0)).build());
+        let mut contract = Contract::new(admin_pk.clone());
+        let id = contract.propose_admin_key(new_pk.clone());
+        execute_after_timelock(&mut contract, id);
+        let message = messages::accept_admin_key_message(&accounts(0), &new_pk);
+        contract.accept_admin_key(new_admin.sign(message.as_bytes()).to_bytes().to_vec()).unwrap();
+        // the replaced key still validates during the overlap
+        let id = contract.set_recovery_key(admin_pk);
+        execute_after_timelock(&mut contract, id);
+    }
+}
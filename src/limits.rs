@@ -0,0 +1,30 @@
+use near_sdk::near;
+
+use crate::{Contract, ContractError, ContractExt, InputLimits};
+
+pub(crate) fn check_len(field: &'static str, value: &str, max: u32) -> Result<(), ContractError> {
+    if value.len() > max as usize {
+        return Err(ContractError::InputTooLong { field, max });
+    }
+    Ok(())
+}
+
+#[near]
+impl Contract {
+    pub fn set_input_limits(&mut self, limits: InputLimits) {
+        self.assert_owner();
+        self.input_limits = limits;
+    }
+
+    pub fn get_input_limits(&self) -> InputLimits {
+        self.input_limits.clone()
+    }
+}
+
+impl Contract {
+    pub(crate) fn check_input_limits(&self, platform: &str, handle: &str, proof: &str) -> Result<(), ContractError> {
+        check_len("platform", platform, self.input_limits.max_platform_len)?;
+        check_len("handle", handle, self.input_limits.max_handle_len)?;
+        check_len("proof", proof, self.input_limits.max_proof_len)
+    }
+}
@@ -0,0 +1,253 @@
+use near_sdk::serde_json::json;
+use near_sdk::{env, near, require, AccountId};
+
+use crate::stamps::MAX_STAMPS;
+use crate::{events, Contract, ContractExt, FunctionError, ReleaseReason, SocialData, PAUSE_LINK_ACCOUNT, PAUSE_MERGE_INTO};
+
+// accounts in one identity, e.g. a hot wallet and a vault
+const MAX_LINKED_ACCOUNTS: usize = 10;
+
+// two accounts link by both calling `link_account` with the other. the first account of an identity is its
+// primary, and accounts linking to a member later join that identity.
+#[near]
+impl Contract {
+    /// Asks `other_account` to link with the caller, or confirms its earlier request. Storage the link takes is
+    /// paid like a registration's, any excess deposit is refunded.
+    #[payable]
+    pub fn link_account(&mut self, other_account: AccountId) {
+        self.ensure_not_paused(PAUSE_LINK_ACCOUNT).unwrap_or_else(|err| err.panic());
+        let account_id = env::predecessor_account_id();
+        require!(account_id != other_account, "cannot link an account to itself");
+        require!(!self.merged_accounts.contains_key(&account_id) && !self.merged_accounts.contains_key(&other_account), "merged accounts can't be linked");
+        self.ensure_not_banned(&account_id).unwrap_or_else(|err| err.panic());
+        let usage_before = env::storage_usage();
+        if self.link_requests.remove(&(other_account.clone(), account_id.clone())) {
+            self.join_identity(&other_account, &account_id);
+            events::emit("accounts_linked", json!({ "primary": self.primary_account(&account_id), "account_id": account_id, "other_account": other_account }));
+        } else {
+            require!(self.primary_account(&account_id) != self.primary_account(&other_account), "accounts are already linked");
+            self.link_requests.insert((account_id.clone(), other_account.clone()));
+            events::emit("link_requested", json!({ "account_id": account_id, "other_account": other_account }));
+        }
+        self.settle_storage(&account_id, usage_before).unwrap_or_else(|err| err.panic());
+    }
+
+    /// Removes the caller from its identity. A primary leaving hands that role to the next account.
+    pub fn unlink_account(&mut self) {
+        let account_id = env::predecessor_account_id();
+        require!(self.identity_of.contains_key(&account_id), "account is not linked");
+        self.flush_collections();
+        let usage_before = env::storage_usage();
+        self.leave_identity(&account_id);
+        events::emit("account_unlinked", json!({ "account_id": account_id }));
+        self.flush_collections();
+        self.release_storage(&account_id, usage_before.saturating_sub(env::storage_usage()));
+    }
+
+    /// Moves the caller's socials and attested attributes to `primary`, another account of its identity, and
+    /// leaves the identity. Checks for the caller then answer for `primary`.
+    ///
+    /// Where both hold a handle on a single handle platform the attestation expiring later is kept, `primary`'s on
+    /// a tie, and socials `primary` has no room for are dropped. Key counts keep the higher value and account ages
+    /// the older one. Linked addresses stay with the caller. Storage `primary` gains is paid from its balance and
+    /// the attached deposit, any excess is refunded.
+    #[payable]
+    pub fn merge_into(&mut self, primary: AccountId) {
+        self.ensure_not_paused(PAUSE_MERGE_INTO).unwrap_or_else(|err| err.panic());
+        let account_id = env::predecessor_account_id();
+        require!(account_id != primary, "cannot merge an account into itself");
+        require!(self.identity_of.contains_key(&account_id) && self.primary_account(&account_id) == self.primary_account(&primary), "accounts are not linked");
+        self.ensure_not_banned(&account_id).unwrap_or_else(|err| err.panic());
+        self.ensure_not_banned(&primary).unwrap_or_else(|err| err.panic());
+        // checks for the caller answer for `primary` from now on, its own cached score would only go stale
+        self.flush_collections();
+        let usage_before = env::storage_usage();
+        self.cached_scores.remove(&account_id);
+        self.flush_collections();
+        self.release_storage(&account_id, usage_before.saturating_sub(env::storage_usage()));
+        let mut socials: Vec<(String, SocialData)> = Vec::new();
+        for platform in self.social_platforms.get(&account_id).cloned().unwrap_or_default() {
+            for social in self.socials_on(&account_id, &platform) {
+                self.remove_handle(&account_id, &platform, &social.handle, ReleaseReason::Merged);
+                socials.push((platform.clone(), social));
+            }
+        }
+        // ordered so conflicts resolve the same however the caller's socials were stored
+        socials.sort_by(|(a, x), (b, y)| (a, &x.handle).cmp(&(b, &y.handle)));
+        self.flush_collections();
+        let usage_before = env::storage_usage();
+        let (mut moved, mut dropped) = (0u32, 0u32);
+        for (platform, social) in socials {
+            let handle = social.handle.clone();
+            let kept = self.platform_config(&platform).multi_handle
+                || self.social(&primary, &platform).is_none_or(|held| social.expiry_date > held.expiry_date);
+            if kept && self.store_social(&primary, &platform, social).is_ok() {
+                moved += 1;
+            } else {
+                dropped += 1;
+                events::emit("merge_conflict", json!({ "platform": platform, "handle": handle, "account_id": account_id, "primary": primary }));
+            }
+        }
+        // the attribute kept carries over when it was attested
+        let updates = self.attribute_updates.remove(&account_id).unwrap_or_default();
+        if let Some(keys) = self.access_key_counts.remove(&account_id) {
+            if self.access_key_counts.get(&primary).is_none_or(|&held| keys > held) {
+                self.access_key_counts.insert(primary.clone(), keys);
+                self.attribute_updates_mut(&primary).access_keys = updates.access_keys;
+            }
+        }
+        if let Some(created) = self.account_ages.remove(&account_id) {
+            if self.account_ages.get(&primary).is_none_or(|&held| created < held) {
+                self.account_ages.insert(primary.clone(), created);
+                self.attribute_updates_mut(&primary).account_age = updates.account_age;
+            }
+        }
+        if let Some(passport) = self.passport_scores.remove(&account_id) {
+            if self.passport_scores.get(&primary).is_none_or(|held| passport.expires_at > held.expires_at) {
+                self.passport_scores.insert(primary.clone(), passport);
+            }
+        }
+        // nullifiers stay spent, now on the primary, even where the primary's own World ID wins. those it doesn't
+        // hold as its World ID are tracked so erasing the primary frees them
+        let mut nullifiers = self.merged_world_id_nullifiers.remove(&account_id).unwrap_or_default();
+        if let Some(world_id) = self.world_ids.remove(&account_id) {
+            if self.world_ids.contains_key(&primary) {
+                nullifiers.push(world_id.nullifier_hash);
+            } else {
+                self.world_id_nullifiers.insert(world_id.nullifier_hash.clone(), primary.clone());
+                self.world_ids.insert(primary.clone(), world_id);
+            }
+        }
+        if !nullifiers.is_empty() {
+            for nullifier in &nullifiers {
+                self.world_id_nullifiers.insert(nullifier.clone(), primary.clone());
+            }
+            let mut held = self.merged_world_id_nullifiers.get(&primary).cloned().unwrap_or_default();
+            held.extend(nullifiers);
+            self.merged_world_id_nullifiers.insert(primary.clone(), held);
+        }
+        if let Some(stamps) = self.stamps.remove(&account_id) {
+            // the primary's own stamp wins where both hold the type
+            let mut held = self.stamps.get(&primary).cloned().unwrap_or_default();
+            for stamp in stamps {
+                if held.len() < MAX_STAMPS && held.iter().all(|other| other.stamp_type != stamp.stamp_type) {
+                    held.push(stamp);
+                }
+            }
+            self.stamps.insert(primary.clone(), held);
+        }
+        self.accounts.insert(primary.clone());
+        if !self.holds_attestations(&account_id) {
+            self.accounts.remove(&account_id);
+        }
+        self.leave_identity(&account_id);
+        self.merged_accounts.insert(account_id.clone(), primary.clone());
+        self.refresh_cached_score(&primary);
+        events::emit("accounts_merged", json!({ "account_id": account_id, "primary": primary, "moved": moved, "dropped": dropped }));
+        self.settle_storage(&primary, usage_before).unwrap_or_else(|err| err.panic());
+    }
+
+    /// The account `account_id` was merged into, following later merges, `None` if it never was.
+    pub fn get_merged_into(&self, account_id: AccountId) -> Option<AccountId> {
+        self.merged_accounts.contains_key(&account_id).then(|| self.merge_target(&account_id))
+    }
+
+    /// The primary account of the identity `account_id` belongs to, itself if it isn't linked. Merged accounts
+    /// resolve through the account they merged into.
+    pub fn get_primary_account(&self, account_id: AccountId) -> AccountId {
+        self.primary_account(&self.merge_target(&account_id))
+    }
+
+    /// Every account of the identity, primary first, just `account_id` if it isn't linked.
+    pub fn get_linked_accounts(&self, account_id: AccountId) -> Vec<AccountId> {
+        self.identity(&account_id)
+    }
+
+    /// Whether the checks and `score` see the best attestations across an account's identity rather than only
+    /// its own.
+    pub fn set_resolve_linked_accounts(&mut self, enabled: bool) {
+        self.assert_owner();
+        self.resolve_linked = enabled;
+    }
+
+    pub fn get_resolve_linked_accounts(&self) -> bool {
+        self.resolve_linked
+    }
+}
+
+impl Contract {
+    pub(crate) fn primary_account(&self, account_id: &AccountId) -> AccountId {
+        self.identity_of.get(account_id).cloned().unwrap_or_else(|| account_id.clone())
+    }
+
+    // the account a merged account's data ended up in, `account_id` itself if it wasn't merged. merged accounts
+    // can't link again, so the chain has no cycles.
+    pub(crate) fn merge_target(&self, account_id: &AccountId) -> AccountId {
+        let mut account_id = account_id;
+        while let Some(target) = self.merged_accounts.get(account_id) {
+            account_id = target;
+        }
+        account_id.clone()
+    }
+
+    fn identity(&self, account_id: &AccountId) -> Vec<AccountId> {
+        self.identity_members.get(&self.primary_account(account_id)).cloned().unwrap_or_else(|| vec![account_id.clone()])
+    }
+
+    // the accounts whose attestations count for `account_id`, leaving out banned ones: its identity with
+    // `resolve_linked`, otherwise just itself, or for a merged account those of the account it merged into. empty
+    // if `account_id` is banned.
+    pub(crate) fn resolved_accounts(&self, account_id: &AccountId) -> Vec<AccountId> {
+        if self.bans.contains_key(account_id) {
+            return Vec::new();
+        }
+        let account_id = &self.merge_target(account_id);
+        if self.bans.contains_key(account_id) {
+            return Vec::new();
+        }
+        if !self.resolve_linked {
+            return vec![account_id.clone()];
+        }
+        self.identity(account_id).into_iter().filter(|member| !self.bans.contains_key(member)).collect()
+    }
+
+    // links `account_id` into the identity of `requester`, or of `account_id` if only it has one
+    fn join_identity(&mut self, requester: &AccountId, account_id: &AccountId) {
+        let (primary, joining) = match (self.identity_of.get(requester), self.identity_of.get(account_id)) {
+            (Some(_), Some(_)) => env::panic_str("both accounts already belong to an identity"),
+            (Some(primary), None) => (primary.clone(), account_id),
+            (None, Some(primary)) => (primary.clone(), requester),
+            (None, None) => {
+                self.identity_of.insert(requester.clone(), requester.clone());
+                (requester.clone(), account_id)
+            }
+        };
+        let mut members = self.identity_members.get(&primary).cloned().unwrap_or_else(|| vec![primary.clone()]);
+        require!(members.len() < MAX_LINKED_ACCOUNTS, format!("an identity links at most {} accounts", MAX_LINKED_ACCOUNTS));
+        members.push(joining.clone());
+        self.identity_of.insert(joining.clone(), primary.clone());
+        self.identity_members.insert(primary, members);
+    }
+
+    pub(crate) fn leave_identity(&mut self, account_id: &AccountId) {
+        let Some(primary) = self.identity_of.remove(account_id) else {
+            return;
+        };
+        let mut members = self.identity_members.remove(&primary).unwrap_or_default();
+        members.retain(|member| member != account_id);
+        if members.len() < 2 {
+            // a single account left is no longer linked
+            for member in members {
+                self.identity_of.remove(&member);
+            }
+            return;
+        }
+        let new_primary = members[0].clone();
+        if new_primary != primary {
+            for member in &members {
+                self.identity_of.insert(member.clone(), new_primary.clone());
+            }
+        }
+        self.identity_members.insert(new_primary, members);
+    }
+}
@@ -0,0 +1,157 @@
+use std::fmt::{self, Display};
+
+use near_sdk::json_types::U64;
+#[cfg(feature = "expanded-views")]
+use near_sdk::near;
+use near_sdk::{env, AccountId, PublicKey};
+
+use crate::{ContractError, WorldIdProof};
+#[cfg(feature = "expanded-views")]
+use crate::{Contract, ContractExt, TestVector};
+
+// payloads signed by the verifier backend. every signed message is a comma separated list of fields
+// starting with the account the attestation is for and ending with the request bound.
+
+/// Validity bound of a signed request, either a block height or a timestamp in nanoseconds.
+/// Block heights encode as the bare number, timestamps as `ts:<nanoseconds>`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RequestBound {
+    BlockHeight(u64),
+    Timestamp(u64),
+}
+
+impl RequestBound {
+    pub(crate) fn new(max_block_height: Option<u64>, valid_until_timestamp_ns: Option<U64>) -> Result<Self, ContractError> {
+        match (max_block_height, valid_until_timestamp_ns) {
+            (Some(height), None) => Ok(RequestBound::BlockHeight(height)),
+            (None, Some(timestamp)) => Ok(RequestBound::Timestamp(timestamp.0)),
+            _ => Err(ContractError::InvalidRequestBound),
+        }
+    }
+
+    pub(crate) fn ensure_valid(&self) -> Result<(), ContractError> {
+        let valid = match self {
+            RequestBound::BlockHeight(height) => *height > env::block_height(),
+            RequestBound::Timestamp(timestamp) => *timestamp > env::block_timestamp(),
+        };
+        if valid { Ok(()) } else { Err(ContractError::RequestExpired) }
+    }
+}
+
+impl Display for RequestBound {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RequestBound::BlockHeight(height) => write!(f, "{}", height),
+            RequestBound::Timestamp(timestamp) => write!(f, "ts:{}", timestamp),
+        }
+    }
+}
+
+// the validity the verifier grants in place of the platform's `validity_ns` is signed as `validity:<nanoseconds>`
+// before the request bound, `validity:0` for the platform's own. always present, so a proof ending in
+// `,validity:<n>` can't pass a payload signed with a validity off as one without.
+fn validity_field(validity_ns: Option<u64>) -> String {
+    format!("validity:{},", validity_ns.unwrap_or(0))
+}
+
+pub fn social_message(account_id: &AccountId, platform: &str, handle: &str, proof: &str, validity_ns: Option<u64>, bound: RequestBound) -> String {
+    format!("{},{},{},{},{}{}", account_id, platform, handle, proof, validity_field(validity_ns), bound)
+}
+
+// verifier payload for relayed registrations, additionally attesting that `public_key` is a key of `account_id`
+pub fn relayed_social_message(account_id: &AccountId, public_key: &PublicKey, platform: &str, handle: &str, proof: &str, validity_ns: Option<u64>, bound: RequestBound) -> String {
+    format!("{},{},{},{},{},{}{}", account_id, String::from(public_key), platform, handle, proof, validity_field(validity_ns), bound)
+}
+
+// names the method first so it can never be read as a `social_message`
+pub fn renew_social_message(account_id: &AccountId, platform: &str, handle: &str, bound: RequestBound) -> String {
+    format!("renew_social,{},{},{},{}", account_id, platform, handle, bound)
+}
+
+pub fn transfer_handle_message(account_id: &AccountId, receiver_id: &AccountId, platform: &str, handle: &str, bound: RequestBound) -> String {
+    format!("transfer_handle,{},{},{},{},{}", account_id, receiver_id, platform, handle, bound)
+}
+
+pub fn passport_score_message(account_id: &AccountId, score: u32, bound: RequestBound) -> String {
+    format!("passport_score,{},{},{}", account_id, score, bound)
+}
+
+pub fn world_id_message(account_id: &AccountId, proof: &WorldIdProof, bound: RequestBound) -> String {
+    format!("world_id,{},{},{},{},{}", account_id, proof.nullifier_hash, proof.merkle_root, proof.verification_level, bound)
+}
+
+pub fn stamp_message(account_id: &AccountId, stamp_type: &str, value: &str, expires_at: Option<u64>, bound: RequestBound) -> String {
+    let expires = expires_at.map_or_else(String::new, |expires_at| format!("expires:{},", expires_at));
+    format!("stamp,{},{},{},{}{}", account_id, stamp_type, value, expires, bound)
+}
+
+pub fn account_info_message(account_id: &AccountId, account_info: impl Display, bound: RequestBound) -> String {
+    format!("{},{},{}", account_id, account_info, bound)
+}
+
+// what an account writes to its NEAR Social profile for `verify_near_social`
+pub(crate) fn near_social_claim(contract_id: &AccountId, account_id: &AccountId) -> String {
+    format!("near_social_claim,{},{}", contract_id, account_id)
+}
+
+pub(crate) fn accept_admin_key_message(contract_id: &AccountId, new_key: &PublicKey) -> String {
+    format!("accept_admin_key,{},{}", contract_id, String::from(new_key))
+}
+
+pub(crate) fn bond_verifier_message(contract_id: &AccountId, account_id: &AccountId) -> String {
+    format!("bond_verifier,{},{}", contract_id, account_id)
+}
+
+pub(crate) fn recover_admin_key_message(contract_id: &AccountId, new_key: &PublicKey, bound: RequestBound) -> String {
+    format!("recover_admin_key,{},{},{}", contract_id, String::from(new_key), bound)
+}
+
+#[cfg(feature = "expanded-views")]
+fn test_vector(method: &str, message: String) -> TestVector {
+    #[cfg(feature = "dev")]
+    let (signature, public_key) = {
+        let (signing_key, public_key) = crate::test_utils::keypair(crate::test_utils::DEV_SECRET_KEY);
+        (Some(crate::test_utils::sign(&signing_key, &message)), Some(public_key))
+    };
+    #[cfg(not(feature = "dev"))]
+    let (signature, public_key) = (None, None);
+    TestVector { method: method.to_string(), message_bytes: message.as_bytes().to_vec(), message, signature, public_key }
+}
+
+#[cfg(feature = "expanded-views")]
+#[near]
+impl Contract {
+    /// Example payloads with their exact byte encodings, for checking a backend signer against the deployed contract.
+    pub fn get_signing_test_vectors(&self) -> Vec<TestVector> {
+        let account_id: AccountId = "alice.near".parse().unwrap();
+        let height = RequestBound::BlockHeight(100_000_000);
+        let timestamp = RequestBound::Timestamp(1_700_000_000_000_000_000);
+        vec![
+            test_vector("register_social", social_message(&account_id, "lens", "alice.lens", "0x8a3f", None, height)),
+            test_vector("register_social", social_message(&account_id, "lens", "alice.lens", "0x8a3f", None, timestamp)),
+            test_vector("register_social", social_message(&account_id, "lens", "alice.lens", "0x8a3f", Some(30 * 24 * 60 * 60 * 1_000_000_000), height)),
+            test_vector(
+                "register_social_relayed",
+                relayed_social_message(&account_id, &"ed25519:6BTMQWnxGDrzWizymRMdnRsofDMRJ1assMUrym6kSEj9".parse().unwrap(), "lens", "alice.lens", "0x8a3f", None, height),
+            ),
+            test_vector("renew_social", renew_social_message(&account_id, "lens", "alice.lens", height)),
+            test_vector("transfer_handle", transfer_handle_message(&account_id, &"bob.near".parse().unwrap(), "lens", "alice.lens", height)),
+            test_vector("update_access_key", account_info_message(&account_id, 12u32, height)),
+            test_vector("update_contract_age", account_info_message(&account_id, 1_650_000_000_000_000_000u128, height)),
+            test_vector("import_passport_score", passport_score_message(&account_id, 2050, height)),
+            test_vector("add_stamp", stamp_message(&account_id, "poap_count", "7", Some(1_800_000_000_000_000_000), height)),
+            test_vector(
+                "verify_world_id",
+                world_id_message(
+                    &account_id,
+                    &WorldIdProof {
+                        nullifier_hash: format!("0x{}", "2b".repeat(32)),
+                        merkle_root: format!("0x{}", "1f".repeat(32)),
+                        verification_level: "orb".to_string(),
+                    },
+                    height,
+                ),
+            ),
+        ]
+    }
+}
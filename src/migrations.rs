@@ -0,0 +1,135 @@
+use std::collections::HashMap;
+
+use near_sdk::borsh::BorshDeserialize;
+use near_sdk::collections::UnorderedMap;
+use near_sdk::{env, near, AccountId, PublicKey};
+
+use crate::{proofs, Contract, ContractExt, Provenance, Role, SocialData};
+
+/// Version of the layout `Contract` is stored in, kept as its first field.
+pub(crate) const STATE_VERSION: u32 = 2;
+
+const STATE_KEY: &[u8] = b"STATE";
+
+// layout of the first deployed version, legacy collections under the raw `b"r"` / `b"h"` prefixes. it predates the
+// version field and can't be told apart by its first bytes: they are the length of the `b"ri"` key index prefix,
+// which reads as version 2. it is recognized by the whole state deserializing as this struct instead.
+#[near(serializers = [borsh])]
+pub(crate) struct ContractV1 {
+    pub records: UnorderedMap<AccountId, UserDataV1>,
+    pub handles: UnorderedMap<(String, String), AccountId>,
+    pub admin_pub: PublicKey,
+}
+
+// the version 1 collections `migrate_v1_batch` hasn't emptied yet, their entries still under the raw prefixes
+#[near(serializers = [borsh])]
+pub(crate) struct LegacyCollections {
+    records: UnorderedMap<AccountId, UserDataV1>,
+    handles: UnorderedMap<(String, String), AccountId>,
+}
+
+#[near(serializers = [borsh])]
+pub(crate) struct UserDataV1 {
+    pub access_key_count: Option<u32>,
+    pub account_age: Option<u128>,
+    pub socials: HashMap<String, SocialDataV1>,
+}
+
+#[near(serializers = [borsh])]
+pub(crate) struct SocialDataV1 {
+    pub issued_date: u64,
+    pub handle: String,
+    pub proof: String,
+    pub expiry_date: u64,
+}
+
+impl From<SocialDataV1> for SocialData {
+    fn from(social: SocialDataV1) -> Self {
+        SocialData {
+            issued_date: social.issued_date,
+            handle: social.handle,
+            proof_digest: proofs::proof_digest(&social.proof),
+            proof_type: proofs::proof_type(&social.proof),
+            expiry_date: social.expiry_date,
+            provenance: Provenance::Verifier(None),
+        }
+    }
+}
+
+#[near]
+impl Contract {
+    /// Rewrites the stored state from whatever version it is in to the current layout. Deploy the new code
+    /// and call this in the same batch transaction. From version 1 the records and handles are left for
+    /// `migrate_v1_batch` and the contract stays paused until it has moved them all.
+    #[private]
+    #[init(ignore_state)]
+    pub fn migrate() -> Self {
+        let state = env::storage_read(STATE_KEY).unwrap_or_else(|| env::panic_str("no state to migrate"));
+        if let Ok(old) = ContractV1::try_from_slice(&state) {
+            return Self::migrate_from_v1(old);
+        }
+        let version = state.get(..4).and_then(|bytes| u32::try_from_slice(bytes).ok()).unwrap_or(0);
+        match version {
+            STATE_VERSION => env::panic_str("state is already at the current version"),
+            _ => env::panic_str(&format!("unknown state version {}", version)),
+        }
+    }
+
+    /// Moves up to `limit` version 1 records, then handles, into the current layout and returns how many are
+    /// left. Each record takes a few storage writes per platform, a `limit` of 100 stays well within a
+    /// transaction's gas. Once none are left the contract takes registrations again, unless the owner paused it.
+    pub fn migrate_v1_batch(&mut self, limit: u32) -> u64 {
+        self.assert_any_role(&[Role::Owner, Role::Operator]);
+        let mut legacy = self.legacy_v1.take().unwrap_or_else(|| env::panic_str("no version 1 data left to migrate"));
+        let records: Vec<_> = legacy.records.iter().take(limit as usize).collect();
+        let limit = limit as usize - records.len();
+        for (account_id, data) in records {
+            legacy.records.remove(&account_id);
+            self.import_v1_record(account_id, data);
+        }
+        let handles: Vec<_> = legacy.handles.iter().take(limit).collect();
+        for (key, account_id) in handles {
+            legacy.handles.remove(&key);
+            self.handles.insert(key, account_id);
+        }
+        let left = legacy.records.len() + legacy.handles.len();
+        if left == 0 {
+            env::log_str("version 1 migration complete");
+        } else {
+            self.legacy_v1 = Some(legacy);
+        }
+        left
+    }
+
+    pub fn get_state_version(&self) -> u32 {
+        self.state_version
+    }
+}
+
+impl Contract {
+    // keeps the old collections where they are for `migrate_v1_batch`, so this call costs the same whatever
+    // their size. paused until they are empty, see `ensure_not_paused`.
+    fn migrate_from_v1(old: ContractV1) -> Self {
+        let mut contract = Contract::new(old.admin_pub);
+        contract.legacy_v1 = Some(LegacyCollections { records: old.records, handles: old.handles });
+        contract
+    }
+
+    // splits a record into the per-attribute maps, proofs reduced to their digest. ages that don't fit a u64
+    // timestamp are dropped.
+    fn import_v1_record(&mut self, account_id: AccountId, data: UserDataV1) {
+        if let Some(count) = data.access_key_count {
+            self.access_key_counts.insert(account_id.clone(), count);
+        }
+        if let Some(age) = data.account_age.and_then(|age| u64::try_from(age).ok()) {
+            self.account_ages.insert(account_id.clone(), age);
+        }
+        if !data.socials.is_empty() {
+            self.social_platforms.insert(account_id.clone(), data.socials.keys().cloned().collect());
+        }
+        for (platform, social) in data.socials {
+            self.socials.insert((account_id.clone(), platform), vec![SocialData::from(social).into()]);
+        }
+        self.accounts.insert(account_id);
+    }
+}
@@ -0,0 +1,70 @@
+use near_sdk::json_types::U128;
+use near_sdk::serde_json::Value;
+use near_sdk::{env, ext_contract, near, AccountId, Gas, Promise, PromiseError};
+
+use crate::oracle::REFUND_IF_REVERTED_GAS;
+use crate::{messages, ConfigChange, Contract, ContractError, ContractExt, FunctionError, Provenance, PAUSE_VERIFY_NEAR_SOCIAL};
+
+pub(crate) const DEFAULT_NEAR_SOCIAL_ID: &str = "social.near";
+const PLATFORM: &str = "near_social";
+const SOCIAL_GET_GAS: Gas = Gas::from_tgas(10);
+const ON_PROFILE_READ_GAS: Gas = Gas::from_tgas(30);
+
+#[ext_contract(ext_social_db)]
+#[allow(dead_code)] // only called through the generated `ext_social_db`
+pub trait SocialDb {
+    fn get(&self, keys: Vec<String>) -> Value;
+    fn set(&mut self, data: Value);
+}
+
+// the account proves it controls its NEAR Social profile by writing `get_near_social_claim` to its
+// `profile/sybil_claim` key, which the contract reads itself. no verifier is involved.
+#[near]
+impl Contract {
+    /// Reads the caller's `profile/sybil_claim` from SocialDB and records a `near_social` attestation with the
+    /// account id as the handle if it holds `get_near_social_claim`. The attached deposit is handled like
+    /// `register_social_via_oracle`'s, and so are failures. The callback returns whether it succeeded.
+    #[payable]
+    pub fn verify_near_social(&mut self) -> Promise {
+        self.ensure_not_paused(PAUSE_VERIFY_NEAR_SOCIAL).unwrap_or_else(|err| err.panic());
+        self.ensure_deposit(self.registration_fee_due()).unwrap_or_else(|err| err.panic());
+        let account_id = env::predecessor_account_id();
+        self.check_claimable(&account_id, PLATFORM, account_id.as_str()).unwrap_or_else(|err| err.panic());
+        let deposit = U128(env::attached_deposit().as_yoctonear());
+        ext_social_db::ext(self.near_social_id.clone())
+            .with_static_gas(SOCIAL_GET_GAS)
+            .get(vec![format!("{}/profile/sybil_claim", account_id)])
+            .then(Self::ext(env::current_account_id()).with_static_gas(ON_PROFILE_READ_GAS).on_near_social_profile(account_id.clone(), deposit))
+            .then(Self::ext(env::current_account_id()).with_static_gas(REFUND_IF_REVERTED_GAS).refund_if_reverted(account_id, deposit))
+    }
+
+    #[private]
+    pub fn on_near_social_profile(&mut self, account_id: AccountId, deposit: U128, #[callback_result] profile: Result<Value, PromiseError>) -> bool {
+        let usage_before = env::storage_usage();
+        let claim = messages::near_social_claim(&env::current_account_id(), &account_id);
+        // SocialDB returns the keys asked for as nested objects, `{"<account>": {"profile": {"sybil_claim": ..}}}`
+        let written = profile.ok().and_then(|profile| profile[account_id.as_str()]["profile"]["sybil_claim"].as_str().map(str::to_string));
+        let res = if written.as_deref() == Some(claim.as_str()) {
+            self.check_claimable(&account_id, PLATFORM, account_id.as_str())
+                .and_then(|handle| self.admit_social(&account_id, PLATFORM, handle, &claim, self.platform_config(PLATFORM).validity_ns.0, Provenance::NearSocial))
+        } else {
+            Err(ContractError::ProfileClaimMissing)
+        };
+        self.finish_callback_registration(&account_id, PLATFORM, res, usage_before, deposit.0)
+    }
+
+    /// The text `account_id` writes to its `profile/sybil_claim` for `verify_near_social`.
+    pub fn get_near_social_claim(&self, account_id: AccountId) -> String {
+        messages::near_social_claim(&env::current_account_id(), &account_id)
+    }
+
+    /// Stages the SocialDB contract profiles are read from behind the timelock, returning the pending change id.
+    pub fn set_near_social_contract(&mut self, account_id: AccountId) -> u64 {
+        self.assert_owner();
+        self.schedule_change(ConfigChange::NearSocialContract(account_id))
+    }
+
+    pub fn get_near_social_contract(&self) -> AccountId {
+        self.near_social_id.clone()
+    }
+}
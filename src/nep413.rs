@@ -0,0 +1,31 @@
+use near_sdk::{borsh, env, near, AccountId, PublicKey};
+
+use crate::keys::verify_ed25519;
+use crate::ContractError;
+
+// 2^31 + 413, prepended to the payload so it can never be a valid transaction
+const NEP413_TAG: u32 = 2_147_484_061;
+
+#[near(serializers = [borsh])]
+struct Payload {
+    message: String,
+    nonce: [u8; 32],
+    recipient: String,
+    callback_url: Option<String>,
+}
+
+// the bytes a wallet actually signs for `signMessage`
+pub(crate) fn payload_hash(message: &str, nonce: [u8; 32], recipient: &AccountId) -> Vec<u8> {
+    let payload = Payload { message: message.to_string(), nonce, recipient: recipient.to_string(), callback_url: None };
+    let mut bytes = borsh::to_vec(&NEP413_TAG).unwrap();
+    bytes.extend(borsh::to_vec(&payload).unwrap());
+    env::sha256(&bytes)
+}
+
+/// Verifies a NEP-413 `signMessage` signature over `message` addressed to `recipient`.
+pub(crate) fn verify_signed_message(public_key: &PublicKey, signature: &[u8], message: &str, nonce: [u8; 32], recipient: &AccountId) -> Result<(), ContractError> {
+    verify_ed25519(public_key, &payload_hash(message, nonce, recipient), signature).map_err(|err| match err {
+        ContractError::UnverifiedData => ContractError::InvalidUserSignature,
+        err => err,
+    })
+}
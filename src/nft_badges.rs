@@ -0,0 +1,104 @@
+use near_sdk::json_types::U128;
+use near_sdk::serde_json::json;
+use near_sdk::{env, ext_contract, near, AccountId, Gas, NearToken, PromiseError};
+
+use crate::{events, Contract, ContractExt, FunctionError, MintedNft, NftBadgeConfig};
+
+const NFT_MINT_GAS: Gas = Gas::from_tgas(20);
+const ON_NFT_MINTED_GAS: Gas = Gas::from_tgas(10);
+
+/// NEP-177 token metadata, the fields a badge sets.
+#[near(serializers = [json])]
+pub struct NftMetadata {
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub issued_at: Option<String>, // milliseconds
+}
+
+/// The part of a NEP-171 `Token` the callback reads.
+#[near(serializers = [json])]
+pub struct NftToken {
+    pub token_id: String,
+}
+
+/// The mint method of the badge collection, as in the NEP-171 reference implementation.
+#[ext_contract(ext_nft_collection)]
+#[allow(dead_code)] // only called through the generated `ext_nft_collection`
+pub trait NftCollection {
+    fn nft_mint(&mut self, token_id: String, receiver_id: AccountId, token_metadata: NftMetadata) -> NftToken;
+}
+
+// badges and tiers can additionally be minted as NFTs on a collection contract this contract may mint on, for
+// wallets and marketplaces that show NFTs. the mint deposits are paid from the treasury.
+#[near]
+impl Contract {
+    /// `None` stops minting. NFTs already minted stay with their holders.
+    pub fn set_nft_badge_config(&mut self, config: Option<NftBadgeConfig>) {
+        self.assert_owner();
+        self.nft_badge_config = config;
+    }
+
+    pub fn get_nft_badge_config(&self) -> Option<NftBadgeConfig> {
+        self.nft_badge_config.clone()
+    }
+
+    /// The NFTs minted to the account, in the order they were minted.
+    pub fn get_minted_nfts(&self, account_id: AccountId) -> Vec<MintedNft> {
+        self.minted_nfts.get(&account_id).cloned().unwrap_or_default()
+    }
+
+    #[private]
+    pub fn on_nft_minted(&mut self, account_id: AccountId, source: String, deposit: U128, #[callback_result] token: Result<NftToken, PromiseError>) -> bool {
+        let Ok(token) = token else {
+            // the failed call's deposit came back to this contract
+            self.treasury += deposit.0;
+            env::log_str(&format!("nft mint of {} for {} failed", source, account_id));
+            return false;
+        };
+        let usage_before = env::storage_usage();
+        events::emit("nft_badge_minted", json!({ "account_id": account_id, "source": source, "token_id": token.token_id }));
+        let mut minted = self.minted_nfts.get(&account_id).cloned().unwrap_or_default();
+        minted.push(MintedNft { source, token_id: token.token_id, minted_at: env::block_timestamp().into() });
+        self.minted_nfts.insert(account_id.clone(), minted);
+        self.settle_storage_from(&account_id, usage_before, 0).unwrap_or_else(|err| err.panic());
+        true
+    }
+}
+
+impl Contract {
+    pub(crate) fn mint_badge_nft(&mut self, account_id: &AccountId, rule_id: &str) {
+        if self.nft_badge_config.as_ref().is_some_and(|config| config.badges) {
+            self.mint_nft(account_id, format!("badge:{}", rule_id));
+        }
+    }
+
+    pub(crate) fn mint_tier_nft(&mut self, account_id: &AccountId, tier: &str) {
+        if self.nft_badge_config.as_ref().is_some_and(|config| config.tiers) {
+            self.mint_nft(account_id, format!("tier:{}", tier));
+        }
+    }
+
+    // mints `source` to the account once, skipped if it already holds it or the treasury can't pay the deposit
+    fn mint_nft(&mut self, account_id: &AccountId, source: String) {
+        let config = self.nft_badge_config.clone().unwrap();
+        if self.minted_nfts.get(account_id).is_some_and(|minted| minted.iter().any(|nft| nft.source == source)) {
+            return;
+        }
+        if self.treasury < config.mint_deposit.0 {
+            env::log_str(&format!("treasury can't cover the nft mint of {} for {}", source, account_id));
+            return;
+        }
+        self.treasury -= config.mint_deposit.0;
+        let metadata = NftMetadata {
+            title: Some(source.clone()),
+            description: None,
+            issued_at: Some((env::block_timestamp() / 1_000_000).to_string()),
+        };
+        ext_nft_collection::ext(config.collection)
+            .with_static_gas(NFT_MINT_GAS)
+            .with_attached_deposit(NearToken::from_yoctonear(config.mint_deposit.0))
+            .nft_mint(format!("{}:{}", source, account_id), account_id.clone(), metadata)
+            .then(Self::ext(env::current_account_id()).with_static_gas(ON_NFT_MINTED_GAS).on_nft_minted(account_id.clone(), source, config.mint_deposit))
+            .detach();
+    }
+}
@@ -0,0 +1,72 @@
+use near_sdk::json_types::U128;
+use near_sdk::serde_json::json;
+use near_sdk::{env, ext_contract, near, require, AccountId, Gas, Promise};
+
+use crate::{events, AttestationReceipt, Contract, ContractExt, FunctionError, PAUSE_REQUEST_ATTESTATION, PAUSE_VERIFY_FOR};
+
+// reserved for the receiver's `on_sybil_result`, which also gets a share of the unused gas
+const ON_SYBIL_RESULT_GAS: Gas = Gas::from_tgas(5);
+
+// the name `is_human` answers to in `verify_for`, rule ids and thresholds answer to their own names
+pub(crate) const HUMAN_CHECK: &str = "is_human";
+
+/// What a contract implements to receive `verify_for` results.
+#[ext_contract(ext_sybil_receiver)]
+#[allow(dead_code)] // only called through the generated `ext_sybil_receiver`
+pub trait SybilResultReceiver {
+    fn on_sybil_result(&mut self, account_id: AccountId, check: String, result: bool, msg: String);
+}
+
+// pushes a check's result into a consumer contract, so it can continue its own flow in a callback instead of
+// calling a view and handling the promise itself
+#[near]
+impl Contract {
+    /// Evaluates `check` for the account and calls `on_sybil_result(account_id, check, result, msg)` on
+    /// `receiver_id`. `check` is `"is_human"`, a rule id or a threshold name, looked up in that order, and
+    /// panics if it is none of them. Only the account itself or the receiver may ask, so a receiver can trust
+    /// `msg` came from one of them. Costs `get_verify_fee`, attached or from the caller's subscription balance.
+    #[payable]
+    pub fn verify_for(&mut self, account_id: AccountId, check: String, receiver_id: AccountId, msg: String) -> Promise {
+        self.ensure_not_paused(PAUSE_VERIFY_FOR).unwrap_or_else(|err| err.panic());
+        let caller = env::predecessor_account_id();
+        require!(caller == account_id || caller == receiver_id, "only the account or the receiver can request a result");
+        self.charge_query(self.verify_fee);
+        let result = self.evaluate_check(&account_id, &check).unwrap_or_else(|| env::panic_str(&format!("unknown check {}", check)));
+        ext_sybil_receiver::ext(receiver_id).with_static_gas(ON_SYBIL_RESULT_GAS).on_sybil_result(account_id, check, result, msg)
+    }
+
+    /// Evaluates `check` like `verify_for` and returns the result as a receipt, also emitted as an
+    /// `attestation_receipt` event so the caller has an on-chain billing record of what it relied on. Costs
+    /// `get_attestation_fee`, attached or from the caller's subscription balance.
+    #[payable]
+    pub fn request_attestation(&mut self, account_id: AccountId, check: String) -> AttestationReceipt {
+        self.ensure_not_paused(PAUSE_REQUEST_ATTESTATION).unwrap_or_else(|err| err.panic());
+        self.charge_query(self.attestation_fee);
+        let result = self.evaluate_check(&account_id, &check).unwrap_or_else(|| env::panic_str(&format!("unknown check {}", check)));
+        let receipt = AttestationReceipt {
+            receipt_id: self.receipt_count,
+            consumer: env::predecessor_account_id(),
+            account_id,
+            check,
+            result,
+            fee: U128(self.attestation_fee),
+            block_height: env::block_height().into(),
+            timestamp: env::block_timestamp().into(),
+        };
+        self.receipt_count += 1;
+        events::emit("attestation_receipt", json!(receipt));
+        receipt
+    }
+}
+
+impl Contract {
+    pub(crate) fn evaluate_check(&self, account_id: &AccountId, check: &str) -> Option<bool> {
+        if check == HUMAN_CHECK {
+            return Some(self.is_human(account_id.clone()));
+        }
+        if let Some(rule) = self.rules.get(check) {
+            return Some(self.satisfies(account_id, rule, env::block_timestamp()));
+        }
+        self.thresholds.get(check).map(|&min| self.attested_key_count(account_id, None) >= min)
+    }
+}
@@ -0,0 +1,154 @@
+use near_sdk::serde_json::json;
+use near_sdk::{env, near, require, AccountId, NearToken, Promise};
+
+use crate::handles::normalize_handle;
+use crate::{events, Contract, ContractExt, FunctionError, OptimisticClaim, Provenance, Role, PAUSE_DISPUTE_CLAIM, PAUSE_FINALIZE_CLAIM, PAUSE_SUBMIT_CLAIM};
+
+// on platforms with an `optimistic` config a claim needs no verifier signature up front: it activates once its
+// challenge window passes undisputed. a watcher disputing it matches the bond, a verifier then decides and the
+// winner takes both bonds.
+#[near]
+impl Contract {
+    /// Claims `handle` for the caller, backed by the platform's bond. The attached deposit must cover the bond
+    /// and the registration fee, the rest pays for storing the claim and any excess is refunded. The full proof
+    /// is logged for watchers to check.
+    #[payable]
+    pub fn submit_claim(&mut self, platform: String, handle: String, proof: String) {
+        self.ensure_not_paused(PAUSE_SUBMIT_CLAIM).unwrap_or_else(|err| err.panic());
+        let optimistic = self.platform_config(&platform).optimistic.unwrap_or_else(|| env::panic_str("platform does not accept optimistic claims"));
+        self.ensure_deposit(optimistic.bond.0 + self.registration_fee_due()).unwrap_or_else(|err| err.panic());
+        self.check_input_limits(&platform, &handle, &proof).unwrap_or_else(|err| err.panic());
+        let account_id = env::predecessor_account_id();
+        let handle = self.check_claimable(&account_id, &platform, &handle).unwrap_or_else(|err| err.panic());
+        let handle_key = (platform, handle);
+        require!(!self.optimistic_claims.contains_key(&handle_key), "handle already has a pending claim");
+        let usage_before = env::storage_usage();
+        let challenge_ends_at = env::block_timestamp() + optimistic.challenge_window_ns.0;
+        events::emit("claim_submitted", json!({ "account_id": account_id, "platform": handle_key.0, "handle": handle_key.1, "proof": proof, "challenge_ends_at": challenge_ends_at.to_string() }));
+        let claim = OptimisticClaim { account_id: account_id.clone(), proof, bond: optimistic.bond, challenge_ends_at: challenge_ends_at.into(), challenger: None };
+        self.claims_of.entry(account_id.clone()).or_default().push(handle_key.clone());
+        self.optimistic_claims.insert(handle_key, claim);
+        let fee = self.take_fee(&account_id, self.registration_fee);
+        self.treasury += fee;
+        let deposit = env::attached_deposit().as_yoctonear() - optimistic.bond.0 - fee;
+        self.settle_storage_from(&account_id, usage_before, deposit).unwrap_or_else(|err| err.panic());
+    }
+
+    /// Disputes a claim within its challenge window, attaching the claim's bond. Any excess is refunded.
+    #[payable]
+    pub fn dispute_claim(&mut self, platform: String, handle: String) {
+        self.ensure_not_paused(PAUSE_DISPUTE_CLAIM).unwrap_or_else(|err| err.panic());
+        let handle_key = (platform, normalize_handle(&handle));
+        let mut claim = self.optimistic_claims.get(&handle_key).cloned().unwrap_or_else(|| env::panic_str("no pending claim for this handle"));
+        let challenger = env::predecessor_account_id();
+        require!(claim.account_id != challenger, "cannot dispute your own claim");
+        require!(claim.challenger.is_none(), "claim is already disputed");
+        require!(env::block_timestamp() < claim.challenge_ends_at.0, "challenge window has passed");
+        let attached = env::attached_deposit().as_yoctonear();
+        let bond = claim.bond.0;
+        require!(attached >= bond, format!("attach the claim's bond of {} yoctoNEAR", bond));
+        claim.challenger = Some(challenger.clone());
+        self.optimistic_claims.insert(handle_key.clone(), claim);
+        if attached > bond {
+            Promise::new(challenger.clone()).transfer(NearToken::from_yoctonear(attached - bond)).detach();
+        }
+        events::emit("claim_disputed", json!({ "platform": handle_key.0, "handle": handle_key.1, "challenger": challenger }));
+    }
+
+    /// Decides a disputed claim. An upheld claim becomes an attestation and the claimant takes both bonds,
+    /// otherwise the claim is dropped and the challenger takes them. Returns whether an attestation was stored,
+    /// as `finalize_claim` does.
+    pub fn resolve_claim(&mut self, platform: String, handle: String, claim_valid: bool) -> bool {
+        self.assert_any_role(&[Role::Owner, Role::Verifier]);
+        let handle_key = (platform, normalize_handle(&handle));
+        let claim = self.optimistic_claims.get(&handle_key).cloned().unwrap_or_else(|| env::panic_str("no pending claim for this handle"));
+        let challenger = claim.challenger.clone().unwrap_or_else(|| env::panic_str("claim is not disputed"));
+        events::emit("claim_resolved", json!({ "platform": handle_key.0, "handle": handle_key.1, "claim_valid": claim_valid }));
+        let winner = if claim_valid { claim.account_id.clone() } else { challenger };
+        Promise::new(winner).transfer(NearToken::from_yoctonear(2 * claim.bond.0)).detach();
+        if claim_valid {
+            self.activate_claim(handle_key, claim)
+        } else {
+            self.drop_claim(&handle_key, &claim.account_id);
+            false
+        }
+    }
+
+    /// Turns an undisputed claim whose challenge window has passed into an attestation and returns the bond.
+    /// Anyone can call it. Returns whether the attestation was stored: the handle may have been registered in
+    /// the meantime, in which case the claim is dropped and the failure recorded against the claimant. Storage the
+    /// attestation takes beyond the claim's is paid from the claimant's storage balance.
+    pub fn finalize_claim(&mut self, platform: String, handle: String) -> bool {
+        self.ensure_not_paused(PAUSE_FINALIZE_CLAIM).unwrap_or_else(|err| err.panic());
+        let handle_key = (platform, normalize_handle(&handle));
+        let claim = self.optimistic_claims.get(&handle_key).cloned().unwrap_or_else(|| env::panic_str("no pending claim for this handle"));
+        require!(claim.challenger.is_none(), "claim is disputed and awaits resolution");
+        require!(env::block_timestamp() >= claim.challenge_ends_at.0, "challenge window has not passed");
+        Promise::new(claim.account_id.clone()).transfer(NearToken::from_yoctonear(claim.bond.0)).detach();
+        self.activate_claim(handle_key, claim)
+    }
+
+    pub fn get_claim(&self, platform: String, handle: String) -> Option<OptimisticClaim> {
+        self.optimistic_claims.get(&(platform, normalize_handle(&handle))).cloned()
+    }
+}
+
+impl Contract {
+    // replaces the claim with the attestation it backs, settling the difference in storage with the claimant
+    fn activate_claim(&mut self, (platform, handle): (String, String), claim: OptimisticClaim) -> bool {
+        self.flush_collections();
+        let usage_before = env::storage_usage();
+        self.remove_claim(&(platform.clone(), handle.clone()));
+        let account_id = claim.account_id;
+        let res = self.check_claimable(&account_id, &platform, &handle).and_then(|handle| self.admit_social(&account_id, &platform, handle, &claim.proof, self.platform_config(&platform).validity_ns.0, Provenance::Optimistic));
+        match res {
+            Ok(evicted) => {
+                self.record_issuance(&account_id);
+                self.settle_storage_from(&account_id, usage_before - evicted, 0).unwrap_or_else(|err| err.panic());
+                self.record_registration(&platform);
+                events::emit("claim_finalized", json!({ "account_id": account_id, "platform": platform, "handle": handle }));
+                true
+            }
+            Err(err) => {
+                env::log_str(&format!("claim failed: {}", err));
+                self.flush_collections();
+                self.release_storage(&account_id, usage_before.saturating_sub(env::storage_usage()));
+                self.emit_registration_failed(&account_id, &platform, &err);
+                self.record_failed_attempt(&account_id, &platform, &err, 0);
+                false
+            }
+        }
+    }
+
+    fn drop_claim(&mut self, handle_key: &(String, String), account_id: &AccountId) {
+        self.flush_collections();
+        let usage_before = env::storage_usage();
+        self.remove_claim(handle_key);
+        self.flush_collections();
+        self.release_storage(account_id, usage_before.saturating_sub(env::storage_usage()));
+    }
+
+    fn remove_claim(&mut self, handle_key: &(String, String)) -> Option<OptimisticClaim> {
+        let claim = self.optimistic_claims.remove(handle_key)?;
+        let mut claims = self.claims_of.remove(&claim.account_id).unwrap_or_default();
+        claims.retain(|key| key != handle_key);
+        if !claims.is_empty() {
+            self.claims_of.insert(claim.account_id.clone(), claims);
+        }
+        Some(claim)
+    }
+
+    // drops the account's pending claims when it deletes its data. an undisputed bond goes back to the account, a
+    // disputed claim is conceded and the challenger takes both bonds.
+    pub(crate) fn withdraw_claims(&mut self, account_id: &AccountId) {
+        for handle_key in self.claims_of.get(account_id).cloned().unwrap_or_default() {
+            let Some(claim) = self.remove_claim(&handle_key) else { continue };
+            let (receiver, amount) = match claim.challenger {
+                Some(challenger) => (challenger, 2 * claim.bond.0),
+                None => (claim.account_id, claim.bond.0),
+            };
+            Promise::new(receiver).transfer(NearToken::from_yoctonear(amount)).detach();
+            events::emit("claim_withdrawn", json!({ "platform": handle_key.0, "handle": handle_key.1 }));
+        }
+    }
+}
@@ -0,0 +1,118 @@
+use near_sdk::json_types::U128;
+use near_sdk::{env, ext_contract, near, AccountId, Gas, NearToken, Promise, PromiseError};
+
+use crate::handles::normalize_handle;
+use crate::{Contract, ContractError, ContractExt, FunctionError, Provenance, PAUSE_REGISTER_SOCIAL_ORACLE};
+
+const ORACLE_GAS: Gas = Gas::from_tgas(10);
+const ON_ORACLE_CONFIRMED_GAS: Gas = Gas::from_tgas(30);
+pub(crate) const REFUND_IF_REVERTED_GAS: Gas = Gas::from_tgas(5);
+
+/// What a platform's oracle contract implements, e.g. a bridge reading Lens handle ownership from Polygon.
+#[ext_contract(ext_handle_oracle)]
+#[allow(dead_code)] // only called through the generated `ext_handle_oracle`
+pub trait HandleOracle {
+    /// Whether `handle` on `platform` currently belongs to `address`.
+    fn confirm_handle(&self, platform: String, handle: String, address: String) -> bool;
+}
+
+#[near]
+impl Contract {
+    /// Registers the caller's `handle` once the platform's oracle contract confirms it belongs to `address`,
+    /// one of the caller's `link_evm` addresses, instead of on a verifier signature. The address is stored as
+    /// the proof. The attached deposit must cover `get_min_deposit` plus the registration fee; what's left after
+    /// the fee is added to the caller's storage balance and pays for the attestation, or all of it is refunded
+    /// if the registration fails. The callback can't revert the call, so a failure is emitted as
+    /// `registration_failed` and kept in `get_failed_attempts` right away, and the callback returns whether it
+    /// succeeded.
+    #[payable]
+    pub fn register_social_via_oracle(&mut self, platform: String, handle: String, address: String) -> Promise {
+        self.ensure_not_paused(PAUSE_REGISTER_SOCIAL_ORACLE).unwrap_or_else(|err| err.panic());
+        self.ensure_deposit(self.registration_fee_due()).unwrap_or_else(|err| err.panic());
+        self.check_input_limits(&platform, &handle, &address).unwrap_or_else(|err| err.panic());
+        let oracle_id = self.platforms.get(&platform).and_then(|platform| platform.oracle_id.clone()).unwrap_or_else(|| env::panic_str("platform has no oracle"));
+        let account_id = env::predecessor_account_id();
+        let address = address.to_lowercase();
+        // checked again in the callback, this only saves a doomed oracle call
+        self.ensure_address_linked(&account_id, &address).unwrap_or_else(|err| err.panic());
+        self.check_claimable(&account_id, &platform, &handle).unwrap_or_else(|err| err.panic());
+        let deposit = U128(env::attached_deposit().as_yoctonear());
+        ext_handle_oracle::ext(oracle_id.clone())
+            .with_static_gas(ORACLE_GAS)
+            .confirm_handle(platform.clone(), handle.clone(), address.clone())
+            .then(Self::ext(env::current_account_id()).with_static_gas(ON_ORACLE_CONFIRMED_GAS).on_oracle_confirmed(account_id.clone(), platform, handle, address, oracle_id, deposit))
+            .then(Self::ext(env::current_account_id()).with_static_gas(REFUND_IF_REVERTED_GAS).refund_if_reverted(account_id, deposit))
+    }
+
+    #[private]
+    pub fn on_oracle_confirmed(&mut self, account_id: AccountId, platform: String, handle: String, address: String, oracle_id: AccountId, deposit: U128, #[callback_result] confirmed: Result<bool, PromiseError>) -> bool {
+        let usage_before = env::storage_usage();
+        let res = match confirmed {
+            Ok(true) => self
+                .ensure_address_linked(&account_id, &address)
+                .and_then(|_| self.check_claimable(&account_id, &platform, &handle))
+                .and_then(|handle| self.admit_social(&account_id, &platform, handle, &address, self.platform_config(&platform).validity_ns.0, Provenance::Oracle(oracle_id))),
+            _ => Err(ContractError::OracleRejected),
+        };
+        self.finish_callback_registration(&account_id, &platform, res, usage_before, deposit.0)
+    }
+
+    /// Refunds `deposit` to `account_id` when the registration callback before it reverted, which it only does
+    /// when the deposit and the account's storage balance don't pay for the attestation. Any other failure is
+    /// refunded by the callback itself. Returns whether the registration succeeded.
+    #[private]
+    pub fn refund_if_reverted(&mut self, account_id: AccountId, deposit: U128, #[callback_result] registered: Result<bool, PromiseError>) -> bool {
+        registered.unwrap_or_else(|_| {
+            env::log_str(&format!("registration for {} reverted", account_id));
+            if deposit.0 > 0 {
+                Promise::new(account_id).transfer(NearToken::from_yoctonear(deposit.0)).detach();
+            }
+            false
+        })
+    }
+
+    /// What confirmed the account's attestation of `handle`, `None` if it holds none.
+    pub fn get_provenance(&self, account_id: AccountId, platform: String, handle: String) -> Option<Provenance> {
+        let handle = normalize_handle(&handle);
+        self.socials_on(&account_id, &platform).into_iter().find(|social| social.handle == handle).map(|social| social.provenance)
+    }
+}
+
+impl Contract {
+    // `finish_registration` for a callback, which has no deposit attached: `deposit` is what the call that
+    // dispatched it had attached, added to the account's storage balance after the fee or refunded on failure
+    pub(crate) fn finish_callback_registration(&mut self, account_id: &AccountId, platform: &str, res: Result<u64, ContractError>, usage_before: u64, deposit: u128) -> bool {
+        match res {
+            Ok(evicted) => {
+                // the fee may have changed since the call, it never takes more than was attached
+                let fee = self.take_fee(account_id, self.registration_fee).min(deposit);
+                self.treasury += fee;
+                let mut balance = self.storage_balances.get(account_id).cloned().unwrap_or_default();
+                balance.total += deposit - fee;
+                self.storage_balances.insert(account_id.clone(), balance);
+                self.record_issuance(account_id);
+                // reverts the callback, `refund_if_reverted` then returns the deposit
+                self.settle_storage_from(account_id, usage_before - evicted, 0).unwrap_or_else(|err| err.panic());
+                self.record_registration(platform);
+                true
+            }
+            Err(err) => {
+                env::log_str(&format!("registration failed: {}", err));
+                self.emit_registration_failed(account_id, platform, &err);
+                let deposit = self.record_failed_attempt(account_id, platform, &err, deposit);
+                if deposit > 0 {
+                    Promise::new(account_id.clone()).transfer(NearToken::from_yoctonear(deposit)).detach();
+                }
+                false
+            }
+        }
+    }
+
+    fn ensure_address_linked(&self, account_id: &AccountId, address: &String) -> Result<(), ContractError> {
+        if self.evm_addresses.get(account_id).is_some_and(|addresses| addresses.contains(address)) {
+            Ok(())
+        } else {
+            Err(ContractError::AddressNotLinked)
+        }
+    }
+}
@@ -0,0 +1,42 @@
+use near_sdk::{env, near, require, AccountId};
+
+use crate::{Contract, ContractExt};
+
+// admin methods are gated on `owner_id` rather than `#[private]`, so control can move to a DAO or another
+// operator without redeploying. a transfer only completes once the new owner accepts it.
+#[near]
+impl Contract {
+    /// Replaces any transfer already proposed.
+    pub fn propose_owner(&mut self, new_owner: AccountId) {
+        self.assert_owner();
+        env::log_str(&format!("proposed ownership transfer to {}", new_owner));
+        self.pending_owner_id = Some(new_owner);
+    }
+
+    pub fn accept_ownership(&mut self) {
+        let caller = env::predecessor_account_id();
+        require!(self.pending_owner_id.as_ref() == Some(&caller), "only the proposed owner can accept ownership");
+        env::log_str(&format!("ownership transferred from {} to {}", self.owner_id, caller));
+        self.owner_id = caller;
+        self.pending_owner_id = None;
+    }
+
+    pub fn cancel_ownership_transfer(&mut self) {
+        self.assert_owner();
+        self.pending_owner_id = None;
+    }
+
+    pub fn get_owner(&self) -> AccountId {
+        self.owner_id.clone()
+    }
+
+    pub fn get_pending_owner(&self) -> Option<AccountId> {
+        self.pending_owner_id.clone()
+    }
+}
+
+impl Contract {
+    pub(crate) fn assert_owner(&self) {
+        require!(env::predecessor_account_id() == self.owner_id, "only the owner can call this method");
+    }
+}
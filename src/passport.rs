@@ -0,0 +1,51 @@
+use near_sdk::json_types::U64;
+use near_sdk::{env, near, AccountId};
+
+use crate::{messages, Contract, ContractError, ContractExt, PassportScore, RequestBound, PAUSE_IMPORT_PASSPORT_SCORE};
+
+// Gitcoin Passport scores go stale as the stamps behind them expire, so an import is only counted for this long
+pub(crate) const PASSPORT_VALIDITY_NS: u64 = 90 * 24 * 60 * 60 * 1_000_000_000;
+
+// the verifier backend attests a user's off-chain Gitcoin Passport score, which then counts toward `score`
+#[near]
+impl Contract {
+    /// Stores the signer's Passport `score` in hundredths, e.g. 2050 for 20.5. The verifier signs
+    /// `"passport_score,<account_id>,<score>,<bound>"`. Replaces any earlier import, which also restarts its
+    /// `PASSPORT_VALIDITY_NS`.
+    #[payable]
+    #[handle_result]
+    pub fn import_passport_score(&mut self, score: u32, signature: Vec<u8>, max_block_height: Option<u64>, valid_until_timestamp_ns: Option<U64>) -> Result<(), ContractError> {
+        self.ensure_not_paused(PAUSE_IMPORT_PASSPORT_SCORE)?;
+        self.ensure_deposit(0)?;
+        let bound = RequestBound::new(max_block_height, valid_until_timestamp_ns)?;
+        bound.ensure_valid()?;
+        let account_id = self.acting_account()?;
+        self.ensure_not_banned(&account_id)?;
+        self.ensure_within_rate_limit(&account_id)?;
+        let message = messages::passport_score_message(&account_id, score, bound);
+        self.verify_signature(&message, &signature)?;
+        let usage_before = env::storage_usage();
+        let now = env::block_timestamp();
+        self.passport_scores.insert(account_id.clone(), PassportScore { score, issued_at: now.into(), expires_at: (now + PASSPORT_VALIDITY_NS).into() });
+        self.accounts.insert(account_id.clone());
+        self.record_issuance(&account_id);
+        self.settle_storage(&account_id, usage_before)?;
+        Ok(())
+    }
+
+    /// The account's last imported Passport score, expired or not.
+    pub fn get_passport_score(&self, account_id: AccountId) -> Option<PassportScore> {
+        self.passport_scores.get(&account_id).cloned()
+    }
+}
+
+impl Contract {
+    // the highest unexpired Passport score across the account's identity, in hundredths
+    pub(crate) fn attested_passport_score(&self, account_id: &AccountId, now: u64) -> Option<u32> {
+        self.resolved_accounts(account_id).iter()
+            .filter_map(|account_id| self.passport_scores.get(account_id))
+            .filter(|passport| passport.expires_at.0 > now)
+            .map(|passport| passport.score)
+            .max()
+    }
+}
@@ -0,0 +1,57 @@
+use near_sdk::{env, near};
+
+use crate::circuit_breaker::BREAKER_METHODS;
+use crate::{Contract, ContractError, ContractExt};
+
+#[near]
+impl Contract {
+    /// Blocks every entrypoint with a `PAUSE_*` flag until `unpause`: registrations and failed-attempt reports,
+    /// renewals, attribute updates, stamps, address and account links, merges, transfers, unregistering, claims
+    /// and disputes, verifier bonds and paid queries. Views, storage deposits and withdrawals, taking back vouches and bonds, and the admin,
+    /// operator and key recovery methods keep working.
+    pub fn pause(&mut self) {
+        self.assert_owner();
+        self.paused = true;
+        env::log_str("paused");
+    }
+
+    pub fn unpause(&mut self) {
+        self.assert_owner();
+        self.paused = false;
+        env::log_str("unpaused");
+    }
+
+    /// Also true while `migrate_v1_batch` has version 1 data left, whatever `pause` and `unpause` set.
+    pub fn is_paused(&self) -> bool {
+        self.paused || self.legacy_v1.is_some()
+    }
+
+    /// Pauses just the entrypoints whose `PAUSE_*` flags are set in `methods`, replacing the previous set.
+    /// Independent of the global `pause`.
+    pub fn set_paused_methods(&mut self, methods: u32) {
+        self.assert_owner();
+        self.paused_methods = methods;
+        env::log_str(&format!("paused methods set to {:#x}", methods));
+    }
+
+    pub fn get_paused_methods(&self) -> u32 {
+        self.paused_methods
+    }
+}
+
+impl Contract {
+    // `method` is the entrypoint's `PAUSE_*` flag
+    pub(crate) fn ensure_not_paused(&self, method: u32) -> Result<(), ContractError> {
+        // a migration in progress pauses too, nothing may be registered against a half moved handle index
+        if self.is_paused() {
+            return Err(ContractError::Paused);
+        }
+        if self.paused_methods & method != 0 {
+            return Err(ContractError::MethodPaused);
+        }
+        if self.breaker.tripped && method & BREAKER_METHODS != 0 {
+            return Err(ContractError::CircuitBreakerTripped);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,207 @@
+use near_sdk::{env, near, require, AccountId, PublicKey};
+
+use crate::handles::normalize_handle;
+use crate::keys::VerifyingKey;
+use crate::private_handles::{is_private_handle, is_valid_private_handle};
+use crate::{ConfigChange, Contract, ContractError, ContractExt, FunctionError, HandleRules, Platform, PlatformConfig, SocialData, SocialStatus};
+
+const MAX_DISPLAY_NAME_LEN: usize = 64;
+
+#[near(serializers = [borsh])]
+#[derive(Clone)]
+pub struct HolderSlot {
+    account_id: AccountId,
+    expiry_date: u64,
+}
+
+impl HandleRules {
+    fn matches(&self, handle: &str) -> bool {
+        let len = handle.chars().count() as u32;
+        len >= self.min_len
+            && self.max_len.is_none_or(|max_len| len <= max_len)
+            && self.required_suffix.as_ref().is_none_or(|suffix| handle.ends_with(suffix.as_str()))
+            && self.charset.as_ref().is_none_or(|charset| handle.chars().all(|c| charset.contains(c)))
+    }
+}
+
+#[near]
+impl Contract {
+    /// Adds a disabled platform with the default config, accepting the contract's verifiers' signatures. It takes
+    /// registrations once `allow_platform` enables it, so a new platform goes live through the timelock.
+    pub fn add_platform(&mut self, id: String, display_name: String) {
+        self.assert_owner();
+        require!(id.len() <= self.input_limits.max_platform_len as usize, "platform identifier is too long");
+        require!(display_name.len() <= MAX_DISPLAY_NAME_LEN, "display name is too long");
+        require!(!self.platforms.contains_key(&id), "platform already exists");
+        env::log_str(&format!("added platform {}", id));
+        self.platforms.insert(id.clone(), Platform { id, display_name, config: PlatformConfig::default(), verifier_key: None, enabled: false, oracle_id: None });
+    }
+
+    pub fn set_platform_display_name(&mut self, platform: String, display_name: String) {
+        self.assert_owner();
+        require!(display_name.len() <= MAX_DISPLAY_NAME_LEN, "display name is too long");
+        self.platform_mut(&platform).display_name = display_name;
+    }
+
+    /// Stages the platform's own verifier key, or with `None` going back to the contract's verifiers, behind
+    /// the timelock. Returns the pending change id.
+    pub fn set_platform_verifier(&mut self, platform: String, key: Option<PublicKey>) -> u64 {
+        self.assert_owner();
+        require!(self.platforms.contains_key(&platform), "unknown platform");
+        if let Some(Err(err)) = key.clone().map(|key| self.signing_key(key)) {
+            err.panic();
+        }
+        self.schedule_change(ConfigChange::PlatformVerifier { platform, key })
+    }
+
+    /// Stages the contract `register_social_via_oracle` asks to confirm the platform's handles, or with `None`
+    /// turns that off, behind the timelock. Returns the pending change id.
+    pub fn set_platform_oracle(&mut self, platform: String, oracle_id: Option<AccountId>) -> u64 {
+        self.assert_owner();
+        require!(self.platforms.contains_key(&platform), "unknown platform");
+        self.schedule_change(ConfigChange::PlatformOracle { platform, oracle_id })
+    }
+
+    pub fn get_platform(&self, platform: String) -> Option<Platform> {
+        self.platforms.get(&platform).cloned()
+    }
+
+    pub fn get_platforms(&self) -> Vec<Platform> {
+        self.platforms.values().cloned().collect()
+    }
+
+    /// Stages the change behind the timelock, returning the pending change id.
+    pub fn set_platform_config(&mut self, platform: String, config: PlatformConfig) -> u64 {
+        self.assert_owner();
+        require!(self.platforms.contains_key(&platform), "unknown platform");
+        require!(config.pending_weight_pct <= 100, "pending_weight_pct must be at most 100");
+        require!(config.validity_ns.0 > 0, "validity_ns must be positive");
+        require!(config.max_validity_ns.is_none_or(|max| max >= config.validity_ns), "max_validity_ns must be at least validity_ns");
+        if let Some(rules) = &config.handle_rules {
+            require!(rules.max_len.is_none_or(|max_len| rules.min_len <= max_len), "min_len must not exceed max_len");
+            // rules apply to normalized handles, which nothing else could match
+            require!(rules.required_suffix.as_ref().is_none_or(|suffix| &normalize_handle(suffix) == suffix), "required_suffix must be normalized");
+        }
+        if let Some(optimistic) = &config.optimistic {
+            require!(optimistic.bond.0 > 0, "bond must be positive");
+            require!(optimistic.challenge_window_ns.0 > 0, "challenge_window_ns must be positive");
+        }
+        self.schedule_change(ConfigChange::PlatformConfig { platform, config })
+    }
+
+    pub fn get_platform_config(&self, platform: String) -> PlatformConfig {
+        self.platform_config(&platform)
+    }
+
+    /// Free slots left on a capped platform, `None` if the platform has no holder cap.
+    pub fn remaining_slots(&self, platform: String) -> Option<u32> {
+        let max_holders = self.platform_config(&platform).max_holders?;
+        let now = env::block_timestamp();
+        let held = self.holders.get(&platform).map_or(0, |slots| slots.iter().filter(|slot| slot.expiry_date > now).count()) as u32;
+        Some(max_holders.saturating_sub(held))
+    }
+
+    /// Weight in percent the account's attestation for `platform` currently carries: 100 while valid, the
+    /// platform's `pending_weight_pct` while pending renewal, 0 otherwise.
+    pub fn social_weight(&self, account_id: AccountId, platform: String) -> u8 {
+        self.attested_social(&account_id, &platform).map_or(0, |social| self.weight_of(&platform, &social, env::block_timestamp()))
+    }
+}
+
+impl Contract {
+    pub(crate) fn platform_config(&self, platform: &str) -> PlatformConfig {
+        self.platforms.get(platform).map(|platform| platform.config.clone()).unwrap_or_default()
+    }
+
+    pub(crate) fn apply_platform_verifier(&mut self, platform: &str, key: Option<PublicKey>) {
+        if let Some(Err(err)) = key.clone().map(|key| self.signing_key(key)) {
+            err.panic();
+        }
+        self.platform_mut(platform).verifier_key = key;
+    }
+
+    pub(crate) fn platform_mut(&mut self, platform: &str) -> &mut Platform {
+        self.platforms.get_mut(platform).unwrap_or_else(|| env::panic_str("unknown platform"))
+    }
+
+    pub(crate) fn platform_enabled(&self, platform: &str) -> bool {
+        self.platforms.get(platform).is_some_and(|platform| platform.enabled)
+    }
+
+    // verifies an attestation for `platform`, against its own verifier key if it has one
+    pub(crate) fn verify_platform_signature(&self, platform: &str, message: &str, signature: &[u8]) -> Result<PublicKey, ContractError> {
+        match self.platforms.get(platform).and_then(|platform| platform.verifier_key.clone()) {
+            Some(key) if !self.is_bonded(&key) => Err(ContractError::UnverifiedData),
+            Some(key) => VerifyingKey::new(key.clone())?.verify(message.as_bytes(), signature).map(|()| key),
+            None => self.verify_signature(message, signature),
+        }
+    }
+
+    // rejects a normalized handle not matching the platform's `handle_rules`
+    pub(crate) fn check_handle_format(&self, platform: &str, handle: &str) -> Result<(), ContractError> {
+        if is_private_handle(handle) {
+            return if is_valid_private_handle(handle) { Ok(()) } else { Err(ContractError::MalformedHandle) };
+        }
+        match self.platform_config(platform).handle_rules {
+            Some(rules) if !rules.matches(handle) => Err(ContractError::MalformedHandle),
+            _ => Ok(()),
+        }
+    }
+
+    // how long an attestation on `platform` is valid, `requested` by the verifier or the platform's `validity_ns`
+    pub(crate) fn attestation_validity(&self, platform: &str, requested: Option<u64>) -> Result<u64, ContractError> {
+        let config = self.platform_config(platform);
+        let max = config.max_validity_ns.unwrap_or(config.validity_ns).0;
+        match requested {
+            None => Ok(config.validity_ns.0),
+            Some(validity_ns) if validity_ns > 0 && validity_ns <= max => Ok(validity_ns),
+            Some(_) => Err(ContractError::InvalidValidity { max }),
+        }
+    }
+
+    // takes (or renews) `account_id`'s slot on a capped platform, failing once all slots are held
+    pub(crate) fn claim_holder_slot(&mut self, platform: &str, account_id: &AccountId, expiry_date: u64) -> Result<(), ContractError> {
+        let max_holders = match self.platform_config(platform).max_holders {
+            Some(max_holders) => max_holders,
+            None => return Ok(()),
+        };
+        let now = env::block_timestamp();
+        let platform = platform.to_string();
+        let mut slots = self.holders.get(&platform).cloned().unwrap_or_default();
+        slots.retain(|slot| slot.expiry_date > now);
+        // on multi-handle platforms the slot lasts as long as the account's longest held attestation
+        let held = slots.iter().position(|slot| &slot.account_id == account_id).map(|i| slots.remove(i).expiry_date);
+        if held.is_none() && slots.len() as u32 >= max_holders {
+            return Err(ContractError::PlatformAtCapacity);
+        }
+        let expiry_date = held.map_or(expiry_date, |held| held.max(expiry_date));
+        slots.push(HolderSlot { account_id: account_id.clone(), expiry_date });
+        self.holders.insert(platform, slots);
+        Ok(())
+    }
+
+    pub(crate) fn release_holder_slot(&mut self, platform: &str, account_id: &AccountId) {
+        let platform = platform.to_string();
+        if let Some(slots) = self.holders.get_mut(&platform) {
+            slots.retain(|slot| &slot.account_id != account_id);
+        }
+    }
+
+    pub(crate) fn weight_of(&self, platform: &str, social: &SocialData, now: u64) -> u8 {
+        match self.status_of(platform, social, now) {
+            SocialStatus::Active => 100,
+            SocialStatus::InGrace => self.platform_config(platform).pending_weight_pct,
+            _ => 0,
+        }
+    }
+
+    pub(crate) fn status_of(&self, platform: &str, social: &SocialData, now: u64) -> SocialStatus {
+        if social.expiry_date > now {
+            SocialStatus::Active
+        } else if now < social.expiry_date.saturating_add(self.platform_config(platform).grace_period_ns.0) {
+            SocialStatus::InGrace
+        } else {
+            SocialStatus::Expired
+        }
+    }
+}
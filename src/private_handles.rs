@@ -0,0 +1,58 @@
+use near_sdk::json_types::U64;
+use near_sdk::{env, near, AccountId};
+
+use crate::handles::normalize_handle;
+use crate::{Contract, ContractError, ContractExt, RequestBound, PAUSE_REGISTER_SOCIAL};
+
+// private handles are stored as this prefix followed by the hex sha256 of platform, handle and salt, so they go
+// through the same uniqueness, cooldown and history bookkeeping as plaintext ones
+pub(crate) const PRIVATE_HANDLE_PREFIX: &str = "sha256:";
+
+// hex sha256 of `platform || handle || salt`, the handle normalized first
+pub(crate) fn private_handle_hash(platform: &str, handle: &str, salt: &str) -> String {
+    let preimage = format!("{}{}{}", platform, normalize_handle(handle), salt);
+    env::sha256_array(preimage.as_bytes()).iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+pub(crate) fn is_private_handle(handle: &str) -> bool {
+    handle.starts_with(PRIVATE_HANDLE_PREFIX)
+}
+
+// a private handle must be the prefix and a lowercase hex sha256, the platform's handle rules don't apply to it
+pub(crate) fn is_valid_private_handle(handle: &str) -> bool {
+    handle.strip_prefix(PRIVATE_HANDLE_PREFIX).is_some_and(|hash| hash.len() == 64 && hash.chars().all(|c| c.is_ascii_hexdigit()))
+}
+
+#[near]
+impl Contract {
+    /// `register_social` storing only `handle_hash`, the hex `sha256(platform || handle || salt)` of the
+    /// normalized handle (see `get_private_handle_hash`), so the account isn't publicly tied to the handle. The
+    /// verifier signs the stored form, `"sha256:<handle_hash>"`, as the handle, and `proof` shouldn't reveal the
+    /// handle either. Uniqueness only holds per hash, keeping a handle to one account across plaintext and
+    /// salted registrations is up to the verifier backend, which sees the handle.
+    #[payable]
+    #[handle_result]
+    pub fn register_social_private(&mut self, platform: String, signature: Vec<u8>, handle_hash: String, proof: String, max_block_height: Option<u64>, valid_until_timestamp_ns: Option<U64>, validity_ns: Option<U64>) -> Result<(), ContractError> {
+        self.ensure_not_paused(PAUSE_REGISTER_SOCIAL)?;
+        self.ensure_deposit(self.registration_fee_due())?;
+        self.check_input_limits(&platform, &handle_hash, &proof)?;
+        let account_id = self.acting_account()?;
+        let handle = format!("{}{}", PRIVATE_HANDLE_PREFIX, handle_hash);
+        let usage_before = env::storage_usage();
+        let res = RequestBound::new(max_block_height, valid_until_timestamp_ns)
+            .and_then(|bound| self.internal_register_social(&account_id, platform.clone(), signature, handle, proof, validity_ns.map(|v| v.0), bound, None));
+        self.finish_registration(&account_id, &platform, res, usage_before)
+    }
+
+    /// The `handle_hash` `register_social_private` takes for `handle` and `salt`.
+    pub fn get_private_handle_hash(&self, platform: String, handle: String, salt: String) -> String {
+        private_handle_hash(&platform, &handle, &salt)
+    }
+
+    /// Whether the account holds an unexpired private attestation of `handle` salted with `salt`, for anyone
+    /// the account shared the salt with.
+    pub fn confirm_private_handle(&self, account_id: AccountId, platform: String, handle: String, salt: String) -> bool {
+        let hash = private_handle_hash(&platform, &handle, &salt);
+        self.handle_is_live(&(platform, format!("{}{}", PRIVATE_HANDLE_PREFIX, hash)), &account_id, env::block_timestamp())
+    }
+}
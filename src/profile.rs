@@ -0,0 +1,67 @@
+use std::collections::BTreeMap;
+
+use near_sdk::serde_json::json;
+use near_sdk::{env, near, AccountId};
+
+use crate::limits::check_len;
+use crate::{events, Contract, ContractError, ContractExt, UserData, PAUSE_SET_PROFILE_METADATA};
+
+// bounds on the self-set metadata, enough for a display name, an avatar URL and a few links
+const MAX_METADATA_ENTRIES: usize = 8;
+const MAX_METADATA_KEY_LEN: u32 = 32;
+const MAX_METADATA_VALUE_LEN: u32 = 256;
+
+// metadata is what the account says about itself, nothing here is attested. dApps rendering a verified profile
+// can show it next to the socials without reading another contract.
+#[near]
+impl Contract {
+    /// Replaces the caller's profile metadata, e.g. `{"name": "Alice", "avatar": "https://..."}`. An empty map
+    /// removes it. The storage it takes is paid from the caller's storage balance and the attached deposit, any
+    /// excess is refunded.
+    #[payable]
+    #[handle_result]
+    pub fn set_profile_metadata(&mut self, metadata: BTreeMap<String, String>) -> Result<(), ContractError> {
+        self.ensure_not_paused(PAUSE_SET_PROFILE_METADATA)?;
+        let account_id = env::predecessor_account_id();
+        self.ensure_not_banned(&account_id)?;
+        if metadata.len() > MAX_METADATA_ENTRIES {
+            return Err(ContractError::TooManyMetadataEntries { max: MAX_METADATA_ENTRIES as u32 });
+        }
+        for (key, value) in &metadata {
+            check_len("metadata key", key, MAX_METADATA_KEY_LEN)?;
+            check_len("metadata value", value, MAX_METADATA_VALUE_LEN)?;
+        }
+        self.flush_collections();
+        let usage_before = env::storage_usage();
+        events::emit("profile_metadata_set", json!({ "account_id": account_id, "keys": metadata.keys().collect::<Vec<_>>() }));
+        if metadata.is_empty() {
+            self.profile_metadata.remove(&account_id);
+        } else {
+            self.profile_metadata.insert(account_id.clone(), metadata);
+            self.accounts.insert(account_id.clone());
+        }
+        self.settle_storage(&account_id, usage_before)?;
+        Ok(())
+    }
+
+    pub fn get_profile_metadata(&self, account_id: AccountId) -> BTreeMap<String, String> {
+        self.profile_metadata.get(&account_id).cloned().unwrap_or_default()
+    }
+
+    /// Everything stored for the account, `None` if nothing is. Attributes are shown as stored, also for banned
+    /// accounts, use the checks for what counts.
+    pub fn get_user_data(&self, account_id: AccountId) -> Option<UserData> {
+        if !self.accounts.contains(&account_id) {
+            return None;
+        }
+        let socials = self.social_platforms.get(&account_id).into_iter().flatten()
+            .map(|platform| (platform.clone(), self.handles_on(&account_id, platform)))
+            .collect();
+        Some(UserData {
+            stamps: self.get_stamps(account_id.clone()),
+            socials,
+            nfts: self.get_minted_nfts(account_id.clone()),
+            metadata: self.get_profile_metadata(account_id),
+        })
+    }
+}
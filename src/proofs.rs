@@ -0,0 +1,31 @@
+use near_sdk::{env, near, AccountId};
+
+use crate::{Contract, ContractExt};
+
+// only the digest of a proof is kept on-chain and logged on registration, the proof itself stays with the account
+pub(crate) fn proof_digest(proof: &str) -> [u8; 32] {
+    env::sha256_array(proof.as_bytes())
+}
+
+pub(crate) fn digest_hex(digest: &[u8; 32]) -> String {
+    digest.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+// short tag describing the proof format, so auditors know how to check the proof they retrieved
+pub(crate) fn proof_type(proof: &str) -> String {
+    match proof.strip_prefix("0x") {
+        // 65 byte secp256k1 signature, as produced by lens and farcaster wallets
+        Some(hex) if hex.len() == 130 && hex.chars().all(|c| c.is_ascii_hexdigit()) => "eth_sig",
+        Some(hex) if hex.chars().all(|c| c.is_ascii_hexdigit()) => "hex",
+        _ => "text",
+    }
+    .to_string()
+}
+
+#[near]
+impl Contract {
+    /// Whether `proof` is the proof stored for one of the account's `platform` attestations.
+    pub fn verify_proof_digest(&self, account_id: AccountId, platform: String, proof: String) -> bool {
+        self.socials_on(&account_id, &platform).iter().any(|social| social.proof_digest == proof_digest(&proof))
+    }
+}
@@ -0,0 +1,42 @@
+use near_sdk::env::block_timestamp;
+use near_sdk::json_types::U128;
+use near_sdk::{near, AccountId};
+
+use crate::score::MAX_SCORE;
+use crate::{Contract, ContractExt, QfConfig, QfCurve};
+
+// matching weights for quadratic funding rounds, one call per contributor
+#[near]
+impl Contract {
+    /// The account's matching weight: 0 below the config's `min_score`, otherwise its score mapped onto
+    /// `0..=max_weight` along the config's curve.
+    pub fn qf_weight(&self, account_id: AccountId) -> U128 {
+        let score = self.compute_score(&account_id, block_timestamp());
+        weight_for(&self.qf_config, score).into()
+    }
+
+    pub fn set_qf_config(&mut self, config: QfConfig) {
+        self.assert_owner();
+        self.qf_config = config;
+    }
+
+    pub fn get_qf_config(&self) -> QfConfig {
+        self.qf_config.clone()
+    }
+}
+
+fn weight_for(config: &QfConfig, score: u32) -> u128 {
+    if score < config.min_score {
+        return 0;
+    }
+    let score = score.min(MAX_SCORE);
+    // the fraction of `max_weight`, in percent
+    let pct = match config.curve {
+        QfCurve::Linear => score,
+        QfCurve::Sqrt => (score * MAX_SCORE).isqrt(),
+    };
+    // multiplied first so a small `max_weight` doesn't truncate to zero, a weight too large to multiply loses
+    // nothing significant to dividing first
+    let (max_weight, pct) = (config.max_weight.0, u128::from(pct));
+    max_weight.checked_mul(pct).map_or(max_weight / u128::from(MAX_SCORE) * pct, |weight| weight / u128::from(MAX_SCORE))
+}
@@ -0,0 +1,50 @@
+use near_sdk::{env, near, AccountId};
+
+use crate::{Contract, ContractError, ContractExt};
+
+#[near(serializers = [borsh])]
+#[derive(Clone)]
+pub struct EpochWrites {
+    epoch: u64, // epoch height `count` belongs to
+    count: u32,
+}
+
+// bounds how many attestations a single account can get accepted per epoch, so a leaked verifier key can't
+// be used to mass produce them for one account before anyone reacts. only tracked while a limit is set.
+#[near]
+impl Contract {
+    /// 0 removes the limit.
+    pub fn set_rate_limit(&mut self, max_writes_per_epoch: u32) {
+        self.assert_owner();
+        env::log_str(&format!("rate limit set to {} writes per epoch", max_writes_per_epoch));
+        self.max_writes_per_epoch = max_writes_per_epoch;
+    }
+
+    pub fn get_rate_limit(&self) -> u32 {
+        self.max_writes_per_epoch
+    }
+
+    /// Attestations accepted for the account in the current epoch, as counted against the rate limit.
+    pub fn get_epoch_writes(&self, account_id: AccountId) -> u32 {
+        self.epoch_writes.get(&account_id).filter(|writes| writes.epoch == env::epoch_height()).map_or(0, |writes| writes.count)
+    }
+}
+
+impl Contract {
+    pub(crate) fn ensure_within_rate_limit(&self, account_id: &AccountId) -> Result<(), ContractError> {
+        let max = self.max_writes_per_epoch;
+        if max > 0 && self.get_epoch_writes(account_id.clone()) >= max {
+            return Err(ContractError::RateLimited { max });
+        }
+        Ok(())
+    }
+
+    pub(crate) fn record_write(&mut self, account_id: &AccountId) {
+        if self.max_writes_per_epoch == 0 {
+            return;
+        }
+        let epoch = env::epoch_height();
+        let count = self.get_epoch_writes(account_id.clone()) + 1;
+        self.epoch_writes.insert(account_id.clone(), EpochWrites { epoch, count });
+    }
+}
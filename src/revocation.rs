@@ -0,0 +1,65 @@
+use near_sdk::serde_json::json;
+use near_sdk::{env, near, require, AccountId};
+
+use crate::{events, Contract, ContractExt, ReleaseReason, Revocation, RevocationCode, RevocationRecord, Role};
+
+pub(crate) const MAX_REASON_LEN: usize = 256;
+const DEFAULT_PAGE_SIZE: u32 = 50;
+
+#[near]
+impl Contract {
+    /// Removes the attestations on `platform` and their handle mappings, e.g. once the social account is deleted
+    /// or found to be fraudulent. The reason is kept for `get_revocation` and emitted with a `social_revoked` event,
+    /// and each revoked handle is added to the revocation ledger under `code`, `Other` by default.
+    pub fn revoke_social(&mut self, account_id: AccountId, platform: String, reason: String, code: Option<RevocationCode>) {
+        self.assert_any_role(&[Role::Owner, Role::Verifier]);
+        require!(reason.len() <= MAX_REASON_LEN, format!("reason must be at most {} bytes", MAX_REASON_LEN));
+        self.revoke(account_id, platform, code.unwrap_or(RevocationCode::Other), reason);
+    }
+
+    /// The latest revocation of the account's attestation on `platform`.
+    pub fn get_revocation(&self, account_id: AccountId, platform: String) -> Option<Revocation> {
+        self.revocations.get(&(account_id, platform)).cloned()
+    }
+
+    /// Every handle revoked from the account, oldest first. Unlike expiry, revocations stay on record even if the
+    /// account erases its data.
+    pub fn get_revocation_history(&self, account_id: AccountId) -> Vec<RevocationRecord> {
+        let indices = self.account_revocations.get(&account_id).cloned().unwrap_or_default();
+        indices.into_iter().filter_map(|i| self.revocation_ledger.get(i).cloned()).collect()
+    }
+
+    /// The ledger of every revoked handle, oldest first.
+    pub fn get_revocation_ledger(&self, from_index: Option<u32>, limit: Option<u32>) -> Vec<RevocationRecord> {
+        let from = from_index.unwrap_or(0);
+        let to = from.saturating_add(limit.unwrap_or(DEFAULT_PAGE_SIZE)).min(self.revocation_ledger.len());
+        (from..to).filter_map(|i| self.revocation_ledger.get(i).cloned()).collect()
+    }
+
+    pub fn get_revocation_ledger_len(&self) -> u32 {
+        self.revocation_ledger.len()
+    }
+}
+
+impl Contract {
+    pub(crate) fn revoke(&mut self, account_id: AccountId, platform: String, code: RevocationCode, reason: String) {
+        let handles = self.handles_on(&account_id, &platform);
+        if handles.is_empty() {
+            env::panic_str("no attestation to revoke");
+        }
+        self.remove_social(&account_id, &platform, ReleaseReason::Revoked);
+        self.record_revocation(&platform);
+        events::emit("social_revoked", json!({ "account_id": account_id, "platform": platform, "handles": handles, "code": code, "reason": reason }));
+        let revoked_at = env::block_timestamp().into();
+        let mut indices = self.account_revocations.get(&account_id).cloned().unwrap_or_default();
+        for handle in &handles {
+            indices.push(self.revocation_ledger.len());
+            let record = RevocationRecord { account_id: account_id.clone(), platform: platform.clone(), handle: handle.clone(), code, reason: reason.clone(), revoked_at };
+            self.revocation_ledger.push(record);
+        }
+        self.account_revocations.insert(account_id.clone(), indices);
+        let revocation = Revocation { handles, reason, revoked_at };
+        self.revocations.insert((account_id.clone(), platform), revocation);
+        self.refresh_cached_score(&account_id);
+    }
+}
@@ -0,0 +1,45 @@
+use near_sdk::{env, near, require, AccountId};
+
+use crate::{Contract, ContractExt, Role};
+
+// the owner holds its role through `owner_id`, verifiers and operators are granted by the owner. roles are
+// distinct, the owner doesn't implicitly hold the others.
+#[near]
+impl Contract {
+    pub fn grant_role(&mut self, account_id: AccountId, role: Role) {
+        self.assert_owner();
+        require!(role != Role::Owner, "ownership is transferred with propose_owner");
+        if self.roles.insert((role, account_id.clone())) {
+            env::log_str(&format!("granted {:?} to {}", role, account_id));
+        }
+    }
+
+    pub fn revoke_role(&mut self, account_id: AccountId, role: Role) {
+        self.assert_owner();
+        require!(role != Role::Owner, "ownership is transferred with propose_owner");
+        if self.roles.remove(&(role, account_id.clone())) {
+            env::log_str(&format!("revoked {:?} from {}", role, account_id));
+        }
+    }
+
+    pub fn has_role(&self, account_id: AccountId, role: Role) -> bool {
+        match role {
+            Role::Owner => account_id == self.owner_id,
+            _ => self.roles.contains(&(role, account_id)),
+        }
+    }
+}
+
+impl Contract {
+    pub(crate) fn assert_role(&self, role: Role) {
+        self.assert_any_role(&[role]);
+    }
+
+    pub(crate) fn assert_any_role(&self, roles: &[Role]) {
+        let caller = env::predecessor_account_id();
+        require!(
+            roles.iter().any(|role| self.has_role(caller.clone(), *role)),
+            format!("only accounts with the {} role can call this method", roles.iter().map(|role| format!("{:?}", role)).collect::<Vec<_>>().join(" or "))
+        );
+    }
+}
@@ -0,0 +1,78 @@
+use near_sdk::env::block_timestamp;
+use near_sdk::{env, near, require, AccountId};
+
+use crate::{Contract, ContractExt, HumanPolicy, RuleExpr, SocialStatus};
+
+// keeps `get_rules` cheap to load
+const MAX_RULES: u32 = 64;
+// bound the work `evaluate_rule` does
+const MAX_RULE_NODES: u32 = 32;
+const MAX_RULE_DEPTH: u32 = 8;
+const MAX_RULE_ID_LEN: usize = 64;
+
+// named eligibility policies evaluated on-chain, so consumers reference a rule id instead of combining the
+// individual checks themselves
+#[near]
+impl Contract {
+    /// Adds or replaces the rule `rule_id`. A rule has at most `MAX_RULE_NODES` nodes nested at most
+    /// `MAX_RULE_DEPTH` deep.
+    pub fn set_rule(&mut self, rule_id: String, rule: RuleExpr) {
+        self.assert_owner();
+        require!(!rule_id.is_empty() && rule_id.len() <= MAX_RULE_ID_LEN, format!("rule_id must be 1 to {} bytes", MAX_RULE_ID_LEN));
+        require!(self.rules.contains_key(&rule_id) || self.rules.len() < MAX_RULES, format!("at most {} rules", MAX_RULES));
+        let (nodes, depth) = rule_size(&rule);
+        require!(nodes <= MAX_RULE_NODES && depth <= MAX_RULE_DEPTH, format!("rules have at most {} nodes nested {} deep", MAX_RULE_NODES, MAX_RULE_DEPTH));
+        env::log_str(&format!("rule {} set", rule_id));
+        self.rules.insert(rule_id, rule);
+    }
+
+    pub fn remove_rule(&mut self, rule_id: String) {
+        self.assert_owner();
+        require!(self.human_policy != HumanPolicy::Rule(rule_id.clone()), "rule is the human policy, replace it first");
+        require!(self.rules.remove(&rule_id).is_some(), "no such rule");
+    }
+
+    pub fn get_rule(&self, rule_id: String) -> Option<RuleExpr> {
+        self.rules.get(&rule_id).cloned()
+    }
+
+    pub fn get_rules(&self) -> Vec<(String, RuleExpr)> {
+        self.rules.iter().map(|(rule_id, rule)| (rule_id.clone(), rule.clone())).collect()
+    }
+
+    /// Whether the account satisfies the rule. Panics on unknown rules.
+    pub fn evaluate_rule(&self, rule_id: String, account_id: AccountId) -> bool {
+        let rule = self.rules.get(&rule_id).unwrap_or_else(|| env::panic_str(&format!("unknown rule {}", rule_id)));
+        self.satisfies(&account_id, rule, block_timestamp())
+    }
+}
+
+// (nodes, depth) of the rule
+fn rule_size(rule: &RuleExpr) -> (u32, u32) {
+    let children: Vec<(u32, u32)> = match rule {
+        RuleExpr::All(rules) | RuleExpr::Any(rules) => rules.iter().map(rule_size).collect(),
+        RuleExpr::Not(rule) => vec![rule_size(rule)],
+        _ => Vec::new(),
+    };
+    let nodes = children.iter().map(|(nodes, _)| nodes).sum::<u32>() + 1;
+    let depth = children.iter().map(|(_, depth)| *depth).max().unwrap_or(0) + 1;
+    (nodes, depth)
+}
+
+impl Contract {
+    pub(crate) fn satisfies(&self, account_id: &AccountId, rule: &RuleExpr, now: u64) -> bool {
+        match rule {
+            RuleExpr::All(rules) => rules.iter().all(|rule| self.satisfies(account_id, rule, now)),
+            RuleExpr::Any(rules) => rules.iter().any(|rule| self.satisfies(account_id, rule, now)),
+            RuleExpr::Not(rule) => !self.satisfies(account_id, rule, now),
+            RuleExpr::MinAccessKeys(min) => self.attested_key_count(account_id, None) >= *min,
+            RuleExpr::MinAccountAgeNs(min) => self.attested_age(account_id, None).is_some_and(|created| now.saturating_sub(created) >= min.0),
+            RuleExpr::Platform(platform) => {
+                self.attested_social(account_id, platform).is_some_and(|social| self.status_of(platform, &social, now) == SocialStatus::Active)
+            }
+            RuleExpr::MinScore(min) => self.compute_score(account_id, now) >= *min,
+            // counted per vouching identity, as the score counts them
+            RuleExpr::MinVouches(min) => !self.bans.contains_key(account_id) && self.identity_vouches(&self.resolved_accounts(account_id)).len() as u32 >= *min,
+        }
+    }
+}
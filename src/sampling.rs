@@ -0,0 +1,55 @@
+use std::collections::BTreeMap;
+
+use near_sdk::env::{self, block_timestamp};
+use near_sdk::json_types::Base64VecU8;
+use near_sdk::{near, require, AccountId};
+
+use crate::score::MAX_SCORE;
+use crate::{Contract, ContractExt};
+
+pub(crate) const MAX_SAMPLE_SIZE: u32 = 100;
+// probes per requested account before giving up on sparse registries
+const PROBES_PER_SAMPLE: u32 = 4;
+// every probe of a new account computes its score, this keeps the view well within view gas whatever `n` is
+const MAX_PROBES: u32 = 200;
+
+#[near]
+impl Contract {
+    /// Deterministically draws up to `n` distinct accounts with a score of at least `min_score`, each
+    /// picked with probability proportional to its score. The same seed over the same state always
+    /// returns the same accounts, so raffles can be replayed and audited. Gives up after `MAX_PROBES` draws, so
+    /// fewer than `n` accounts come back when few pass `min_score`.
+    pub fn sample_verified_accounts(&self, seed: Base64VecU8, n: u32, min_score: u32) -> Vec<AccountId> {
+        require!(n <= MAX_SAMPLE_SIZE, format!("n must be at most {}", MAX_SAMPLE_SIZE));
+        let accounts = &self.accounts;
+        let mut sampled: Vec<AccountId> = Vec::new();
+        if accounts.is_empty() {
+            return sampled;
+        }
+        let now = block_timestamp();
+        let mut scores: BTreeMap<AccountId, u32> = BTreeMap::new();
+        for round in 0..(n * PROBES_PER_SAMPLE).min(MAX_PROBES) {
+            if sampled.len() as u32 == n {
+                break;
+            }
+            let draw = random_u64(&seed.0, round);
+            let account_id = accounts.iter().nth((draw % accounts.len() as u64) as usize).unwrap().clone();
+            if sampled.contains(&account_id) {
+                continue;
+            }
+            let score = *scores.entry(account_id.clone()).or_insert_with(|| self.compute_score(&account_id, now));
+            // accept with probability score / MAX_SCORE so that higher scores are drawn more often
+            if score >= min_score && score > 0 && (draw >> 32) % (MAX_SCORE as u64) < score as u64 {
+                sampled.push(account_id);
+            }
+        }
+        sampled
+    }
+}
+
+fn random_u64(seed: &[u8], round: u32) -> u64 {
+    let mut input = seed.to_vec();
+    input.extend_from_slice(&round.to_le_bytes());
+    let hash = env::sha256(&input);
+    u64::from_le_bytes(hash[..8].try_into().unwrap())
+}
@@ -0,0 +1,120 @@
+use near_sdk::env::block_timestamp;
+use near_sdk::json_types::{Base64VecU8, U128};
+use near_sdk::serde_json::json;
+use near_sdk::{env, ext_contract, near, require, AccountId, Gas, NearToken, Promise, PromiseError};
+
+use crate::{events, Contract, ContractExt, FunctionError, SbtConfig, SocialStatus, PAUSE_MINT_SBT};
+
+const SBT_GAS: Gas = Gas::from_tgas(20);
+const ON_SBT_MINTED_GAS: Gas = Gas::from_tgas(10);
+const MAX_SBT_CLASSES: usize = 64;
+
+/// Token metadata as NEP-393 registries take it, timestamps in milliseconds.
+#[near(serializers = [json])]
+pub struct SbtMetadata {
+    pub class: u64,
+    pub issued_at: Option<u64>,
+    pub expires_at: Option<u64>,
+    pub reference: Option<String>,
+    pub reference_hash: Option<Base64VecU8>,
+}
+
+/// The NEP-393 registry methods an issuer calls.
+#[ext_contract(ext_sbt_registry)]
+#[allow(dead_code)] // only called through the generated `ext_sbt_registry`
+pub trait SbtRegistry {
+    fn sbt_mint(&mut self, token_spec: Vec<(AccountId, Vec<SbtMetadata>)>) -> Vec<u64>;
+    fn sbt_renew(&mut self, tokens: Vec<u64>, expires_at: u64);
+}
+
+// this contract issues soulbound tokens through the configured NEP-393 registry, one class per rule, for
+// consumers that read SBTs rather than this contract's views
+#[near]
+impl Contract {
+    /// `None` stops minting. Tokens already minted stay with their holders.
+    pub fn set_sbt_config(&mut self, config: Option<SbtConfig>) {
+        self.assert_owner();
+        if let Some(config) = &config {
+            require!(config.classes.len() <= MAX_SBT_CLASSES, format!("at most {} sbt classes", MAX_SBT_CLASSES));
+        }
+        self.sbt_config = config;
+    }
+
+    pub fn get_sbt_config(&self) -> Option<SbtConfig> {
+        self.sbt_config.clone()
+    }
+
+    /// Mints the caller the SBT class of `rule_id` if it satisfies the rule now, or renews the token it already
+    /// holds. The token expires with the earliest of the caller's active attestations, so calling again after
+    /// renewing them extends it. The attached deposit is forwarded to the registry for the token's storage and
+    /// refunded if minting fails.
+    #[payable]
+    pub fn mint_sbt(&mut self, rule_id: String) -> Promise {
+        self.ensure_not_paused(PAUSE_MINT_SBT).unwrap_or_else(|err| err.panic());
+        let account_id = env::predecessor_account_id();
+        self.ensure_not_banned(&account_id).unwrap_or_else(|err| err.panic());
+        let config = self.sbt_config.as_ref().unwrap_or_else(|| env::panic_str("sbt minting is not configured"));
+        let class = config.classes.iter().find(|(id, _)| *id == rule_id).map(|(_, class)| *class)
+            .unwrap_or_else(|| env::panic_str(&format!("rule {} has no sbt class", rule_id)));
+        let rule = self.rules.get(&rule_id).unwrap_or_else(|| env::panic_str(&format!("unknown rule {}", rule_id)));
+        let now = block_timestamp();
+        require!(self.satisfies(&account_id, rule, now), "rule is not satisfied");
+        let expires_at = self.credential_expiry(&account_id, now).map(|expiry| expiry / 1_000_000);
+        let registry = ext_sbt_registry::ext(config.registry.clone()).with_static_gas(SBT_GAS);
+        if let Some(&token) = self.sbt_tokens.get(&(account_id.clone(), rule_id.clone())) {
+            require!(expires_at.is_some(), "token already minted and has no expiry to renew");
+            events::emit("sbt_renewed", json!({ "account_id": account_id, "rule_id": rule_id, "token": token }));
+            return registry.sbt_renew(vec![token], expires_at.unwrap());
+        }
+        let deposit = env::attached_deposit();
+        let metadata = SbtMetadata { class, issued_at: Some(now / 1_000_000), expires_at, reference: None, reference_hash: None };
+        registry
+            .with_attached_deposit(deposit)
+            .sbt_mint(vec![(account_id.clone(), vec![metadata])])
+            .then(Self::ext(env::current_account_id()).with_static_gas(ON_SBT_MINTED_GAS).on_sbt_minted(account_id, rule_id, U128(deposit.as_yoctonear())))
+    }
+
+    #[private]
+    pub fn on_sbt_minted(&mut self, account_id: AccountId, rule_id: String, deposit: U128, #[callback_result] tokens: Result<Vec<u64>, PromiseError>) -> Option<u64> {
+        match tokens.ok().and_then(|tokens| tokens.first().copied()) {
+            Some(token) => {
+                let usage_before = env::storage_usage();
+                self.sbt_tokens.insert((account_id.clone(), rule_id.clone()), token);
+                events::emit("sbt_minted", json!({ "account_id": account_id, "rule_id": rule_id, "token": token }));
+                self.settle_storage_from(&account_id, usage_before, 0).unwrap_or_else(|err| err.panic());
+                Some(token)
+            }
+            None => {
+                env::log_str("sbt mint failed");
+                if deposit.0 > 0 {
+                    Promise::new(account_id).transfer(NearToken::from_yoctonear(deposit.0)).detach();
+                }
+                None
+            }
+        }
+    }
+
+    /// The registry token id minted to the account for `rule_id`.
+    pub fn get_sbt_token(&self, account_id: AccountId, rule_id: String) -> Option<u64> {
+        self.sbt_tokens.get(&(account_id, rule_id)).copied()
+    }
+}
+
+impl Contract {
+    // the earliest expiry among the account's active attestations across its identity, `None` if none expire
+    pub(crate) fn credential_expiry(&self, account_id: &AccountId, now: u64) -> Option<u64> {
+        let socials = self.resolved_accounts(account_id).into_iter().flat_map(|account_id| {
+            let platforms = self.social_platforms.get(&account_id).cloned().unwrap_or_default();
+            platforms.into_iter().flat_map(move |platform| {
+                self.socials_on(&account_id, &platform).into_iter()
+                    .filter(|social| self.status_of(&platform, social, now) == SocialStatus::Active)
+                    .map(|social| social.expiry_date)
+                    .collect::<Vec<_>>()
+            })
+        });
+        let passport = self.resolved_accounts(account_id).into_iter()
+            .filter_map(|account_id| self.passport_scores.get(&account_id).map(|passport| passport.expires_at.0))
+            .filter(|&expires_at| expires_at > now);
+        socials.chain(passport).min()
+    }
+}
@@ -0,0 +1,105 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use near_sdk::env::block_timestamp;
+use near_sdk::{near, AccountId};
+
+use crate::{Contract, ContractExt, ScoreWeights};
+
+pub(crate) const MAX_SCORE: u32 = 100;
+
+const MONTH_NS: u64 = 30 * 24 * 60 * 60 * 1_000_000_000;
+// shared with the `is_*_year_old` views so the score and the checks agree on when an account turns one
+pub(crate) const YEAR_NS: u64 = 365 * 24 * 60 * 60 * 1_000_000_000;
+
+#[near]
+impl Contract {
+    /// Sybil resistance out of `MAX_SCORE`, adding up the points the score weights give each attested attribute.
+    pub fn score(&self, account_id: AccountId) -> u32 {
+        self.compute_score(&account_id, block_timestamp())
+    }
+
+    pub fn has_min_score(&self, account_id: AccountId, threshold: u32) -> bool {
+        self.compute_score(&account_id, block_timestamp()) >= threshold
+    }
+
+    pub fn set_score_weights(&mut self, weights: ScoreWeights) {
+        self.assert_owner();
+        self.score_weights = weights;
+    }
+
+    pub fn get_score_weights(&self) -> ScoreWeights {
+        self.score_weights.clone()
+    }
+}
+
+impl Contract {
+    // score out of `MAX_SCORE` built from the attested attributes, socials pending renewal count at their
+    // platform's reduced weight and expired ones don't count. every component decays per the score weights.
+    pub(crate) fn compute_score(&self, account_id: &AccountId, now: u64) -> u32 {
+        if self.bans.contains_key(account_id) {
+            return 0;
+        }
+        let weights = &self.score_weights;
+        let resolved = self.resolved_accounts(account_id);
+        // the best of the linked accounts' attributes counts, each decayed from when it was attested
+        let keys_points = resolved.iter()
+            .filter_map(|account_id| self.access_key_counts.get(account_id).map(|&keys| (keys, self.attribute_updates(account_id).access_keys)))
+            .map(|(keys, attested_at)| {
+                let mut points: u32 = 0;
+                if keys >= 5 { points = points.saturating_add(weights.keys_light) }
+                if keys >= 20 { points = points.saturating_add(weights.keys_heavy) }
+                decayed(points, weights.keys_half_life_ns.0, attested_at.map(|at| now.saturating_sub(at.0)))
+            })
+            .max()
+            .unwrap_or(0);
+        let age_points = resolved.iter()
+            .filter_map(|account_id| self.account_ages.get(account_id).map(|&created| (created, self.attribute_updates(account_id).account_age)))
+            .map(|(created, attested_at)| {
+                let age = now.saturating_sub(created);
+                let mut points: u32 = 0;
+                if age >= 6 * MONTH_NS { points = points.saturating_add(weights.age_six_months) }
+                if age >= YEAR_NS { points = points.saturating_add(weights.age_one_year) }
+                decayed(points, weights.age_half_life_ns.0, attested_at.map(|at| now.saturating_sub(at.0)))
+            })
+            .max()
+            .unwrap_or(0);
+        let platforms: BTreeSet<&String> = resolved.iter().flat_map(|account_id| self.social_platforms.get(account_id).into_iter().flatten()).collect();
+        let social_points: u32 = platforms.into_iter()
+            .filter_map(|platform| self.attested_social(account_id, platform).map(|social| {
+                let points = (self.weight_of(platform, &social, now) as u32).saturating_mul(weights.per_social) / 100;
+                decayed(points, weights.social_half_life_ns.0, Some(now.saturating_sub(social.issued_date)))
+            }))
+            .sum();
+        let vouch_points: u32 = self.identity_vouches(&resolved).into_values()
+            .map(|at| decayed(weights.per_vouch, weights.vouch_half_life_ns.0, Some(now.saturating_sub(at))))
+            .sum();
+        let passport_points = self.attested_passport_score(account_id, now).map_or(0, |passport| {
+            let full = u64::from(weights.passport_full_score.max(1));
+            (u64::from(weights.passport) * u64::from(passport).min(full) / full) as u32
+        });
+        keys_points.saturating_add(age_points).saturating_add(social_points).saturating_add(vouch_points).saturating_add(passport_points).min(MAX_SCORE)
+    }
+
+    // when each identity vouching for the `resolved` accounts last did, keyed by its primary account. one vouch
+    // per vouching identity, its latest, and none from the accounts' own identity
+    pub(crate) fn identity_vouches(&self, resolved: &[AccountId]) -> BTreeMap<AccountId, u64> {
+        let own: BTreeSet<AccountId> = resolved.iter().map(|account_id| self.primary_account(account_id)).collect();
+        let mut vouched_at: BTreeMap<AccountId, u64> = BTreeMap::new();
+        for vouch in resolved.iter().flat_map(|account_id| self.vouches.get(account_id).into_iter().flatten()) {
+            let voucher = self.primary_account(&self.merge_target(&vouch.voucher));
+            if !own.contains(&voucher) {
+                let at = vouched_at.entry(voucher).or_default();
+                *at = (*at).max(vouch.vouched_at.0);
+            }
+        }
+        vouched_at
+    }
+}
+
+// `points` attested `elapsed` ago, halved after `half_life` and a third after twice that
+fn decayed(points: u32, half_life: u64, elapsed: Option<u64>) -> u32 {
+    match elapsed {
+        Some(elapsed) if half_life > 0 => (u128::from(points) * u128::from(half_life) / (u128::from(half_life) + u128::from(elapsed))) as u32,
+        _ => points,
+    }
+}
@@ -0,0 +1,239 @@
+use near_sdk::json_types::Base64VecU8;
+use near_sdk::serde_json::json;
+use near_sdk::{env, near, require, AccountId};
+
+use crate::{events, Contract, ContractExt, MerkleSnapshot, RuleExpr, RuleSnapshot};
+
+// fixed depth, so proofs always carry this many sibling hashes and the tree can be built append-only
+const TREE_DEPTH: usize = 32;
+// bytes of sha256(account_id) a rule snapshot stores per passing account, too many to grind a collision
+pub(crate) const MEMBER_HASH_LEN: usize = 16;
+
+// incremental merkle tree in the style of the eth2 deposit contract: only the left siblings still waiting for
+// a right one are kept, so each leaf costs at most TREE_DEPTH hashes and the state stays constant size
+#[near(serializers = [borsh])]
+pub struct MerkleTree {
+    leaf_count: u32,
+    branch: Vec<[u8; 32]>,
+}
+
+#[near(serializers = [borsh])]
+pub struct SnapshotBuilder {
+    check: String,
+    min_access_keys: u32, // the threshold as it stood when the snapshot started
+    block_height: u64,
+    cursor: u32, // index into `accounts` the next call resumes at
+    tree: MerkleTree,
+}
+
+// a rule snapshot being built by `commit_rule_snapshot`. the rule is copied, so editing it meanwhile has no effect
+#[near(serializers = [borsh])]
+pub struct RuleSnapshotBuilder {
+    snapshot_id: u32,
+    rule: RuleExpr,
+    timestamp: u64, // the rule is evaluated as of the start, whenever a batch runs
+    cursor: u32,
+    end: u32, // accounts registered after the start sit past it and are never included
+    tree: MerkleTree,
+}
+
+fn hash_pair(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    env::sha256_array([left.as_slice(), right.as_slice()].concat())
+}
+
+fn leaf_hash(account_id: &AccountId) -> [u8; 32] {
+    env::sha256_array(account_id.as_bytes())
+}
+
+pub(crate) fn member_hash(account_id: &AccountId) -> [u8; MEMBER_HASH_LEN] {
+    leaf_hash(account_id)[..MEMBER_HASH_LEN].try_into().unwrap()
+}
+
+// whether `proof`, the siblings from the leaf up, leads from the leaf at `index` to `root`
+fn verify_proof(root: &[u8; 32], leaf: [u8; 32], index: u32, proof: &[[u8; 32]]) -> bool {
+    if proof.len() != TREE_DEPTH {
+        return false;
+    }
+    let mut node = leaf;
+    for (level, sibling) in proof.iter().enumerate() {
+        node = if (index >> level) & 1 == 1 { hash_pair(sibling, &node) } else { hash_pair(&node, sibling) };
+    }
+    node == *root
+}
+
+impl MerkleTree {
+    fn new() -> Self {
+        MerkleTree { leaf_count: 0, branch: vec![[0; 32]; TREE_DEPTH] }
+    }
+
+    fn push(&mut self, leaf: [u8; 32]) {
+        let mut node = leaf;
+        let mut size = self.leaf_count;
+        for level in 0..TREE_DEPTH {
+            if size & 1 == 0 {
+                self.branch[level] = node;
+                break;
+            }
+            node = hash_pair(&self.branch[level], &node);
+            size >>= 1;
+        }
+        self.leaf_count += 1;
+    }
+
+    fn root(&self) -> [u8; 32] {
+        let mut node = [0; 32];
+        let mut zero = [0; 32];
+        let mut size = self.leaf_count;
+        for level in 0..TREE_DEPTH {
+            node = if size & 1 == 1 { hash_pair(&self.branch[level], &node) } else { hash_pair(&node, &zero) };
+            zero = hash_pair(&zero, &zero);
+            size >>= 1;
+        }
+        node
+    }
+}
+
+#[near]
+impl Contract {
+    /// Builds a merkle root over the accounts meeting the named key threshold, processing up to `limit`
+    /// accounts per call; call again with the same `check` until it returns `true`, at which point the root is
+    /// stored for `get_latest_snapshot`. Each call emits a `snapshot_leaves` event with the included accounts
+    /// in leaf order, from which inclusion proofs can be built. Leaves are `sha256(account_id)`, inner nodes
+    /// `sha256(left ++ right)`, padded with zero leaves to a depth of 32.
+    pub fn commit_snapshot(&mut self, check: String, limit: u32) -> bool {
+        self.assert_owner();
+        let mut builder = match self.snapshot_builder.take() {
+            Some(builder) => {
+                require!(builder.check == check, format!("a snapshot of {} is in progress", builder.check));
+                builder
+            }
+            None => SnapshotBuilder {
+                min_access_keys: *self.thresholds.get(&check).unwrap_or_else(|| env::panic_str(&format!("unknown threshold {}", check))),
+                check,
+                block_height: env::block_height(),
+                cursor: 0,
+                tree: MerkleTree::new(),
+            },
+        };
+        let end = builder.cursor.saturating_add(limit).min(self.accounts.len());
+        let included: Vec<AccountId> = self
+            .accounts
+            .iter()
+            .skip(builder.cursor as usize)
+            .take((end - builder.cursor) as usize)
+            .filter(|account_id| self.attested_key_count(account_id, None) >= builder.min_access_keys)
+            .cloned()
+            .collect();
+        for account_id in &included {
+            builder.tree.push(leaf_hash(account_id));
+        }
+        events::emit("snapshot_leaves", json!({ "check": builder.check, "block_height": builder.block_height.to_string(), "accounts": included }));
+        builder.cursor = end;
+        if end < self.accounts.len() {
+            self.snapshot_builder = Some(builder);
+            return false;
+        }
+        self.latest_snapshot = Some(MerkleSnapshot {
+            root: Base64VecU8(builder.tree.root().to_vec()),
+            leaf_count: builder.tree.leaf_count,
+            block_height: builder.block_height.into(),
+            check: builder.check,
+        });
+        true
+    }
+
+    /// Discards a snapshot in progress, e.g. to start one for another check.
+    pub fn abort_snapshot(&mut self) {
+        self.assert_owner();
+        require!(self.snapshot_builder.take().is_some(), "no snapshot in progress");
+    }
+
+    pub fn get_latest_snapshot(&self) -> Option<MerkleSnapshot> {
+        self.latest_snapshot.clone()
+    }
+
+    /// Records which accounts satisfy `rule_id` as of the current block, processing up to `limit` accounts per
+    /// call; call again with the same `rule_id` until it returns the snapshot id. Only accounts known when the
+    /// snapshot started are considered, so registering later can't get an account into it. Banned accounts never
+    /// pass. Like `commit_snapshot`, the passing accounts also form a merkle tree, stored as the snapshot's `root`,
+    /// with each call emitting them in leaf order as a `rule_snapshot_leaves` event.
+    pub fn commit_rule_snapshot(&mut self, rule_id: String, limit: u32) -> Option<u32> {
+        self.assert_owner();
+        let mut builder = match self.rule_snapshot_builder.take() {
+            Some(builder) => {
+                let rule_id_in_progress = &self.rule_snapshots.get(builder.snapshot_id).unwrap().rule_id;
+                require!(*rule_id_in_progress == rule_id, format!("a snapshot of rule {} is in progress", rule_id_in_progress));
+                builder
+            }
+            None => {
+                let rule = self.rules.get(&rule_id).unwrap_or_else(|| env::panic_str(&format!("unknown rule {}", rule_id))).clone();
+                let snapshot_id = self.rule_snapshots.len();
+                events::emit("rule_snapshot_started", json!({ "snapshot_id": snapshot_id, "rule_id": rule_id }));
+                self.rule_snapshots.push(RuleSnapshot {
+                    rule_id,
+                    block_height: env::block_height().into(),
+                    timestamp: env::block_timestamp().into(),
+                    passed: 0,
+                    complete: false,
+                    root: None,
+                });
+                RuleSnapshotBuilder { snapshot_id, rule, timestamp: env::block_timestamp(), cursor: 0, end: self.accounts.len(), tree: MerkleTree::new() }
+            }
+        };
+        let end = builder.cursor.saturating_add(limit).min(builder.end).min(self.accounts.len());
+        let passed: Vec<AccountId> = self
+            .accounts
+            .iter()
+            .skip(builder.cursor as usize)
+            .take((end - builder.cursor) as usize)
+            .filter(|account_id| !self.bans.contains_key(*account_id) && self.satisfies(account_id, &builder.rule, builder.timestamp))
+            .cloned()
+            .collect();
+        for account_id in &passed {
+            self.rule_snapshot_members.insert((builder.snapshot_id, member_hash(account_id)));
+            builder.tree.push(leaf_hash(account_id));
+        }
+        events::emit("rule_snapshot_leaves", json!({ "snapshot_id": builder.snapshot_id, "accounts": passed }));
+        builder.cursor = end;
+        let snapshot = self.rule_snapshots.get_mut(builder.snapshot_id).unwrap();
+        snapshot.passed += passed.len() as u32;
+        if end < builder.end.min(self.accounts.len()) {
+            self.rule_snapshot_builder = Some(builder);
+            return None;
+        }
+        snapshot.complete = true;
+        snapshot.root = Some(Base64VecU8(builder.tree.root().to_vec()));
+        events::emit("rule_snapshot_completed", json!({ "snapshot_id": builder.snapshot_id, "passed": snapshot.passed }));
+        Some(builder.snapshot_id)
+    }
+
+    /// Discards a rule snapshot in progress. Its id stays taken and the snapshot never completes.
+    pub fn abort_rule_snapshot(&mut self) {
+        self.assert_owner();
+        require!(self.rule_snapshot_builder.take().is_some(), "no rule snapshot in progress");
+    }
+
+    pub fn get_rule_snapshot(&self, snapshot_id: u32) -> Option<RuleSnapshot> {
+        self.rule_snapshots.get(snapshot_id).cloned()
+    }
+
+    /// Whether the account satisfied the rule when the snapshot was taken. Panics on unknown or incomplete
+    /// snapshots.
+    pub fn passed_at_snapshot(&self, snapshot_id: u32, account_id: AccountId) -> bool {
+        let snapshot = self.rule_snapshots.get(snapshot_id).unwrap_or_else(|| env::panic_str("unknown snapshot"));
+        require!(snapshot.complete, "snapshot is not complete");
+        self.rule_snapshot_members.contains(&(snapshot_id, member_hash(&account_id)))
+    }
+
+    /// Checks a merkle proof against the root of a completed rule snapshot, so claim contracts can verify
+    /// membership themselves. `proof` holds the 32 sibling hashes from the leaf up, `leaf_index` the account's
+    /// position among the snapshot's leaves, which decides on which side each sibling is hashed.
+    pub fn verify_snapshot_proof(&self, snapshot_id: u32, account_id: AccountId, leaf_index: u32, proof: Vec<[u8; 32]>) -> bool {
+        let snapshot = self.rule_snapshots.get(snapshot_id).unwrap_or_else(|| env::panic_str("unknown snapshot"));
+        let root: [u8; 32] = match &snapshot.root {
+            Some(root) => root.0.as_slice().try_into().unwrap(),
+            None => env::panic_str("snapshot is not complete"),
+        };
+        leaf_index < snapshot.passed && verify_proof(&root, leaf_hash(&account_id), leaf_index, &proof)
+    }
+}
@@ -0,0 +1,64 @@
+use near_sdk::serde_json::{json, Map, Value};
+use near_sdk::{env, near, require, AccountId, Gas};
+
+use crate::near_social::ext_social_db;
+use crate::{Contract, ContractExt, FunctionError};
+
+const SOCIAL_SET_GAS: Gas = Gas::from_tgas(20);
+
+// accounts can opt in to having their badges mirrored into their NEAR Social profile under
+// `profile/sybil_badges`, so BOS widgets can show them without reading this contract. the account has to grant
+// this contract write permission on that key in SocialDB first, and the writes take its SocialDB storage.
+#[near]
+impl Contract {
+    /// Opts the caller in or out. Opting in writes its current badges right away, opting out clears the key. The
+    /// storage the opt-in takes here is paid from the caller's storage balance and the attached deposit.
+    #[payable]
+    pub fn set_social_badge_publishing(&mut self, enabled: bool) {
+        let account_id = env::predecessor_account_id();
+        let usage_before = env::storage_usage();
+        if enabled {
+            self.social_badge_publishers.insert(account_id.clone());
+            self.publish_social_badges_of(&account_id);
+        } else {
+            require!(self.social_badge_publishers.remove(&account_id), "not publishing badges");
+            self.write_social_badges(&account_id, Value::Null);
+        }
+        self.settle_storage(&account_id, usage_before).unwrap_or_else(|err| err.panic());
+    }
+
+    pub fn is_publishing_social_badges(&self, account_id: AccountId) -> bool {
+        self.social_badge_publishers.contains(&account_id)
+    }
+
+    /// Writes the caller's badges again, e.g. after granting SocialDB permission late.
+    pub fn publish_social_badges(&mut self) {
+        let account_id = env::predecessor_account_id();
+        require!(self.social_badge_publishers.contains(&account_id), "not publishing badges");
+        self.publish_social_badges_of(&account_id);
+    }
+}
+
+impl Contract {
+    // writes `{"<rule_id>": "<granted_at>"}` for the account's badges if it opted in. badges whose rule id isn't
+    // a valid SocialDB key are left out
+    pub(crate) fn publish_social_badges_of(&self, account_id: &AccountId) {
+        if !self.social_badge_publishers.contains(account_id) {
+            return;
+        }
+        let badges: Map<String, Value> = self.badges.get(account_id).into_iter().flatten()
+            .filter(|badge| is_social_key(&badge.rule_id))
+            .map(|badge| (badge.rule_id.clone(), Value::String(badge.granted_at.0.to_string())))
+            .collect();
+        self.write_social_badges(account_id, Value::Object(badges));
+    }
+
+    fn write_social_badges(&self, account_id: &AccountId, badges: Value) {
+        let data = json!({ account_id.as_str(): { "profile": { "sybil_badges": badges } } });
+        ext_social_db::ext(self.near_social_id.clone()).with_static_gas(SOCIAL_SET_GAS).set(data).detach();
+    }
+}
+
+fn is_social_key(key: &str) -> bool {
+    !key.is_empty() && key.bytes().all(|byte| byte.is_ascii_alphanumeric() || matches!(byte, b'_' | b'-' | b'.'))
+}
@@ -0,0 +1,171 @@
+use near_sdk::json_types::U64;
+use near_sdk::serde_json::json;
+use near_sdk::{env, near, require, AccountId};
+
+use crate::limits::check_len;
+use crate::{events, messages, Contract, ContractError, ContractExt, RequestBound, Stamp, StampIssuer, StampSchema, StampValueKind, PAUSE_ADD_STAMP};
+
+// built-in stamp types, backed by their own storage and methods. they can't be given a schema
+pub const STAMP_ACCESS_KEY_COUNT: &str = "access_key_count";
+pub const STAMP_ACCOUNT_AGE: &str = "account_age";
+pub const STAMP_PASSPORT_SCORE: &str = "passport_score";
+pub const STAMP_WORLD_ID: &str = "world_id";
+const BUILT_IN_STAMPS: [&str; 4] = [STAMP_ACCESS_KEY_COUNT, STAMP_ACCOUNT_AGE, STAMP_PASSPORT_SCORE, STAMP_WORLD_ID];
+
+pub(crate) const MAX_STAMPS: usize = 32;
+const MAX_STAMP_SCHEMAS: u32 = 64;
+const MAX_STAMP_TYPE_LEN: u32 = 32;
+const MAX_STAMP_VALUE_LEN: u32 = 256;
+
+// stamps are attestations under a type name the owner registers a schema for, so the backend can start
+// attesting something new without a contract upgrade. consumers read them through `get_stamps`.
+#[near]
+impl Contract {
+    /// Adds or replaces the schema of `stamp_type`. Type names are lowercase ascii letters, digits and `_`.
+    /// Stamps already stored are kept as they are.
+    pub fn set_stamp_schema(&mut self, stamp_type: String, schema: StampSchema) {
+        self.assert_owner();
+        require!(is_valid_stamp_type(&stamp_type), format!("stamp type must be 1 to {} lowercase letters, digits or _ and not a built-in type", MAX_STAMP_TYPE_LEN));
+        require!(self.stamp_schemas.contains_key(&stamp_type) || self.stamp_schemas.len() < MAX_STAMP_SCHEMAS, format!("at most {} stamp schemas", MAX_STAMP_SCHEMAS));
+        env::log_str(&format!("stamp schema {} set", stamp_type));
+        self.stamp_schemas.insert(stamp_type, schema);
+    }
+
+    /// New stamps of the type are rejected from then on, stored ones are kept.
+    pub fn remove_stamp_schema(&mut self, stamp_type: String) {
+        self.assert_owner();
+        require!(self.stamp_schemas.remove(&stamp_type).is_some(), "no such stamp schema");
+    }
+
+    pub fn get_stamp_schema(&self, stamp_type: String) -> Option<StampSchema> {
+        self.stamp_schemas.get(&stamp_type).cloned()
+    }
+
+    pub fn get_stamp_schemas(&self) -> Vec<(String, StampSchema)> {
+        self.stamp_schemas.iter().map(|(stamp_type, schema)| (stamp_type.clone(), schema.clone())).collect()
+    }
+
+    /// Stores a stamp of `stamp_type` with `value` for the signer, replacing any earlier one of the same type.
+    /// Only for types whose schema requires no `issuer_role`. The verifier signs
+    /// `"stamp,<account_id>,<stamp_type>,<value>,<bound>"`, with `expires:<nanoseconds>,` before the bound when
+    /// `expires_at` is set.
+    #[payable]
+    #[handle_result]
+    pub fn add_stamp(&mut self, stamp_type: String, value: String, expires_at: Option<U64>, signature: Vec<u8>, max_block_height: Option<u64>, valid_until_timestamp_ns: Option<U64>) -> Result<(), ContractError> {
+        self.ensure_not_paused(PAUSE_ADD_STAMP)?;
+        self.ensure_deposit(0)?;
+        let bound = RequestBound::new(max_block_height, valid_until_timestamp_ns)?;
+        bound.ensure_valid()?;
+        let account_id = self.acting_account()?;
+        self.ensure_not_banned(&account_id)?;
+        self.ensure_within_rate_limit(&account_id)?;
+        let message = messages::stamp_message(&account_id, &stamp_type, &value, expires_at.map(|expires_at| expires_at.0), bound);
+        let issuer = StampIssuer::Verifier(self.verify_signature(&message, &signature)?);
+        self.internal_add_stamp(account_id.clone(), stamp_type, value, expires_at, issuer, &account_id)
+    }
+
+    /// Stores a stamp for `account_id` issued directly by the caller, who must hold the `issuer_role` of the
+    /// type's schema. Storage is paid by the issuer, from its storage balance and the attached deposit, never
+    /// from the account's.
+    #[payable]
+    #[handle_result]
+    pub fn issue_stamp(&mut self, account_id: AccountId, stamp_type: String, value: String, expires_at: Option<U64>) -> Result<(), ContractError> {
+        self.ensure_not_paused(PAUSE_ADD_STAMP)?;
+        self.ensure_deposit(0)?;
+        self.ensure_not_banned(&account_id)?;
+        self.ensure_within_rate_limit(&account_id)?;
+        let issuer_id = env::predecessor_account_id();
+        let issuer = StampIssuer::Account(issuer_id.clone());
+        self.internal_add_stamp(account_id, stamp_type, value, expires_at, issuer, &issuer_id)
+    }
+
+    /// Every stamp the account holds, expired ones included, the built-in types first. Built-in stamps carry
+    /// no issuer, any verifier key may attest them.
+    pub fn get_stamps(&self, account_id: AccountId) -> Vec<Stamp> {
+        let updates = self.attribute_updates(&account_id);
+        let built_in = |stamp_type: &str, value: String, issued_at: Option<U64>, expires_at: Option<U64>| Stamp {
+            stamp_type: stamp_type.to_string(),
+            value,
+            issuer: None,
+            issued_at: issued_at.unwrap_or(U64(0)),
+            expires_at,
+        };
+        let access_keys = self.access_key_counts.get(&account_id)
+            .map(|count| built_in(STAMP_ACCESS_KEY_COUNT, count.to_string(), updates.access_keys, None));
+        let account_age = self.account_ages.get(&account_id)
+            .map(|created| built_in(STAMP_ACCOUNT_AGE, created.to_string(), updates.account_age, None));
+        let passport = self.passport_scores.get(&account_id)
+            .map(|passport| built_in(STAMP_PASSPORT_SCORE, passport.score.to_string(), Some(passport.issued_at), Some(passport.expires_at)));
+        let world_id = self.world_ids.get(&account_id)
+            .map(|world_id| built_in(STAMP_WORLD_ID, world_id.nullifier_hash.clone(), Some(world_id.issued_at), None));
+        access_keys.into_iter().chain(account_age).chain(passport).chain(world_id)
+            .chain(self.stamps.get(&account_id).cloned().unwrap_or_default())
+            .collect()
+    }
+}
+
+impl Contract {
+    // `payer` is charged for the storage the stamp takes up
+    fn internal_add_stamp(&mut self, account_id: AccountId, stamp_type: String, value: String, expires_at: Option<U64>, issuer: StampIssuer, payer: &AccountId) -> Result<(), ContractError> {
+        check_len("stamp value", &value, MAX_STAMP_VALUE_LEN)?;
+        self.check_stamp(&stamp_type, &value, expires_at, &issuer)?;
+        let mut stamps = self.stamps.get(&account_id).cloned().unwrap_or_default();
+        stamps.retain(|stamp| stamp.stamp_type != stamp_type);
+        if stamps.len() >= MAX_STAMPS {
+            return Err(ContractError::TooManyStamps { max: MAX_STAMPS as u32 });
+        }
+        self.flush_collections();
+        let usage_before = env::storage_usage();
+        events::emit("stamp_added", json!({ "account_id": account_id, "stamp_type": stamp_type, "issuer": issuer }));
+        stamps.push(Stamp { stamp_type, value, issuer: Some(issuer), issued_at: env::block_timestamp().into(), expires_at });
+        self.stamps.insert(account_id.clone(), stamps);
+        self.accounts.insert(account_id.clone());
+        self.record_issuance(&account_id);
+        self.settle_storage(payer, usage_before)?;
+        Ok(())
+    }
+
+    // the stamp against its type's schema: a value of the schema's kind, an expiry within `max_validity_ns`
+    // and an issuer the schema allows
+    fn check_stamp(&self, stamp_type: &str, value: &str, expires_at: Option<U64>, issuer: &StampIssuer) -> Result<(), ContractError> {
+        let schema = self.stamp_schemas.get(stamp_type).ok_or(ContractError::UnknownStampType)?;
+        let allowed = match (issuer, schema.issuer_role) {
+            (StampIssuer::Verifier(_), None) => true,
+            (StampIssuer::Account(account_id), Some(role)) => self.has_role(account_id.clone(), role),
+            _ => false,
+        };
+        if !allowed {
+            return Err(ContractError::StampIssuerNotAllowed);
+        }
+        let now = env::block_timestamp();
+        let expiry_ok = match (expires_at, schema.max_validity_ns) {
+            (Some(expires_at), Some(max)) => expires_at.0 > now && expires_at.0 - now <= max.0,
+            (Some(expires_at), None) => expires_at.0 > now,
+            (None, Some(_)) => false,
+            (None, None) => true,
+        };
+        if !expiry_ok || !schema.value_kind.accepts(value) {
+            return Err(ContractError::InvalidStamp);
+        }
+        Ok(())
+    }
+}
+
+impl StampValueKind {
+    // commas are never accepted, they separate the fields of the signed message
+    fn accepts(&self, value: &str) -> bool {
+        match self {
+            StampValueKind::Text => !value.contains(','),
+            StampValueKind::Integer => value.parse::<i128>().is_ok(),
+            StampValueKind::Boolean => value == "true" || value == "false",
+            StampValueKind::Timestamp => value.parse::<u64>().is_ok(),
+        }
+    }
+}
+
+fn is_valid_stamp_type(stamp_type: &str) -> bool {
+    !stamp_type.is_empty()
+        && stamp_type.len() <= MAX_STAMP_TYPE_LEN as usize
+        && stamp_type.bytes().all(|byte| byte.is_ascii_lowercase() || byte.is_ascii_digit() || byte == b'_')
+        && !BUILT_IN_STAMPS.contains(&stamp_type)
+}
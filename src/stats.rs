@@ -0,0 +1,84 @@
+use near_sdk::json_types::U64;
+use near_sdk::{env, near, require};
+
+use crate::dashboard::DAY_NS;
+use crate::{Contract, ContractExt, PlatformStats, Stats};
+
+// days of registrations kept in the rolling window, the most `stats` can look back
+pub(crate) const ROLLING_DAYS: usize = 30;
+
+#[near(serializers = [borsh])]
+#[derive(Clone, Default)]
+pub struct PlatformCounts {
+    registrations: u64,
+    revocations: u64,
+}
+
+#[near(serializers = [borsh])]
+pub struct Activity {
+    registrations: u64,
+    revocations: u64,
+    last_day: u64, // days since epoch of the newest bucket in `daily`
+    daily: Vec<u32>, // registrations per day, indexed by day % ROLLING_DAYS
+}
+
+impl Default for Activity {
+    fn default() -> Self {
+        Self { registrations: 0, revocations: 0, last_day: 0, daily: vec![0; ROLLING_DAYS] }
+    }
+}
+
+#[near]
+impl Contract {
+    /// Registrations cover renewals, so they can exceed the number of accounts. `days` defaults to and is at
+    /// most 30.
+    pub fn stats(&self, days: Option<u32>) -> Stats {
+        let days = days.unwrap_or(ROLLING_DAYS as u32) as u64;
+        require!(days <= ROLLING_DAYS as u64, format!("days must be at most {}", ROLLING_DAYS));
+        let today = env::block_timestamp() / DAY_NS;
+        let activity = &self.activity;
+        // buckets are only valid for the window ending at `last_day`, later days had no registrations yet
+        let recent = ((today + 1).saturating_sub(days)..=today.min(activity.last_day))
+            .filter(|day| day + ROLLING_DAYS as u64 > activity.last_day)
+            .map(|day| u64::from(activity.daily[day as usize % ROLLING_DAYS]))
+            .sum::<u64>();
+        let platforms = self
+            .platform_counts
+            .iter()
+            .map(|(platform, counts)| PlatformStats {
+                platform: platform.clone(),
+                registrations: counts.registrations.into(),
+                revocations: counts.revocations.into(),
+            })
+            .collect();
+        Stats {
+            accounts: self.accounts.len(),
+            handles: self.handles.len(),
+            registrations: activity.registrations.into(),
+            revocations: activity.revocations.into(),
+            recent_registrations: U64(recent),
+            platforms,
+        }
+    }
+}
+
+impl Contract {
+    pub(crate) fn record_registration(&mut self, platform: &str) {
+        let today = env::block_timestamp() / DAY_NS;
+        let activity = &mut self.activity;
+        // clear the buckets of the days skipped since the last registration
+        for day in (activity.last_day + 1..=today).take(ROLLING_DAYS) {
+            activity.daily[day as usize % ROLLING_DAYS] = 0;
+        }
+        activity.last_day = activity.last_day.max(today);
+        activity.daily[today as usize % ROLLING_DAYS] += 1;
+        activity.registrations += 1;
+        self.platform_counts.entry(platform.to_string()).or_default().registrations += 1;
+        self.count_for_breaker();
+    }
+
+    pub(crate) fn record_revocation(&mut self, platform: &str) {
+        self.activity.revocations += 1;
+        self.platform_counts.entry(platform.to_string()).or_default().revocations += 1;
+    }
+}
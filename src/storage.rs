@@ -0,0 +1,209 @@
+use near_sdk::{env, near, AccountId, BorshStorageKey, NearToken, Promise};
+
+use crate::{Contract, ContractError};
+
+/// Prefixes of every persistent collection. Only append new variants: reordering changes the prefixes
+/// of existing data.
+#[near]
+#[derive(BorshStorageKey)]
+pub(crate) enum StorageKey {
+    Accounts,
+    Handles,
+    FailedAttempts,
+    UsedNonces,
+    Platforms,
+    EvmOwners,
+    Ed25519Owners,
+    Holders,
+    PendingChanges,
+    AccessKeyCounts,
+    AccountAges,
+    Socials,
+    EvmAddresses,
+    Ed25519Addresses,
+    SocialPlatforms,
+    StorageBalances,
+    Roles,
+    Thresholds,
+    #[allow(dead_code)] // held the allowlist before the platform registry, kept so later prefixes don't shift
+    AllowedPlatforms,
+    Revocations,
+    Bans,
+    PlatformCounts,
+    HandleHistory,
+    HandleHistoryLen,
+    ReleasedHandles,
+    OptimisticClaims,
+    Vouches,
+    VouchActivity,
+    LinkRequests,
+    IdentityOf,
+    IdentityMembers,
+    MergedAccounts,
+    ProfileMetadata,
+    Consumers,
+    Intermediaries,
+    EpochWrites,
+    Disputes,
+    VerifierBonds,
+    Commitments,
+    ExpiryWarnings,
+    AttributeUpdates,
+    RevocationLedger,
+    AccountRevocations,
+    CachedScores,
+    Rules,
+    Badges,
+    PassportScores,
+    Stamps,
+    StampSchemas,
+    RuleSnapshots,
+    RuleSnapshotMembers,
+    SbtTokens,
+    Subscriptions,
+    FeeTokens,
+    FtFeeBalances,
+    FtTreasury,
+    MintedNfts,
+    SocialBadgePublishers,
+    EvmAttestationNonces,
+    PendingEvmAttestations,
+    EvmAttestations,
+    WorldIds,
+    WorldIdNullifiers,
+    MergedWorldIdNullifiers,
+    ClaimsOf,
+}
+
+#[near(serializers = [borsh])]
+#[derive(Clone, Default)]
+pub struct StorageAccount {
+    pub total: u128, // yoctoNEAR
+    pub locked: u128, // part of `total` backing the account's stored data
+}
+
+impl StorageAccount {
+    pub fn available(&self) -> u128 {
+        self.total - self.locked
+    }
+}
+
+impl Contract {
+    // settles the storage a call changed for `account_id` since `usage_before`. growth is paid from the
+    // account's available balance, then from the attached deposit, failing with `InsufficientDeposit` when
+    // the two don't cover it, and the excess deposit refunded to the caller. freed bytes are refunded to
+    // `account_id`, up to what it has paid for so far.
+    pub(crate) fn settle_storage(&mut self, account_id: &AccountId, usage_before: u64) -> Result<(), ContractError> {
+        self.settle_storage_from(account_id, usage_before, env::attached_deposit().as_yoctonear())
+    }
+
+    // `settle_storage` with only `deposit` of the attached deposit available, the rest having been charged
+    pub(crate) fn settle_storage_from(&mut self, account_id: &AccountId, usage_before: u64, mut deposit: u128) -> Result<(), ContractError> {
+        self.flush_collections();
+        let usage = env::storage_usage();
+        if usage >= usage_before {
+            let mut balance = self.storage_balances.get(account_id).cloned().unwrap_or_default();
+            let cost = u128::from(usage - usage_before) * env::storage_byte_cost().as_yoctonear();
+            let from_balance = cost.min(balance.available());
+            let from_deposit = cost - from_balance;
+            if from_deposit > deposit {
+                return Err(ContractError::InsufficientDeposit { need: from_deposit, got: deposit });
+            }
+            deposit -= from_deposit;
+            balance.total += from_deposit;
+            balance.locked += cost;
+            if balance.total > 0 {
+                self.storage_balances.insert(account_id.clone(), balance);
+            }
+        } else {
+            self.release_storage(account_id, usage_before - usage);
+        }
+        if deposit > 0 {
+            Promise::new(env::predecessor_account_id()).transfer(NearToken::from_yoctonear(deposit)).detach();
+        }
+        Ok(())
+    }
+
+    // refunds `account_id` for `bytes` of its data that were removed, up to what it has paid for so far.
+    // unlike `settle_storage` this leaves the attached deposit alone, so it can run for another account.
+    pub(crate) fn release_storage(&mut self, account_id: &AccountId, bytes: u64) {
+        let mut balance = match self.storage_balances.get(account_id) {
+            Some(balance) => balance.clone(),
+            None => return,
+        };
+        let freed = (u128::from(bytes) * env::storage_byte_cost().as_yoctonear()).min(balance.locked);
+        balance.total -= freed;
+        balance.locked -= freed;
+        if balance.total > 0 {
+            self.storage_balances.insert(account_id.clone(), balance);
+        } else {
+            self.storage_balances.remove(account_id);
+        }
+        if freed > 0 {
+            Promise::new(account_id.clone()).transfer(NearToken::from_yoctonear(freed)).detach();
+        }
+    }
+
+    // `store` collections buffer their writes until flushed, which otherwise only happens once the call
+    // returns. `used_nonces` writes through.
+    pub(crate) fn flush_collections(&mut self) {
+        self.accounts.flush();
+        self.handles.flush();
+        self.failed_attempts.flush();
+        self.platforms.flush();
+        self.evm_owners.flush();
+        self.ed25519_owners.flush();
+        self.holders.flush();
+        self.pending_changes.flush();
+        self.access_key_counts.flush();
+        self.account_ages.flush();
+        self.socials.flush();
+        self.social_platforms.flush();
+        self.evm_addresses.flush();
+        self.ed25519_addresses.flush();
+        self.storage_balances.flush();
+        self.thresholds.flush();
+        self.revocations.flush();
+        self.bans.flush();
+        self.platform_counts.flush();
+        self.handle_history.flush();
+        self.handle_history_len.flush();
+        self.released_handles.flush();
+        self.optimistic_claims.flush();
+        self.vouches.flush();
+        self.vouch_activity.flush();
+        self.identity_of.flush();
+        self.identity_members.flush();
+        self.merged_accounts.flush();
+        self.profile_metadata.flush();
+        self.consumers.flush();
+        self.epoch_writes.flush();
+        self.disputes.flush();
+        self.verifier_bonds.flush();
+        self.commitments.flush();
+        self.expiry_warnings.flush();
+        self.attribute_updates.flush();
+        self.revocation_ledger.flush();
+        self.account_revocations.flush();
+        self.cached_scores.flush();
+        self.rules.flush();
+        self.badges.flush();
+        self.passport_scores.flush();
+        self.stamps.flush();
+        self.stamp_schemas.flush();
+        self.rule_snapshots.flush();
+        self.sbt_tokens.flush();
+        self.subscriptions.flush();
+        self.fee_tokens.flush();
+        self.ft_fee_balances.flush();
+        self.ft_treasury.flush();
+        self.minted_nfts.flush();
+        self.evm_attestation_nonces.flush();
+        self.pending_evm_attestations.flush();
+        self.evm_attestations.flush();
+        self.world_ids.flush();
+        self.world_id_nullifiers.flush();
+        self.merged_world_id_nullifiers.flush();
+        self.claims_of.flush();
+    }
+}
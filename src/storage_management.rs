@@ -0,0 +1,163 @@
+use near_sdk::json_types::U128;
+use near_sdk::serde_json::json;
+use near_sdk::{assert_one_yocto, env, near, require, AccountId, NearToken, Promise};
+
+use crate::storage::StorageAccount;
+use crate::{events, Contract, ContractExt, FunctionError, PAUSE_DELETE_MY_DATA, PAUSE_STORAGE_UNREGISTER, SocialData, StorageBalance, StorageBalanceBounds};
+
+// roughly one social with its handle index entry, the smallest useful deposit
+const MIN_STORAGE_BYTES: u64 = 512;
+
+fn min_storage_balance() -> u128 {
+    u128::from(MIN_STORAGE_BYTES) * env::storage_byte_cost().as_yoctonear()
+}
+
+impl From<StorageAccount> for StorageBalance {
+    fn from(balance: StorageAccount) -> Self {
+        StorageBalance { total: balance.total.into(), available: balance.available().into() }
+    }
+}
+
+// NEP-145 storage management. an account is registered once it holds a storage balance, which also
+// happens implicitly the first time a deposit pays for its data.
+#[near]
+impl Contract {
+    #[payable]
+    pub fn storage_deposit(&mut self, account_id: Option<AccountId>, registration_only: Option<bool>) -> StorageBalance {
+        let account_id = account_id.unwrap_or_else(env::predecessor_account_id);
+        let mut deposit = env::attached_deposit().as_yoctonear();
+        let mut balance = self.storage_balances.get(&account_id).cloned().unwrap_or_default();
+        if registration_only.unwrap_or(false) {
+            // only takes what's missing to reach the minimum, the rest is refunded
+            let needed = min_storage_balance().saturating_sub(balance.total);
+            require!(deposit >= needed, "attached deposit is less than the minimum storage balance");
+            balance.total += needed;
+            deposit -= needed;
+            if deposit > 0 {
+                Promise::new(env::predecessor_account_id()).transfer(NearToken::from_yoctonear(deposit)).detach();
+            }
+        } else {
+            require!(balance.total + deposit >= min_storage_balance(), "attached deposit is less than the minimum storage balance");
+            balance.total += deposit;
+        }
+        self.storage_balances.insert(account_id, balance.clone());
+        balance.into()
+    }
+
+    #[payable]
+    pub fn storage_withdraw(&mut self, amount: Option<U128>) -> StorageBalance {
+        assert_one_yocto();
+        let account_id = env::predecessor_account_id();
+        let mut balance = self.storage_balances.get(&account_id).cloned().unwrap_or_else(|| env::panic_str("account is not registered"));
+        let amount = amount.map_or(balance.available(), |amount| amount.0);
+        require!(amount <= balance.available(), "amount exceeds the available storage balance");
+        if amount > 0 {
+            balance.total -= amount;
+            self.storage_balances.insert(account_id.clone(), balance.clone());
+            Promise::new(account_id).transfer(NearToken::from_yoctonear(amount)).detach();
+        }
+        balance.into()
+    }
+
+    /// Refunds the whole storage balance. Fails while the account still has stored data unless `force` is
+    /// set, in which case all of its data is removed first.
+    #[payable]
+    pub fn storage_unregister(&mut self, force: Option<bool>) -> bool {
+        assert_one_yocto();
+        self.ensure_not_paused(PAUSE_STORAGE_UNREGISTER).unwrap_or_else(|err| err.panic());
+        let account_id = env::predecessor_account_id();
+        let balance = match self.storage_balances.get(&account_id) {
+            Some(balance) => balance.total,
+            None => return false,
+        };
+        if self.accounts.contains(&account_id) {
+            require!(force.unwrap_or(false), "account still has stored data, unregister with force to remove it");
+            self.remove_account_data(&account_id);
+        }
+        self.storage_balances.remove(&account_id);
+        if balance > 0 {
+            Promise::new(account_id).transfer(NearToken::from_yoctonear(balance)).detach();
+        }
+        true
+    }
+
+    /// Erases everything stored for the caller, expired attestations included: socials and their handle index
+    /// entries, attested attributes, linked addresses, profile metadata, handle history and the cooldowns it
+    /// started, commitments, pending claims and attestation requests. A pending claim is withdrawn, its bond
+    /// refunded unless it was disputed, in which case the challenger takes both bonds. The storage freed is
+    /// refunded while the storage balance stays registered. Vouches others staked on the caller remain theirs to
+    /// revoke. Requires exactly 1 yoctoNEAR attached.
+    #[payable]
+    pub fn delete_my_data(&mut self) {
+        assert_one_yocto();
+        self.ensure_not_paused(PAUSE_DELETE_MY_DATA).unwrap_or_else(|err| err.panic());
+        let account_id = env::predecessor_account_id();
+        self.flush_collections();
+        let usage_before = env::storage_usage();
+        self.remove_account_data(&account_id);
+        self.flush_collections();
+        self.release_storage(&account_id, usage_before.saturating_sub(env::storage_usage()));
+        events::emit("account_data_deleted", json!({ "account_id": account_id }));
+    }
+
+    pub fn storage_balance_of(&self, account_id: AccountId) -> Option<StorageBalance> {
+        self.storage_balances.get(&account_id).cloned().map(StorageBalance::from)
+    }
+
+    pub fn storage_balance_bounds(&self) -> StorageBalanceBounds {
+        StorageBalanceBounds { min: min_storage_balance().into(), max: None }
+    }
+}
+
+impl Contract {
+    // removes every attestation and linked address of `account_id`, freeing its handles and holder slots
+    pub(crate) fn remove_account_data(&mut self, account_id: &AccountId) {
+        for platform in self.social_platforms.remove(account_id).unwrap_or_default() {
+            for social in self.socials.remove(&(account_id.clone(), platform.clone())).unwrap_or_default() {
+                let handle_key = (platform.clone(), SocialData::from(social).handle);
+                self.expiry_warnings.remove(&(account_id.clone(), platform.clone(), handle_key.1.clone()));
+                if self.handles.get(&handle_key) == Some(account_id) {
+                    self.handles.remove(&handle_key);
+                }
+            }
+            self.release_holder_slot(&platform, account_id);
+        }
+        for address in self.evm_addresses.remove(account_id).unwrap_or_default() {
+            self.evm_owners.remove(&address);
+        }
+        for linked in self.ed25519_addresses.remove(account_id).unwrap_or_default() {
+            self.ed25519_owners.remove(&(linked.chain, linked.address));
+        }
+        self.access_key_counts.remove(account_id);
+        self.account_ages.remove(account_id);
+        self.attribute_updates.remove(account_id);
+        self.cached_scores.remove(account_id);
+        self.badges.remove(account_id);
+        self.passport_scores.remove(account_id);
+        let own = self.world_ids.remove(account_id).map(|world_id| world_id.nullifier_hash);
+        for nullifier in own.into_iter().chain(self.merged_world_id_nullifiers.remove(account_id).unwrap_or_default()) {
+            // a nullifier the account released earlier may back another account by now
+            if self.world_id_nullifiers.get(&nullifier) == Some(account_id) {
+                self.world_id_nullifiers.remove(&nullifier);
+            }
+        }
+        self.stamps.remove(account_id);
+        self.minted_nfts.remove(account_id);
+        self.social_badge_publishers.remove(account_id);
+        self.evm_attestations.remove(account_id);
+        for (rule_id, _) in self.sbt_config.iter().flat_map(|config| &config.classes) {
+            self.sbt_tokens.remove(&(account_id.clone(), rule_id.clone()));
+        }
+        self.failed_attempts.remove(account_id);
+        self.clear_handle_history(account_id);
+        self.leave_identity(account_id);
+        self.profile_metadata.remove(account_id);
+        self.vouch_activity.remove(account_id);
+        self.merged_accounts.remove(account_id);
+        self.withdraw_claims(account_id);
+        self.commitments.remove(account_id);
+        self.pending_evm_attestations.remove(account_id);
+        self.epoch_writes.remove(account_id);
+        self.accounts.remove(account_id);
+    }
+}
@@ -0,0 +1,116 @@
+use near_sdk::json_types::U128;
+use near_sdk::{env, near, require, AccountId, NearToken, Promise};
+
+use crate::{Contract, ContractError, ContractExt, FunctionError};
+
+// consumer contracts making many paid queries can prepay a balance instead of attaching the fee to each call.
+// the balance is the consumer's until a query spends it, spent fees go to the treasury.
+#[near]
+impl Contract {
+    /// Registers the caller as a subscriber. The attached deposit pays for the entry's storage, the rest is its
+    /// starting balance.
+    #[payable]
+    pub fn register_consumer(&mut self) {
+        let consumer = env::predecessor_account_id();
+        require!(!self.subscriptions.contains_key(&consumer), "already registered");
+        let usage_before = env::storage_usage();
+        self.subscriptions.insert(consumer.clone(), 0);
+        self.subscriptions.flush();
+        let cost = u128::from(env::storage_usage() - usage_before) * env::storage_byte_cost().as_yoctonear();
+        let got = env::attached_deposit().as_yoctonear();
+        if got < cost {
+            ContractError::InsufficientDeposit { need: cost, got }.panic();
+        }
+        self.subscriptions.insert(consumer.clone(), got - cost);
+        env::log_str(&format!("registered consumer {} with a balance of {}", consumer, got - cost));
+    }
+
+    /// Adds the attached deposit to the caller's balance.
+    #[payable]
+    pub fn top_up_subscription(&mut self) -> U128 {
+        let consumer = env::predecessor_account_id();
+        let balance = self.subscriptions.get_mut(&consumer).unwrap_or_else(|| env::panic_str("not a registered consumer"));
+        *balance += env::attached_deposit().as_yoctonear();
+        U128(*balance)
+    }
+
+    /// Withdraws `amount` of the caller's unspent balance, all of it if `None`.
+    pub fn withdraw_subscription(&mut self, amount: Option<U128>) -> U128 {
+        let consumer = env::predecessor_account_id();
+        let balance = self.subscriptions.get_mut(&consumer).unwrap_or_else(|| env::panic_str("not a registered consumer"));
+        let amount = amount.map_or(*balance, |amount| amount.0);
+        require!(amount <= *balance, "amount exceeds the subscription balance");
+        *balance -= amount;
+        if amount > 0 {
+            Promise::new(consumer).transfer(NearToken::from_yoctonear(amount)).detach();
+        }
+        U128(amount)
+    }
+
+    /// Removes the caller's registration, refunding its balance and the storage it freed.
+    pub fn unregister_consumer(&mut self) {
+        let consumer = env::predecessor_account_id();
+        let usage_before = env::storage_usage();
+        let balance = self.subscriptions.remove(&consumer).unwrap_or_else(|| env::panic_str("not a registered consumer"));
+        self.subscriptions.flush();
+        let freed = u128::from(usage_before - env::storage_usage()) * env::storage_byte_cost().as_yoctonear();
+        if balance + freed > 0 {
+            Promise::new(consumer).transfer(NearToken::from_yoctonear(balance + freed)).detach();
+        }
+    }
+
+    pub fn get_subscription_balance(&self, account_id: AccountId) -> Option<U128> {
+        self.subscriptions.get(&account_id).map(|&balance| U128(balance))
+    }
+
+    /// In yoctoNEAR, charged per `verify_for` call and added to the treasury. 0 makes it free.
+    pub fn set_verify_fee(&mut self, fee: U128) {
+        self.assert_owner();
+        env::log_str(&format!("verify fee set to {}", fee.0));
+        self.verify_fee = fee.0;
+    }
+
+    pub fn get_verify_fee(&self) -> U128 {
+        self.verify_fee.into()
+    }
+
+    /// In yoctoNEAR, charged per `request_attestation` call and added to the treasury. 0 makes it free.
+    pub fn set_attestation_fee(&mut self, fee: U128) {
+        self.assert_owner();
+        env::log_str(&format!("attestation fee set to {}", fee.0));
+        self.attestation_fee = fee.0;
+    }
+
+    pub fn get_attestation_fee(&self) -> U128 {
+        self.attestation_fee.into()
+    }
+}
+
+impl Contract {
+    // charges `fee` for a paid query: from the attached deposit if it covers it, refunding the excess, then
+    // from the caller's token credit, otherwise the shortfall from its subscription balance
+    pub(crate) fn charge_query(&mut self, fee: u128) {
+        let caller = env::predecessor_account_id();
+        let got = env::attached_deposit().as_yoctonear();
+        if got >= fee {
+            if got > fee {
+                Promise::new(caller).transfer(NearToken::from_yoctonear(got - fee)).detach();
+            }
+        } else if self.take_fee(&caller, fee) == 0 {
+            if got > 0 {
+                Promise::new(caller).transfer(NearToken::from_yoctonear(got)).detach();
+            }
+            return;
+        } else {
+            let balance = self.subscriptions.get_mut(&caller).filter(|balance| **balance >= fee - got);
+            match balance {
+                Some(balance) => *balance -= fee - got,
+                None => {
+                    let held = self.subscriptions.get(&caller).copied().unwrap_or(0);
+                    ContractError::InsufficientDeposit { need: fee, got: got + held }.panic();
+                }
+            }
+        }
+        self.treasury += fee;
+    }
+}
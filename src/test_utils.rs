@@ -0,0 +1,22 @@
+//! Helpers for producing verifier signed payloads outside the contract, for integration tests and for checking a
+//! backend signer. Never enable `test-utils` for mainnet builds.
+
+use ed25519_dalek::Signer;
+use near_sdk::PublicKey;
+
+pub use crate::messages::{account_info_message, relayed_social_message, social_message};
+
+/// Secret key of the well-known test verifier, which also signs `get_signing_test_vectors` in `dev` builds.
+pub const DEV_SECRET_KEY: [u8; 32] = [7; 32];
+
+/// Key pair derived from `secret`, with the public half in the encoding `new` expects.
+pub fn keypair(secret: [u8; 32]) -> (ed25519_dalek::SigningKey, PublicKey) {
+    let signing_key = ed25519_dalek::SigningKey::from_bytes(&secret);
+    let mut key_bytes = vec![0u8];
+    key_bytes.extend_from_slice(signing_key.verifying_key().as_bytes());
+    (signing_key, PublicKey::try_from(key_bytes).unwrap())
+}
+
+pub fn sign(signing_key: &ed25519_dalek::SigningKey, message: &str) -> Vec<u8> {
+    signing_key.sign(message.as_bytes()).to_bytes().to_vec()
+}
@@ -0,0 +1,39 @@
+use std::collections::BTreeMap;
+
+use near_sdk::json_types::U64;
+use near_sdk::{env, near, require, AccountId};
+
+use crate::{Contract, ContractExt};
+
+// keeps `get_thresholds` cheap to load
+const MAX_THRESHOLDS: u32 = 32;
+
+// seeded on `new`, the same levels the `connected_to_*_contracts` checks use
+pub(crate) const DEFAULT_THRESHOLDS: [(&str, u32); 3] = [("keys_light", 5), ("keys_medium", 10), ("keys_heavy", 20)];
+
+#[near]
+impl Contract {
+    /// Adds or replaces the named minimum access key count.
+    pub fn set_threshold(&mut self, name: String, min_access_keys: u32) {
+        self.assert_owner();
+        require!(self.thresholds.contains_key(&name) || self.thresholds.len() < MAX_THRESHOLDS, format!("at most {} thresholds", MAX_THRESHOLDS));
+        env::log_str(&format!("threshold {} set to {}", name, min_access_keys));
+        self.thresholds.insert(name, min_access_keys);
+    }
+
+    pub fn remove_threshold(&mut self, name: String) {
+        self.assert_owner();
+        require!(self.thresholds.remove(&name).is_some(), "no such threshold");
+    }
+
+    pub fn get_thresholds(&self) -> BTreeMap<String, u32> {
+        self.thresholds.iter().map(|(name, min)| (name.clone(), *min)).collect()
+    }
+
+    /// Whether the account's attested access key count reaches the named threshold. Panics on unknown names.
+    /// A count attested more than `max_staleness_ns` ago doesn't.
+    pub fn meets_key_threshold(&self, account_id: AccountId, name: String, max_staleness_ns: Option<U64>) -> bool {
+        let min = *self.thresholds.get(&name).unwrap_or_else(|| env::panic_str(&format!("unknown threshold {}", name)));
+        self.attested_key_count(&account_id, max_staleness_ns.map(|ns| ns.0)) >= min
+    }
+}
@@ -0,0 +1,40 @@
+use near_sdk::env::block_timestamp;
+use near_sdk::{near, require, AccountId};
+
+use crate::{Contract, ContractExt, Tier};
+
+const MAX_TIERS: usize = 8;
+const MAX_TIER_NAME_LEN: usize = 32;
+
+// a ladder of score thresholds partner dApps gate features by. an account's tier changes are emitted as
+// `tier_changed` whenever its cached score is refreshed, see `cached_score`.
+#[near]
+impl Contract {
+    /// Replaces the ladder, ordered by strictly increasing `min_score`. An empty ladder has no tiers.
+    pub fn set_tiers(&mut self, tiers: Vec<Tier>) {
+        self.assert_owner();
+        require!(tiers.len() <= MAX_TIERS, format!("at most {} tiers", MAX_TIERS));
+        require!(tiers.iter().all(|tier| !tier.name.is_empty() && tier.name.len() <= MAX_TIER_NAME_LEN), format!("tier names must be 1 to {} bytes", MAX_TIER_NAME_LEN));
+        require!(tiers.windows(2).all(|pair| pair[0].min_score < pair[1].min_score), "min_score must increase along the ladder");
+        self.tiers = tiers;
+    }
+
+    pub fn get_tiers(&self) -> Vec<Tier> {
+        self.tiers.clone()
+    }
+
+    /// The highest tier the account's current score reaches, `None` below the first.
+    pub fn get_tier(&self, account_id: AccountId) -> Option<String> {
+        self.tier_of(self.compute_score(&account_id, block_timestamp()))
+    }
+}
+
+impl Contract {
+    pub(crate) fn tier_of(&self, score: u32) -> Option<String> {
+        self.tiers.iter().rev().find(|tier| score >= tier.min_score).map(|tier| tier.name.clone())
+    }
+}
+
+pub(crate) fn default_tiers() -> Vec<Tier> {
+    [("bronze", 20), ("silver", 50), ("gold", 80)].into_iter().map(|(name, min_score)| Tier { name: name.to_string(), min_score }).collect()
+}
@@ -0,0 +1,80 @@
+use near_sdk::json_types::U64;
+use near_sdk::{env, near, require};
+
+use crate::{ConfigChange, Contract, ContractExt, PendingChange};
+
+// a raise applies at once, so without a bound one call could lock every timelocked setting out of reach
+pub(crate) const MAX_TIMELOCK_DELAY_NS: u64 = 30 * 24 * 60 * 60 * 1_000_000_000;
+// the delay a new or migrated contract starts with, long enough for users to react to a staged change
+pub(crate) const DEFAULT_TIMELOCK_DELAY_NS: u64 = 48 * 60 * 60 * 1_000_000_000;
+
+#[near(serializers = [borsh])]
+pub struct Timelock {
+    delay_ns: u64,
+    next_id: u64,
+}
+
+impl Timelock {
+    pub(crate) fn new(delay_ns: u64) -> Self {
+        Self { delay_ns, next_id: 0 }
+    }
+}
+
+#[near]
+impl Contract {
+    /// Lowering the delay is itself timelocked, raising it applies immediately. At most `MAX_TIMELOCK_DELAY_NS`,
+    /// starting at `DEFAULT_TIMELOCK_DELAY_NS`.
+    pub fn set_timelock_delay(&mut self, delay_ns: U64) -> Option<u64> {
+        self.assert_owner();
+        require!(delay_ns.0 <= MAX_TIMELOCK_DELAY_NS, format!("timelock delay must be at most {} ns", MAX_TIMELOCK_DELAY_NS));
+        if delay_ns.0 >= self.timelock.delay_ns {
+            self.timelock.delay_ns = delay_ns.0;
+            return None;
+        }
+        Some(self.schedule_change(ConfigChange::TimelockDelay(delay_ns)))
+    }
+
+    pub fn execute_change(&mut self, id: u64) {
+        self.assert_owner();
+        let pending = self.pending_changes.get(&id).cloned().unwrap_or_else(|| env::panic_str("no such pending change"));
+        require!(env::block_timestamp() >= pending.executable_at.0, "change is still timelocked");
+        self.pending_changes.remove(&id);
+        match pending.change {
+            ConfigChange::AdminKey(key) => self.apply_admin_key_proposal(key),
+            ConfigChange::RecoveryKey(key) => self.apply_recovery_key(key),
+            ConfigChange::PlatformConfig { platform, config } => self.platform_mut(&platform).config = config,
+            ConfigChange::TimelockDelay(delay_ns) => self.timelock.delay_ns = delay_ns.0,
+            ConfigChange::AddVerifier(key) => self.apply_add_verifier(key),
+            ConfigChange::PlatformVerifier { platform, key } => self.apply_platform_verifier(&platform, key),
+            ConfigChange::PlatformOracle { platform, oracle_id } => self.platform_mut(&platform).oracle_id = oracle_id,
+            ConfigChange::NearSocialContract(account_id) => self.near_social_id = account_id,
+            ConfigChange::PlatformEnabled { platform, enabled } => self.platform_mut(&platform).enabled = enabled,
+        }
+    }
+
+    pub fn cancel_pending_change(&mut self, id: u64) {
+        self.assert_owner();
+        require!(self.pending_changes.remove(&id).is_some(), "no such pending change");
+    }
+
+    pub fn get_timelock_delay(&self) -> U64 {
+        self.timelock.delay_ns.into()
+    }
+
+    pub fn get_pending_changes(&self) -> Vec<PendingChange> {
+        self.pending_changes.values().cloned().collect()
+    }
+}
+
+impl Contract {
+    // stages `change`, returning the id to execute or cancel it with
+    pub(crate) fn schedule_change(&mut self, change: ConfigChange) -> u64 {
+        let id = self.timelock.next_id;
+        self.timelock.next_id += 1;
+        let now = env::block_timestamp();
+        let pending = PendingChange { id, change, proposed_at: now.into(), executable_at: now.saturating_add(self.timelock.delay_ns).into() };
+        env::log_str(&format!("scheduled config change {} executable at {}", id, pending.executable_at.0));
+        self.pending_changes.insert(id, pending);
+        id
+    }
+}
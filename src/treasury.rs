@@ -0,0 +1,32 @@
+use near_sdk::json_types::U128;
+use near_sdk::{env, near, require, AccountId, NearToken, Promise};
+
+use crate::{Contract, ContractExt};
+
+// the registration fee is charged on top of storage for every accepted registration and kept apart from
+// storage deposits, so withdrawing it never touches what backs stored data
+#[near]
+impl Contract {
+    /// In yoctoNEAR, 0 disables the fee.
+    pub fn set_fee(&mut self, fee: U128) {
+        self.assert_owner();
+        env::log_str(&format!("registration fee set to {}", fee.0));
+        self.registration_fee = fee.0;
+    }
+
+    pub fn get_fee(&self) -> U128 {
+        self.registration_fee.into()
+    }
+
+    pub fn get_treasury_balance(&self) -> U128 {
+        self.treasury.into()
+    }
+
+    pub fn withdraw_treasury(&mut self, amount: U128, to: AccountId) {
+        self.assert_owner();
+        require!(amount.0 <= self.treasury, "amount exceeds the treasury balance");
+        self.treasury -= amount.0;
+        env::log_str(&format!("withdrew {} from the treasury to {}", amount.0, to));
+        Promise::new(to).transfer(NearToken::from_yoctonear(amount.0)).detach();
+    }
+}
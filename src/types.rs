@@ -0,0 +1,790 @@
+//! Return types of the contract's views, shared with off-chain services and consumer contracts.
+//! Build with `default-features = false, features = ["types"]` to get these without the contract itself.
+
+use std::collections::BTreeMap;
+
+use near_sdk::json_types::Base64VecU8;
+use near_sdk::json_types::U64;
+use near_sdk::json_types::U128;
+use near_sdk::{near, AccountId, PublicKey};
+
+#[near(serializers = [borsh, json])]
+#[derive(Clone, Debug)]
+pub struct FailedAttempt {
+    pub platform: String,
+    pub code: String,
+    pub message: String,
+    pub timestamp: U64,
+}
+
+#[near(serializers = [json])]
+#[derive(Clone, Debug)]
+pub struct TestVector {
+    pub method: String,
+    pub message: String,
+    pub message_bytes: Vec<u8>,
+    // only populated in `dev` builds, signed with a well-known test key
+    pub signature: Option<Vec<u8>>,
+    pub public_key: Option<PublicKey>,
+}
+
+#[near(serializers = [borsh, json])]
+#[derive(Clone, Debug)]
+#[serde(default)]
+pub struct PlatformConfig {
+    // how long after `expiry_date` an attestation is "pending renewal" rather than expired. its handle stays
+    // reserved for the holder to renew meanwhile
+    pub grace_period_ns: U64,
+    // weight (percent) a pending renewal attestation still carries in scored checks
+    pub pending_weight_pct: u8,
+    // cap on concurrently valid attestations for scarce credentials, counted from when the cap is set
+    pub max_holders: Option<u32>,
+    // how long an attestation stays valid after it is issued
+    pub validity_ns: U64,
+    // whether an account may hold attestations for several handles at once, e.g. separate github accounts.
+    // otherwise registering a new handle replaces the old one
+    pub multi_handle: bool,
+    // how long a handle stays reserved for its last holder after it expires, is unregistered or revoked, before
+    // another account can claim it
+    pub release_cooldown_ns: U64,
+    // format registered handles must have, any handle is accepted without
+    pub handle_rules: Option<HandleRules>,
+    // accepts bonded claims that activate unless disputed, see `submit_claim`
+    pub optimistic: Option<OptimisticConfig>,
+    // longest validity a verifier may grant in the signed payload. without it the verifier can only shorten
+    // `validity_ns`
+    pub max_validity_ns: Option<U64>,
+}
+
+/// Terms of optimistic claims on a platform.
+#[near(serializers = [borsh, json])]
+#[derive(Clone, Debug)]
+pub struct OptimisticConfig {
+    pub bond: U128, // yoctoNEAR both the claimant and a disputing watcher put up
+    pub challenge_window_ns: U64,
+}
+
+/// A bonded claim waiting out its challenge window, see `submit_claim`.
+#[near(serializers = [borsh, json])]
+#[derive(Clone, Debug)]
+pub struct OptimisticClaim {
+    pub account_id: AccountId,
+    pub proof: String,
+    pub bond: U128,
+    pub challenge_ends_at: U64,
+    pub challenger: Option<AccountId>, // watcher who disputed it, having put up the same bond
+}
+
+/// Format a platform's handles are checked against at registration, after they are normalized.
+#[near(serializers = [borsh, json])]
+#[derive(Clone, Debug, Default)]
+#[serde(default)]
+pub struct HandleRules {
+    // every character the handle may contain, e.g. "abcdefghijklmnopqrstuvwxyz0123456789._". any if unset
+    pub charset: Option<String>,
+    // bounds on the length in characters, including the suffix
+    pub min_len: u32,
+    pub max_len: Option<u32>,
+    // e.g. ".lens"
+    pub required_suffix: Option<String>,
+}
+
+/// A platform attestations are registered for, added by the owner with `add_platform`.
+#[near(serializers = [borsh, json])]
+#[derive(Clone, Debug)]
+pub struct Platform {
+    pub id: String, // the identifier registrations and views refer to it by
+    pub display_name: String,
+    pub config: PlatformConfig, // validity period, handle rules and the rest, see `set_platform_config`
+    // when set, only this key's signatures are accepted for the platform instead of the contract's verifiers
+    pub verifier_key: Option<PublicKey>,
+    pub enabled: bool, // whether new registrations are accepted
+    pub oracle_id: Option<AccountId>, // contract confirming handles for `register_social_via_oracle`
+}
+
+/// Who may vouch and how much a vouch counts, see `vouch_for`.
+#[near(serializers = [borsh, json])]
+#[derive(Clone, Debug)]
+pub struct VouchConfig {
+    pub min_score: u32, // `score` an account needs to vouch
+    // stake at which a vouch counts fully, smaller stakes count proportionally. 0 counts every vouch fully
+    pub full_stake: U128,
+    pub max_per_day: u32, // vouches one account can give per day
+}
+
+impl Default for VouchConfig {
+    fn default() -> Self {
+        Self { min_score: 50, full_stake: U128(0), max_per_day: 5 }
+    }
+}
+
+/// Points each attribute adds to `score`, which is capped at 100. Each component's points can decay with the
+/// time since it was attested: to half after its half-life, a third after twice that and so on. A half-life of
+/// 0 turns decay off, and attributes attested before their time was recorded don't decay.
+#[near(serializers = [borsh, json])]
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[serde(default)]
+pub struct ScoreWeights {
+    pub keys_light: u32, // at least 5 access keys
+    pub keys_heavy: u32, // at least 20 access keys, on top of `keys_light`
+    pub age_six_months: u32,
+    pub age_one_year: u32, // on top of `age_six_months`
+    pub per_social: u32, // per valid social, reduced for ones pending renewal
+    pub per_vouch: u32, // per vouch received, regardless of its stake
+    // for an unexpired Passport score of `passport_full_score` hundredths, proportionally less below it
+    pub passport: u32,
+    pub passport_full_score: u32,
+    pub keys_half_life_ns: U64,
+    pub age_half_life_ns: U64,
+    pub social_half_life_ns: U64, // from when the attestation was issued
+    pub vouch_half_life_ns: U64,
+}
+
+impl Default for ScoreWeights {
+    fn default() -> Self {
+        Self {
+            keys_light: 20,
+            keys_heavy: 10,
+            age_six_months: 20,
+            age_one_year: 10,
+            per_social: 20,
+            per_vouch: 0,
+            passport: 20,
+            passport_full_score: 2000,
+            keys_half_life_ns: U64(0),
+            age_half_life_ns: U64(0),
+            social_half_life_ns: U64(0),
+            vouch_half_life_ns: U64(0),
+        }
+    }
+}
+
+/// A condition on an account's attested attributes, see `set_rule`. E.g. six months old, connected to lens or
+/// farcaster and at least 5 access keys is
+/// `{"All": [{"MinAccountAgeNs": "15552000000000000"}, {"Any": [{"Platform": "lens"}, {"Platform": "farcaster"}]}, {"MinAccessKeys": 5}]}`.
+#[near(serializers = [borsh, json])]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RuleExpr {
+    All(Vec<RuleExpr>),
+    Any(Vec<RuleExpr>),
+    Not(Box<RuleExpr>),
+    MinAccessKeys(u32),
+    MinAccountAgeNs(U64),
+    // an active attestation on the platform, one pending renewal doesn't count
+    Platform(String),
+    MinScore(u32),
+    MinVouches(u32),
+}
+
+#[near(serializers = [borsh, json])]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Badge {
+    pub rule_id: String, // the rule the account satisfied to claim it
+    pub granted_at: U64,
+}
+
+/// A Gitcoin Passport score the verifier attested, see `import_passport_score`.
+#[near(serializers = [borsh, json])]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PassportScore {
+    pub score: u32, // hundredths
+    pub issued_at: U64,
+    pub expires_at: U64,
+}
+
+/// A World ID proof as the verifier backend checked it, see `verify_world_id`.
+#[near(serializers = [json])]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WorldIdProof {
+    pub nullifier_hash: String, // 0x-prefixed 32 byte hex, unique per human for this app's action
+    pub merkle_root: String, // 0x-prefixed 32 byte hex, the World ID identity set the proof is against
+    pub verification_level: String, // `orb` or `device`
+}
+
+/// A World ID the verifier attested for an account, see `verify_world_id`.
+#[near(serializers = [borsh, json])]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WorldId {
+    pub nullifier_hash: String,
+    pub verification_level: String,
+    pub issued_at: U64,
+}
+
+/// A typed attestation, see `add_stamp` and `get_stamps`.
+#[near(serializers = [borsh, json])]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Stamp {
+    pub stamp_type: String,
+    pub value: String,
+    // `None` for the built-in types
+    pub issuer: Option<StampIssuer>,
+    pub issued_at: U64,
+    pub expires_at: Option<U64>,
+}
+
+/// Who issued a stamp.
+#[near(serializers = [borsh, json])]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum StampIssuer {
+    // the verifier key that signed it, see `add_stamp`
+    Verifier(PublicKey),
+    // an account holding the schema's `issuer_role`, see `issue_stamp`
+    Account(AccountId),
+}
+
+/// What values of a stamp type look like and who may issue them, see `set_stamp_schema`.
+#[near(serializers = [borsh, json])]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StampSchema {
+    pub value_kind: StampValueKind,
+    // stamps must expire at most this long after they are issued, `None` allows stamps that never expire
+    pub max_validity_ns: Option<U64>,
+    // `None` for stamps signed by a verifier key, otherwise only accounts holding the role issue them
+    pub issuer_role: Option<Role>,
+}
+
+#[near(serializers = [borsh, json])]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StampValueKind {
+    Text,
+    Integer,
+    Boolean,
+    Timestamp, // nanoseconds
+}
+
+/// How `qf_weight` maps scores to matching weights.
+#[near(serializers = [borsh, json])]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum QfCurve {
+    Linear,
+    // square root of the score, so each further point adds less weight
+    Sqrt,
+}
+
+#[near(serializers = [borsh, json])]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct QfConfig {
+    pub curve: QfCurve,
+    pub min_score: u32, // scores below get no weight
+    pub max_weight: U128, // the weight of a full score
+}
+
+impl Default for QfConfig {
+    fn default() -> Self {
+        Self { curve: QfCurve::Sqrt, min_score: 20, max_weight: U128(1_000_000) }
+    }
+}
+
+/// What `is_human` requires of an account.
+#[near(serializers = [borsh, json])]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum HumanPolicy {
+    MinScore(u32),
+    Rule(String), // a rule id, see `set_rule`
+}
+
+impl Default for HumanPolicy {
+    fn default() -> Self {
+        HumanPolicy::MinScore(50)
+    }
+}
+
+/// Where and as which classes `mint_sbt` issues soulbound tokens.
+#[near(serializers = [borsh, json])]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SbtConfig {
+    pub registry: AccountId, // a NEP-393 registry this contract is an authorized issuer on
+    pub classes: Vec<(String, u64)>, // rule id -> the class minted for it
+}
+
+/// The result of a paid check, see `request_attestation`.
+#[near(serializers = [json])]
+#[derive(Clone, Debug)]
+pub struct AttestationReceipt {
+    pub receipt_id: u64, // sequential across all receipts
+    pub consumer: AccountId, // who paid for it
+    pub account_id: AccountId,
+    pub check: String,
+    pub result: bool,
+    pub fee: U128,
+    pub block_height: U64,
+    pub timestamp: U64,
+}
+
+/// Where badges and tiers are minted as NFTs, see `set_nft_badge_config`.
+#[near(serializers = [borsh, json])]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NftBadgeConfig {
+    pub collection: AccountId, // a NEP-171 contract this contract may `nft_mint` on
+    pub mint_deposit: U128, // attached to each mint from the treasury
+    pub badges: bool, // mint on `claim_badge`
+    pub tiers: bool, // mint on reaching a tier
+}
+
+/// An NFT minted to an account for a badge or tier.
+#[near(serializers = [borsh, json])]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MintedNft {
+    pub source: String, // `badge:<rule_id>` or `tier:<name>`
+    pub token_id: String,
+    pub minted_at: U64,
+}
+
+/// The MPC signer `request_evm_attestation` asks for signatures, see `set_mpc_config`.
+#[near(serializers = [borsh, json])]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MpcConfig {
+    pub signer: AccountId, // the chain signatures contract, e.g. `v1.signer`
+    pub path: String, // derivation path of this contract's key, fixes the EVM address attestations recover to
+    pub key_version: u32,
+}
+
+/// What an attestation exported to an EVM chain states. Signed as the EIP-191 digest of
+/// `sybil_attestation,<contract>,<account>,<evm address>,<chain id>,<score>,<is human>,<nonce>,<issued at>`.
+#[near(serializers = [borsh, json])]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EvmAttestation {
+    pub account_id: AccountId,
+    pub evm_address: String, // linked to the account, lowercase
+    pub chain_id: U64,
+    pub score: u32,
+    pub is_human: bool,
+    pub nonce: U64, // increases per account, for consumers rejecting replays
+    pub issued_at: U64,
+}
+
+/// An `EvmAttestation` with the MPC signer's secp256k1 signature over it.
+#[near(serializers = [borsh, json])]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SignedEvmAttestation {
+    pub attestation: EvmAttestation,
+    pub big_r: String, // hex, compressed point
+    pub s: String, // hex scalar
+    pub recovery_id: u8,
+}
+
+/// A rung of the tier ladder, see `set_tiers`.
+#[near(serializers = [borsh, json])]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Tier {
+    pub name: String,
+    pub min_score: u32,
+}
+
+#[near(serializers = [borsh, json])]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CachedScore {
+    pub score: u32,
+    pub computed_at: U64,
+}
+
+#[near(serializers = [borsh, json])]
+#[derive(Clone, Debug)]
+pub struct Vouch {
+    pub voucher: AccountId,
+    pub stake: U128, // yoctoNEAR attached, refunded by `revoke_vouch`
+    pub vouched_at: U64,
+}
+
+/// What confirmed an attestation.
+#[near(serializers = [borsh, json])]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Provenance {
+    // a signature by the platform's verifier key or one of the contract's verifiers, the key if it was recorded
+    Verifier(Option<PublicKey>),
+    // the platform's oracle contract, see `register_social_via_oracle`
+    Oracle(AccountId),
+    // a bonded claim that went undisputed or was upheld, see `submit_claim`
+    Optimistic,
+    // read by the contract itself from the account's NEAR Social profile, see `verify_near_social`
+    NearSocial,
+}
+
+/// NEAR locked behind a verifier key, slashable through disputes, see `bond_verifier`.
+#[near(serializers = [borsh, json])]
+#[derive(Clone, Debug)]
+pub struct VerifierBond {
+    pub account_id: AccountId, // who bonded it and can withdraw it
+    pub amount: U128,
+    // when `request_unbond` was called, the key counts as unbonded from then on
+    pub unbonding_since: Option<U64>,
+}
+
+#[near(serializers = [borsh, json])]
+#[derive(Clone, Debug)]
+pub struct VerifierBondConfig {
+    // bond a verifier key needs for its signatures to be accepted, 0 accepts unbonded keys. the admin key is exempt.
+    pub min_bond: U128,
+    // share of the bond, in percent, a disputer receives when an attestation the key signed is revoked
+    pub slash_pct: u32,
+}
+
+impl Default for VerifierBondConfig {
+    fn default() -> Self {
+        Self { min_bond: U128(0), slash_pct: 50 }
+    }
+}
+
+/// Validity of attestations on platforms without their own `validity_ns`, 90 days.
+pub const DEFAULT_VALIDITY_NS: u64 = 3 * 30 * 24 * 60 * 60 * 1_000_000_000;
+
+impl Default for PlatformConfig {
+    fn default() -> Self {
+        Self { grace_period_ns: U64(0), pending_weight_pct: 0, max_holders: None, validity_ns: U64(DEFAULT_VALIDITY_NS), multi_handle: false, release_cooldown_ns: U64(0), handle_rules: None, optimistic: None, max_validity_ns: None }
+    }
+}
+
+/// Maximum lengths in bytes of the user supplied fields of a registration, and how many platforms a
+/// single account can hold attestations for.
+#[near(serializers = [borsh, json])]
+#[derive(Clone, Debug)]
+pub struct InputLimits {
+    pub max_platform_len: u32,
+    pub max_handle_len: u32,
+    pub max_proof_len: u32,
+    pub max_platforms_per_account: u32,
+}
+
+impl Default for InputLimits {
+    fn default() -> Self {
+        Self { max_platform_len: 32, max_handle_len: 64, max_proof_len: 256, max_platforms_per_account: 32 }
+    }
+}
+
+/// NEP-145 storage balance of an account.
+#[near(serializers = [json])]
+#[derive(Clone, Debug)]
+pub struct StorageBalance {
+    pub total: U128,
+    // part of `total` not backing stored data, withdrawable with `storage_withdraw`
+    pub available: U128,
+}
+
+#[near(serializers = [json])]
+#[derive(Clone, Debug)]
+pub struct StorageBalanceBounds {
+    pub min: U128,
+    pub max: Option<U128>,
+}
+
+/// Why an account stopped holding a handle.
+#[near(serializers = [borsh, json])]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ReleaseReason {
+    // the account registered another handle on a single handle platform
+    Replaced,
+    Unregistered,
+    Revoked,
+    // registered by another account after the attestation expired
+    TakenOver,
+    ForceReleased,
+    // moved to another account with `transfer_handle`
+    Transferred,
+    // moved to the primary account with `merge_into`, or dropped there for a conflicting handle
+    Merged,
+    // removed by `purge_expired_handles` once past its grace period and cooldown
+    Expired,
+}
+
+#[near(serializers = [borsh, json])]
+#[derive(Clone, Debug)]
+pub struct HandleRecord {
+    pub platform: String,
+    pub handle: String,
+    pub held_from: U64,
+    pub held_until: U64,
+    pub reason_released: ReleaseReason,
+}
+
+/// A contested attestation waiting for an operator, see `file_dispute`.
+#[near(serializers = [borsh, json])]
+#[derive(Clone, Debug)]
+pub struct Dispute {
+    pub id: u64,
+    pub account_id: AccountId,
+    pub platform: String,
+    pub evidence_uri: String,
+    pub disputer: AccountId,
+    pub bond: U128,
+    pub filed_at: U64,
+}
+
+/// Where an account's attestation on a platform stands, see `get_social_status`.
+#[near(serializers = [json])]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SocialStatus {
+    NotRegistered,
+    Active,
+    // expired but within the platform's `grace_period_ns`, renewable with the handle still reserved
+    InGrace,
+    Expired,
+}
+
+#[near(serializers = [json])]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DisputeOutcome {
+    // the attestation is revoked, the disputer gets the bond back and a slash of the signing verifier's bond
+    Revoke,
+    // the attestation stands and the bond goes to the treasury
+    Dismiss,
+}
+
+#[near(serializers = [borsh, json])]
+#[derive(Clone, Debug)]
+pub struct Revocation {
+    pub handles: Vec<String>,
+    pub reason: String,
+    pub revoked_at: U64,
+}
+
+/// Why an attestation was pulled, so consumers can penalize fraud without penalizing e.g. deleted accounts.
+#[near(serializers = [borsh, json])]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RevocationCode {
+    Fraud,
+    AccountDeleted,
+    // revoked by resolving a dispute against it
+    Disputed,
+    Other,
+}
+
+/// An entry of the revocation ledger, one per revoked handle.
+#[near(serializers = [borsh, json])]
+#[derive(Clone, Debug)]
+pub struct RevocationRecord {
+    pub account_id: AccountId,
+    pub platform: String,
+    pub handle: String,
+    pub code: RevocationCode,
+    pub reason: String,
+    pub revoked_at: U64,
+}
+
+#[near(serializers = [borsh, json])]
+#[derive(Clone, Debug)]
+pub struct Ban {
+    pub reason: String,
+    pub banned_at: U64,
+}
+
+#[near(serializers = [borsh, json])]
+#[derive(Clone, Debug)]
+pub struct LinkedAddress {
+    pub chain: String,
+    // base58 encoded ed25519 public key, the native address format on solana
+    pub address: String,
+    pub linked_at: U64,
+}
+
+/// An account's record as `get_user_data` returns it.
+#[near(serializers = [json])]
+#[derive(Clone, Debug)]
+pub struct UserData {
+    // access key count, account creation timestamp and every other attested attribute, see `get_stamps`
+    pub stamps: Vec<Stamp>,
+    // platform -> handles the account holds attestations for, expired ones included
+    pub socials: BTreeMap<String, Vec<String>>,
+    pub nfts: Vec<MintedNft>,
+    // self-set, not attested by anyone, see `set_profile_metadata`
+    pub metadata: BTreeMap<String, String>,
+}
+
+/// An account's full record as `get_account_details` returns it to authorized consumers.
+#[near(serializers = [json])]
+#[derive(Clone, Debug)]
+pub struct AccountDetails {
+    pub access_keys: Option<u32>,
+    pub created_at: Option<U64>,
+    pub socials: Vec<SocialDetails>,
+    pub evm_addresses: Vec<String>,
+    pub ed25519_addresses: Vec<LinkedAddress>,
+    pub passport_score: Option<PassportScore>,
+    pub world_id: Option<WorldId>,
+    pub metadata: BTreeMap<String, String>,
+}
+
+/// When the account's attributes were last attested, `None` for ones attested before this was recorded.
+#[near(serializers = [borsh, json])]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct AttributeUpdates {
+    pub access_keys: Option<U64>,
+    pub account_age: Option<U64>,
+}
+
+#[near(serializers = [json])]
+#[derive(Clone, Debug)]
+pub struct ExpiringSocial {
+    pub platform: String,
+    pub handle: String,
+    pub expires_at: U64,
+}
+
+#[near(serializers = [json])]
+#[derive(Clone, Debug)]
+pub struct SocialDetails {
+    pub platform: String,
+    pub handle: String,
+    // hex sha256 of the proof, the proof itself is only logged at registration
+    pub proof_digest: String,
+    pub proof_type: String,
+    pub issued_at: U64,
+    pub expires_at: U64,
+    pub provenance: Provenance,
+}
+
+#[near(serializers = [json])]
+#[derive(Clone, Debug)]
+pub struct OperatorDashboard {
+    // when the verifier backend last had an attestation accepted, 0 if never
+    pub last_issued_at: U64,
+    pub issued_today: u32,
+    pub account_balance: U128,
+    pub storage_usage: U64,
+    // bytes that can still be stored before the balance no longer covers storage staking
+    pub storage_headroom: U64,
+    pub treasury_balance: U128,
+    // registrations stay blocked until an operator calls `reset_circuit_breaker`
+    pub circuit_breaker_tripped: bool,
+    // the queues waiting on the operator: disputes to resolve and timelocked changes to execute or cancel
+    pub open_disputes: u32,
+    pub pending_changes: u32,
+}
+
+/// Ceiling on registrations across all accounts, see `set_circuit_breaker`.
+#[near(serializers = [borsh, json])]
+#[derive(Clone, Debug)]
+pub struct CircuitBreakerConfig {
+    pub window_ns: U64,
+    pub max_registrations: u32,
+}
+
+#[near(serializers = [json])]
+#[derive(Clone, Debug)]
+pub struct CircuitBreakerStatus {
+    pub config: Option<CircuitBreakerConfig>,
+    pub tripped: bool,
+    // registrations in the last `window_ns`, estimated from the current and previous window
+    pub recent_registrations: u32,
+}
+
+#[near(serializers = [json])]
+#[derive(Clone, Debug)]
+pub struct Stats {
+    // accounts holding at least one attestation
+    pub accounts: u32,
+    pub handles: u32,
+    pub registrations: U64,
+    pub revocations: U64,
+    // registrations in the `days` requested
+    pub recent_registrations: U64,
+    pub platforms: Vec<PlatformStats>,
+}
+
+#[near(serializers = [json])]
+#[derive(Clone, Debug)]
+pub struct PlatformStats {
+    pub platform: String,
+    pub registrations: U64,
+    pub revocations: U64,
+}
+
+/// Merkle root over the accounts that met `check` as of `block_height`, see `commit_snapshot`.
+#[near(serializers = [borsh, json])]
+#[derive(Clone, Debug)]
+pub struct MerkleSnapshot {
+    pub check: String,
+    pub root: Base64VecU8,
+    pub leaf_count: u32,
+    pub block_height: U64,
+}
+
+/// Which accounts satisfied `rule_id` as of `block_height`, see `commit_rule_snapshot` and `passed_at_snapshot`.
+#[near(serializers = [borsh, json])]
+#[derive(Clone, Debug)]
+pub struct RuleSnapshot {
+    pub rule_id: String,
+    pub block_height: U64,
+    pub timestamp: U64,
+    pub passed: u32, // accounts that satisfied the rule
+    pub complete: bool,
+    // merkle root over the passing accounts once complete, built like a `MerkleSnapshot`
+    pub root: Option<Base64VecU8>,
+}
+
+#[near(serializers = [json])]
+#[derive(Clone, Debug)]
+pub struct AdminKeys {
+    pub current: PublicKey,
+    pub pending: Option<PublicKey>,
+    // replaced key, still accepted until `previous_valid_until`
+    pub previous: Option<PublicKey>,
+    pub previous_valid_until: U64,
+    pub recovery: Option<PublicKey>,
+}
+
+// flags of the entrypoints `set_paused_methods` can pause individually
+pub const PAUSE_REGISTER_SOCIAL: u32 = 1 << 0;
+pub const PAUSE_REGISTER_SOCIAL_RELAYED: u32 = 1 << 1;
+pub const PAUSE_UPDATE_ACCESS_KEY: u32 = 1 << 2;
+pub const PAUSE_UPDATE_CONTRACT_AGE: u32 = 1 << 3;
+pub const PAUSE_LINK_EVM_ADDRESS: u32 = 1 << 4;
+pub const PAUSE_LINK_ED25519_ADDRESS: u32 = 1 << 5;
+pub const PAUSE_STORAGE_UNREGISTER: u32 = 1 << 6;
+pub const PAUSE_RENEW_SOCIAL: u32 = 1 << 7;
+pub const PAUSE_TRANSFER_HANDLE: u32 = 1 << 8;
+pub const PAUSE_REGISTER_SOCIAL_ORACLE: u32 = 1 << 9;
+pub const PAUSE_SUBMIT_CLAIM: u32 = 1 << 10;
+pub const PAUSE_VERIFY_NEAR_SOCIAL: u32 = 1 << 11;
+pub const PAUSE_VOUCH: u32 = 1 << 12;
+pub const PAUSE_SET_PROFILE_METADATA: u32 = 1 << 13;
+pub const PAUSE_DELETE_MY_DATA: u32 = 1 << 14;
+pub const PAUSE_CLAIM_BADGE: u32 = 1 << 15;
+pub const PAUSE_IMPORT_PASSPORT_SCORE: u32 = 1 << 16;
+pub const PAUSE_ADD_STAMP: u32 = 1 << 17;
+pub const PAUSE_MINT_SBT: u32 = 1 << 18;
+pub const PAUSE_EXPORT_ATTESTATION: u32 = 1 << 19;
+pub const PAUSE_VERIFY_WORLD_ID: u32 = 1 << 20;
+pub const PAUSE_UNREGISTER_SOCIAL: u32 = 1 << 21;
+pub const PAUSE_DISPUTE_CLAIM: u32 = 1 << 22;
+pub const PAUSE_FINALIZE_CLAIM: u32 = 1 << 23;
+pub const PAUSE_LINK_ACCOUNT: u32 = 1 << 24;
+pub const PAUSE_MERGE_INTO: u32 = 1 << 25;
+pub const PAUSE_FILE_DISPUTE: u32 = 1 << 26;
+pub const PAUSE_BOND_VERIFIER: u32 = 1 << 27;
+pub const PAUSE_VERIFY_FOR: u32 = 1 << 28;
+pub const PAUSE_REQUEST_ATTESTATION: u32 = 1 << 29;
+pub const PAUSE_REPORT_FAILED_ATTEMPT: u32 = 1 << 30;
+
+/// Owner: configuration and upgrades. Verifier: accounts of the signing services, acting on attestations.
+/// Operator: maintenance such as garbage collection.
+#[near(serializers = [borsh, json])]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Role {
+    Owner,
+    Verifier,
+    Operator,
+}
+
+/// A sensitive configuration change staged behind the timelock.
+#[near(serializers = [borsh, json])]
+#[derive(Clone, Debug)]
+pub enum ConfigChange {
+    AdminKey(PublicKey),
+    RecoveryKey(PublicKey),
+    PlatformConfig { platform: String, config: PlatformConfig },
+    TimelockDelay(U64),
+    AddVerifier(PublicKey),
+    PlatformVerifier { platform: String, key: Option<PublicKey> },
+    PlatformOracle { platform: String, oracle_id: Option<AccountId> },
+    NearSocialContract(AccountId),
+    PlatformEnabled { platform: String, enabled: bool },
+}
+
+#[near(serializers = [borsh, json])]
+#[derive(Clone, Debug)]
+pub struct PendingChange {
+    pub id: u64,
+    pub change: ConfigChange,
+    pub proposed_at: U64,
+    pub executable_at: U64,
+}
@@ -0,0 +1,116 @@
+use near_sdk::serde_json::json;
+use near_sdk::{env, near, require, AccountId, NearToken, Promise, PublicKey};
+
+use crate::dashboard::DAY_NS;
+use crate::keys::VerifyingKey;
+use crate::{events, messages, Contract, ContractExt, FunctionError, Provenance, VerifierBond, VerifierBondConfig, PAUSE_BOND_VERIFIER};
+
+// a bond stays slashable this long after `request_unbond`, enough to dispute what the key signed last
+const UNBONDING_PERIOD_NS: u64 = 7 * DAY_NS;
+
+// verifier services lock NEAR behind their keys. with a `min_bond` set only bonded keys are trusted, and an
+// attestation revoked through a dispute costs the key that signed it part of its bond.
+#[near]
+impl Contract {
+    /// Adds the attached deposit to the bond of `key`, which proves the caller holds it by signing
+    /// `"bond_verifier,<contract_id>,<account_id>"`. The first account to bond a key is the only one that can add
+    /// to or withdraw it. The storage the bond takes is paid from that account's storage balance.
+    #[payable]
+    pub fn bond_verifier(&mut self, key: PublicKey, signature: Vec<u8>) {
+        self.ensure_not_paused(PAUSE_BOND_VERIFIER).unwrap_or_else(|err| err.panic());
+        let account_id = env::predecessor_account_id();
+        let amount = env::attached_deposit().as_yoctonear();
+        require!(amount > 0, "attach the NEAR to bond");
+        let message = messages::bond_verifier_message(&env::current_account_id(), &account_id);
+        if let Err(err) = VerifyingKey::new(key.clone()).and_then(|verifying_key| verifying_key.verify(message.as_bytes(), &signature)) {
+            err.panic();
+        }
+        let mut bond = self.verifier_bonds.get(&key).cloned().unwrap_or(VerifierBond { account_id: account_id.clone(), amount: 0.into(), unbonding_since: None });
+        require!(bond.account_id == account_id, "key is bonded by another account");
+        require!(bond.unbonding_since.is_none(), "bond is unbonding");
+        bond.amount = (bond.amount.0 + amount).into();
+        let usage_before = env::storage_usage();
+        events::emit("verifier_bonded", json!({ "key": key, "account_id": account_id, "amount": bond.amount }));
+        self.verifier_bonds.insert(key, bond);
+        self.settle_storage_from(&account_id, usage_before, 0).unwrap_or_else(|err| err.panic());
+    }
+
+    /// Stops the key counting as bonded. The bond can be withdrawn with `withdraw_bond` after the unbonding
+    /// period and can be slashed until then.
+    pub fn request_unbond(&mut self, key: PublicKey) {
+        let mut bond = self.bond_of_caller(&key);
+        require!(bond.unbonding_since.is_none(), "bond is already unbonding");
+        bond.unbonding_since = Some(env::block_timestamp().into());
+        events::emit("verifier_unbonding", json!({ "key": key, "account_id": bond.account_id }));
+        self.verifier_bonds.insert(key, bond);
+    }
+
+    pub fn withdraw_bond(&mut self, key: PublicKey) {
+        let bond = self.bond_of_caller(&key);
+        let since = bond.unbonding_since.unwrap_or_else(|| env::panic_str("request_unbond first"));
+        require!(env::block_timestamp() >= since.0 + UNBONDING_PERIOD_NS, "bond is still unbonding");
+        self.flush_collections();
+        let usage_before = env::storage_usage();
+        self.verifier_bonds.remove(&key);
+        self.flush_collections();
+        self.release_storage(&bond.account_id, usage_before.saturating_sub(env::storage_usage()));
+        if bond.amount.0 > 0 {
+            Promise::new(bond.account_id.clone()).transfer(NearToken::from_yoctonear(bond.amount.0)).detach();
+        }
+        events::emit("verifier_bond_withdrawn", json!({ "key": key, "account_id": bond.account_id, "amount": bond.amount }));
+    }
+
+    pub fn get_verifier_bond(&self, key: PublicKey) -> Option<VerifierBond> {
+        self.verifier_bonds.get(&key).cloned()
+    }
+
+    pub fn set_verifier_bond_config(&mut self, config: VerifierBondConfig) {
+        self.assert_owner();
+        require!(config.slash_pct <= 100, "slash_pct must be at most 100");
+        self.verifier_bond_config = config;
+    }
+
+    pub fn get_verifier_bond_config(&self) -> VerifierBondConfig {
+        self.verifier_bond_config.clone()
+    }
+}
+
+impl Contract {
+    fn bond_of_caller(&self, key: &PublicKey) -> VerifierBond {
+        let bond = self.verifier_bonds.get(key).cloned().unwrap_or_else(|| env::panic_str("key is not bonded"));
+        require!(bond.account_id == env::predecessor_account_id(), "only the account that bonded the key can do this");
+        bond
+    }
+
+    // whether signatures by a verifier key other than the admin key are accepted
+    pub(crate) fn is_bonded(&self, key: &PublicKey) -> bool {
+        let min_bond = self.verifier_bond_config.min_bond.0;
+        min_bond == 0 || self.verifier_bonds.get(key).is_some_and(|bond| bond.unbonding_since.is_none() && bond.amount.0 >= min_bond)
+    }
+
+    // pays `disputer` the configured share of the bond of every key that signed one of the account's
+    // attestations on `platform`
+    pub(crate) fn slash_signers(&mut self, account_id: &AccountId, platform: &str, disputer: &AccountId) {
+        let mut signers: Vec<PublicKey> = Vec::new();
+        for social in self.socials_on(account_id, platform) {
+            if let Provenance::Verifier(Some(key)) = social.provenance {
+                if !signers.contains(&key) {
+                    signers.push(key);
+                }
+            }
+        }
+        for key in signers {
+            let Some(mut bond) = self.verifier_bonds.get(&key).cloned() else {
+                continue;
+            };
+            let slashed = bond.amount.0 * u128::from(self.verifier_bond_config.slash_pct) / 100;
+            if slashed == 0 {
+                continue;
+            }
+            bond.amount = (bond.amount.0 - slashed).into();
+            self.verifier_bonds.insert(key.clone(), bond);
+            Promise::new(disputer.clone()).transfer(NearToken::from_yoctonear(slashed)).detach();
+            events::emit("verifier_slashed", json!({ "key": key, "amount": slashed.to_string(), "disputer": disputer }));
+        }
+    }
+}
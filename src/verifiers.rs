@@ -0,0 +1,47 @@
+use near_sdk::{env, near, require, PublicKey};
+
+use crate::{ConfigChange, Contract, ContractExt, FunctionError};
+
+// verifier keys besides the admin key, e.g. redundant signing services in other regions
+const MAX_VERIFIERS: usize = 10;
+
+#[near]
+impl Contract {
+    /// Stages the addition behind the timelock, returning the pending change id.
+    pub fn add_verifier(&mut self, key: PublicKey) -> u64 {
+        self.assert_owner();
+        if let Err(err) = self.signing_key(key.clone()) {
+            err.panic();
+        }
+        self.schedule_change(ConfigChange::AddVerifier(key))
+    }
+
+    /// Applies immediately, so a leaked key can be retired without waiting out the timelock. The admin key
+    /// can't be removed, only rotated.
+    pub fn remove_verifier(&mut self, key: PublicKey) {
+        self.assert_owner();
+        let count = self.verifiers.len();
+        self.verifiers.retain(|verifier| verifier.public_key() != &key);
+        require!(self.verifiers.len() < count, "not a verifier key");
+        env::log_str(&format!("removed verifier {}", String::from(&key)));
+    }
+
+    /// Every key whose signatures are currently accepted, the admin key first.
+    pub fn get_verifiers(&self) -> Vec<PublicKey> {
+        std::iter::once(&self.admin_pub).chain(&self.verifiers).map(|key| key.public_key().clone()).collect()
+    }
+}
+
+impl Contract {
+    pub(crate) fn apply_add_verifier(&mut self, key: PublicKey) {
+        let key = self.signing_key(key).unwrap_or_else(|err| err.panic());
+        require!(key != self.admin_pub && !self.verifiers.contains(&key), "already a verifier key");
+        require!(self.verifiers.len() < MAX_VERIFIERS, format!("at most {} verifiers besides the admin key", MAX_VERIFIERS));
+        self.verifiers.push(key);
+    }
+
+    pub(crate) fn is_verifier(&self, key: &PublicKey) -> bool {
+        self.admin_pub.public_key() == key || self.verifiers.iter().any(|verifier| verifier.public_key() == key)
+    }
+
+}
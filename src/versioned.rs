@@ -0,0 +1,45 @@
+use near_sdk::near;
+
+use crate::{Provenance, SocialData};
+
+/// Stored form of `SocialData`. Adding a field means adding a variant holding the new struct and upgrading
+/// the older variants on read below, so existing records never need a whole-state migration.
+#[near(serializers = [borsh])]
+#[derive(Clone)]
+pub enum VersionedSocialData {
+    V1(SocialDataV1),
+    V2(SocialData),
+}
+
+// before provenance was recorded, every attestation was verifier signed
+#[near(serializers = [borsh])]
+#[derive(Clone)]
+pub struct SocialDataV1 {
+    issued_date: u64,
+    handle: String,
+    proof_digest: [u8; 32],
+    proof_type: String,
+    expiry_date: u64,
+}
+
+impl From<VersionedSocialData> for SocialData {
+    fn from(versioned: VersionedSocialData) -> Self {
+        match versioned {
+            VersionedSocialData::V1(data) => SocialData {
+                issued_date: data.issued_date,
+                handle: data.handle,
+                proof_digest: data.proof_digest,
+                proof_type: data.proof_type,
+                expiry_date: data.expiry_date,
+                provenance: Provenance::Verifier(None),
+            },
+            VersionedSocialData::V2(data) => data,
+        }
+    }
+}
+
+impl From<SocialData> for VersionedSocialData {
+    fn from(data: SocialData) -> Self {
+        VersionedSocialData::V2(data)
+    }
+}
@@ -0,0 +1,123 @@
+use near_sdk::serde_json::json;
+use near_sdk::{env, near, require, AccountId, NearToken, Promise};
+
+use crate::dashboard::DAY_NS;
+use crate::score::MAX_SCORE;
+use crate::{events, Contract, ContractExt, FunctionError, Vouch, VouchConfig, PAUSE_VOUCH};
+
+// vouches an account can receive, which also bounds the work `trust_score` does per hop
+const MAX_VOUCHES_RECEIVED: usize = 20;
+// share of its standing, in percent, a fully staked vouch passes on
+const VOUCH_SHARE_PCT: u32 = 20;
+// hops `trust_score` follows past the direct vouchers
+const TRUST_DEPTH: u32 = 1;
+
+#[near(serializers = [borsh])]
+#[derive(Clone, Default)]
+pub struct VouchActivity {
+    day: u64, // days since epoch `given_today` counts
+    given_today: u32,
+}
+
+// accounts that are verified themselves vouch for others, staking the attached deposit on it. the graph is
+// stored by vouchee, so the views and `trust_score` only load the vouches they follow.
+#[near]
+impl Contract {
+    /// Vouches for `account_id`, staking the attached deposit. The caller needs a `score` of at least the
+    /// config's `min_score` and is limited to `max_per_day` vouches. The storage the vouch takes is paid from
+    /// the caller's storage balance.
+    #[payable]
+    pub fn vouch_for(&mut self, account_id: AccountId) {
+        self.ensure_not_paused(PAUSE_VOUCH).unwrap_or_else(|err| err.panic());
+        let voucher = env::predecessor_account_id();
+        require!(voucher != account_id, "cannot vouch for yourself");
+        self.ensure_not_banned(&voucher).unwrap_or_else(|err| err.panic());
+        let config = self.vouch_config.clone();
+        require!(self.compute_score(&voucher, env::block_timestamp()) >= config.min_score, "score is too low to vouch");
+        let mut received = self.vouches.get(&account_id).cloned().unwrap_or_default();
+        require!(received.iter().all(|vouch| vouch.voucher != voucher), "already vouched for this account");
+        require!(received.len() < MAX_VOUCHES_RECEIVED, "account has the maximum number of vouches");
+        let today = env::block_timestamp() / DAY_NS;
+        let mut activity = self.vouch_activity.get(&voucher).cloned().unwrap_or_default();
+        if activity.day != today {
+            activity = VouchActivity { day: today, given_today: 0 };
+        }
+        require!(activity.given_today < config.max_per_day, "daily vouch limit reached");
+        activity.given_today += 1;
+        let usage_before = env::storage_usage();
+        let stake = env::attached_deposit().as_yoctonear();
+        received.push(Vouch { voucher: voucher.clone(), stake: stake.into(), vouched_at: env::block_timestamp().into() });
+        self.vouches.insert(account_id.clone(), received);
+        self.vouch_activity.insert(voucher.clone(), activity);
+        self.refresh_cached_score(&account_id);
+        events::emit("vouched", json!({ "voucher": voucher, "account_id": account_id, "stake": stake.to_string() }));
+        self.settle_storage_from(&voucher, usage_before, 0).unwrap_or_else(|err| err.panic());
+    }
+
+    /// Withdraws the caller's vouch for `account_id`, refunding its stake and storage.
+    pub fn revoke_vouch(&mut self, account_id: AccountId) {
+        let voucher = env::predecessor_account_id();
+        let mut received = self.vouches.get(&account_id).cloned().unwrap_or_default();
+        let index = received.iter().position(|vouch| vouch.voucher == voucher).unwrap_or_else(|| env::panic_str("no vouch for this account"));
+        self.flush_collections();
+        let usage_before = env::storage_usage();
+        let vouch = received.remove(index);
+        if received.is_empty() {
+            self.vouches.remove(&account_id);
+        } else {
+            self.vouches.insert(account_id.clone(), received);
+        }
+        self.flush_collections();
+        self.release_storage(&voucher, usage_before.saturating_sub(env::storage_usage()));
+        if vouch.stake.0 > 0 {
+            Promise::new(voucher.clone()).transfer(NearToken::from_yoctonear(vouch.stake.0)).detach();
+        }
+        self.refresh_cached_score(&account_id);
+        events::emit("vouch_revoked", json!({ "voucher": voucher, "account_id": account_id }));
+    }
+
+    pub fn vouch_count(&self, account_id: AccountId) -> u32 {
+        self.vouches.get(&account_id).map_or(0, Vec::len) as u32
+    }
+
+    pub fn vouched_by(&self, account_id: AccountId) -> Vec<Vouch> {
+        self.vouches.get(&account_id).cloned().unwrap_or_default()
+    }
+
+    /// Trust out of `MAX_SCORE` the account receives through vouches. Each vouch passes on a share of the
+    /// voucher's standing, weighted by its stake: its own `score`, or if higher half the trust it receives in
+    /// turn, one hop further.
+    pub fn trust_score(&self, account_id: AccountId) -> u32 {
+        self.compute_trust(&account_id, env::block_timestamp(), TRUST_DEPTH)
+    }
+
+    pub fn set_vouch_config(&mut self, config: VouchConfig) {
+        self.assert_owner();
+        require!(config.min_score <= MAX_SCORE, format!("min_score must be at most {}", MAX_SCORE));
+        self.vouch_config = config;
+    }
+
+    pub fn get_vouch_config(&self) -> VouchConfig {
+        self.vouch_config.clone()
+    }
+}
+
+impl Contract {
+    fn compute_trust(&self, account_id: &AccountId, now: u64, depth: u32) -> u32 {
+        if self.bans.contains_key(account_id) {
+            return 0;
+        }
+        let full_stake = self.vouch_config.full_stake.0;
+        let trust: u32 = self.vouches.get(account_id).into_iter().flatten()
+            .map(|vouch| {
+                let mut standing = self.compute_score(&vouch.voucher, now);
+                if depth > 0 {
+                    standing = standing.max(self.compute_trust(&vouch.voucher, now, depth - 1) / 2);
+                }
+                let stake_pct = (vouch.stake.0.min(full_stake) * 100).checked_div(full_stake).map_or(100, |pct| pct as u32);
+                standing * VOUCH_SHARE_PCT / 100 * stake_pct / 100
+            })
+            .sum();
+        trust.min(MAX_SCORE)
+    }
+}
@@ -0,0 +1,75 @@
+use near_sdk::json_types::U64;
+use near_sdk::serde_json::json;
+use near_sdk::{env, near, AccountId};
+
+use crate::{events, messages, Contract, ContractError, ContractExt, RequestBound, WorldId, WorldIdProof, PAUSE_VERIFY_WORLD_ID};
+
+const WORLD_ID_LEVELS: [&str; 2] = ["orb", "device"];
+
+// World ID proofs are checked by the verifier backend for now, which attests the fields the semaphore proof
+// commits to. verifying the proof on chain later only changes how `proof` is trusted, the nullifier index
+// keeping one World ID to one account stays as it is.
+#[near]
+impl Contract {
+    /// Stores a World ID uniqueness stamp for the signer. The verifier signs
+    /// `"world_id,<account_id>,<nullifier_hash>,<merkle_root>,<verification_level>,<bound>"`, hashes in lowercase
+    /// hex, after checking the proof. A nullifier backing another account is rejected; verifying again replaces
+    /// the account's earlier World ID and frees its nullifier.
+    #[payable]
+    #[handle_result]
+    pub fn verify_world_id(&mut self, proof: WorldIdProof, signature: Vec<u8>, max_block_height: Option<u64>, valid_until_timestamp_ns: Option<U64>) -> Result<(), ContractError> {
+        self.ensure_not_paused(PAUSE_VERIFY_WORLD_ID)?;
+        self.ensure_deposit(0)?;
+        let bound = RequestBound::new(max_block_height, valid_until_timestamp_ns)?;
+        bound.ensure_valid()?;
+        let account_id = self.acting_account()?;
+        self.ensure_not_banned(&account_id)?;
+        self.ensure_within_rate_limit(&account_id)?;
+        let proof = WorldIdProof {
+            nullifier_hash: normalize_field(&proof.nullifier_hash)?,
+            merkle_root: normalize_field(&proof.merkle_root)?,
+            verification_level: proof.verification_level,
+        };
+        if !WORLD_ID_LEVELS.contains(&proof.verification_level.as_str()) {
+            return Err(ContractError::InvalidWorldIdProof);
+        }
+        let message = messages::world_id_message(&account_id, &proof, bound);
+        self.verify_signature(&message, &signature)?;
+        if self.world_id_nullifiers.get(&proof.nullifier_hash).is_some_and(|owner| *owner != account_id) {
+            return Err(ContractError::WorldIdAlreadyUsed);
+        }
+        self.flush_collections();
+        let usage_before = env::storage_usage();
+        if let Some(previous) = self.world_ids.get(&account_id) {
+            if previous.nullifier_hash != proof.nullifier_hash {
+                self.world_id_nullifiers.remove(&previous.nullifier_hash);
+            }
+        }
+        events::emit("world_id_verified", json!({ "account_id": account_id, "verification_level": proof.verification_level }));
+        self.world_id_nullifiers.insert(proof.nullifier_hash.clone(), account_id.clone());
+        let world_id = WorldId { nullifier_hash: proof.nullifier_hash, verification_level: proof.verification_level, issued_at: env::block_timestamp().into() };
+        self.world_ids.insert(account_id.clone(), world_id);
+        self.accounts.insert(account_id.clone());
+        self.record_issuance(&account_id);
+        self.settle_storage(&account_id, usage_before)?;
+        Ok(())
+    }
+
+    pub fn get_world_id(&self, account_id: AccountId) -> Option<WorldId> {
+        self.world_ids.get(&account_id).cloned()
+    }
+
+    /// The account a nullifier hash backs, for checking a World ID before submitting it.
+    pub fn world_id_owner(&self, nullifier_hash: String) -> Option<AccountId> {
+        normalize_field(&nullifier_hash).ok().and_then(|nullifier_hash| self.world_id_nullifiers.get(&nullifier_hash).cloned())
+    }
+}
+
+// lowercases a `0x`-prefixed 32 byte field element, so one nullifier can't be submitted under two spellings
+fn normalize_field(value: &str) -> Result<String, ContractError> {
+    let hex = value.strip_prefix("0x").ok_or(ContractError::InvalidWorldIdProof)?;
+    if hex.len() != 64 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(ContractError::InvalidWorldIdProof);
+    }
+    Ok(format!("0x{}", hex.to_ascii_lowercase()))
+}
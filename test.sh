@@ -3,7 +3,7 @@
 # unit testing
 cargo test
 
-# sandbox testing
+# sandbox testing, including the gas benchmarks
 ./build.sh
 cd sandbox-rs
-cargo run --example sandbox "../target/wasm32-unknown-unknown/release/hello_near.wasm"
\ No newline at end of file
+cargo test -- --nocapture